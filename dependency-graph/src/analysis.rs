@@ -0,0 +1,82 @@
+use alloc::vec::Vec;
+
+use crate::{DependencyGraph, Node};
+
+/// Figures describing the shape of a [`DependencyGraph`], as returned by [`DependencyGraph::analysis`].
+/// Useful for estimating how long a traversal will take before starting it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    /// Number of steps in the longest chain of dependencies. No matter how many workers are available,
+    /// a full traversal takes at least this many steps' worth of time.
+    pub longest_chain: usize,
+    /// Number of steps in each layer, in the same order as [`DependencyGraph::into_layers`] would produce them.
+    pub layer_widths: Vec<usize>,
+    /// Width of the widest layer, meaning the most steps which can ever be processed at the same time.
+    /// Beyond this number, adding workers makes no difference.
+    pub max_parallelism: usize,
+}
+
+impl Analysis {
+    /// Number of rounds needed to process every layer using `workers` workers, assuming
+    /// every step takes the same amount of time, and layers are processed one at a time.
+    ///
+    /// # Panics
+    /// If `workers` is zero.
+    pub fn rounds(&self, workers: usize) -> usize {
+        assert!(workers > 0, "at least one worker is required");
+
+        self.layer_widths
+            .iter()
+            .map(|width| width.div_ceil(workers))
+            .sum()
+    }
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Analyses the shape of the graph, see [`Analysis`]. Steps which are part of (or depend on) a cycle
+    /// are left out, since they are never visited.
+    pub fn analysis(&self) -> Analysis {
+        let mut layer_widths = Vec::new();
+
+        for depth in self.depths().into_values() {
+            if layer_widths.len() <= depth {
+                layer_widths.resize(depth + 1, 0);
+            }
+            layer_widths[depth] += 1;
+        }
+
+        Analysis {
+            longest_chain: layer_widths.len(),
+            max_parallelism: layer_widths.iter().copied().max().unwrap_or(0),
+            layer_widths,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::build_test_graph;
+    use crate::DependencyGraph;
+
+    #[test]
+    fn test_analysis() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+
+        let analysis = graph.analysis();
+        assert_eq!(analysis.longest_chain, 3);
+        assert_eq!(analysis.layer_widths, vec![4, 2, 2]);
+        assert_eq!(analysis.max_parallelism, 4);
+
+        let layers: Vec<_> = graph.into_layers().map(|layer| layer.len()).collect();
+        assert_eq!(layers, analysis.layer_widths);
+
+        assert_eq!(analysis.rounds(1), 8);
+        assert_eq!(analysis.rounds(2), 4);
+        assert_eq!(analysis.rounds(4), 3);
+        assert_eq!(analysis.rounds(16), 3);
+    }
+}
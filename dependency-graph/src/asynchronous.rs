@@ -0,0 +1,197 @@
+use std::{
+    future::Future,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use futures::stream::{FuturesUnordered, StreamExt};
+
+use crate::{schedule::Schedule, Completion, DependencyGraph, Node, Step};
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Visits every step of the graph by calling `f` and awaiting the returned future, running up to
+    /// `concurrency` futures at the same time. A step is only dispatched once the futures of all its
+    /// dependencies have completed, while independent steps run concurrently.
+    ///
+    /// All futures are driven by the returned future itself, so it works with any executor, including Tokio,
+    /// and the futures are free to borrow from the graph. Execution stops at the first error, in which case
+    /// any futures still running are dropped.
+    pub async fn execute_async<F, Fut, E>(&self, concurrency: usize, f: F) -> Result<(), E>
+    where
+        F: FnMut(Step<'a, N>) -> Fut,
+        Fut: Future<Output = Result<(), E>>,
+    {
+        self.execute_async_cancellable(concurrency, &AtomicBool::new(false), f)
+            .await
+            .map(|_| ())
+    }
+
+    /// Same as [`DependencyGraph::execute_async`], but once `cancel` is set, for instance by a Ctrl-C handler,
+    /// no new steps are dispatched. Futures already running are driven to completion, after which the steps
+    /// completed so far are returned.
+    pub async fn execute_async_cancellable<F, Fut, E>(
+        &self,
+        concurrency: usize,
+        cancel: &AtomicBool,
+        mut f: F,
+    ) -> Result<Completion<'a, N>, E>
+    where
+        F: FnMut(Step<'a, N>) -> Fut,
+        Fut: Future<Output = Result<(), E>>,
+    {
+        let concurrency = concurrency.max(1);
+        let mut schedule = Schedule::new(self);
+        // Only the futures which were woken are polled again, so each wake costs the same no matter how
+        // many steps are in flight.
+        let mut running = FuturesUnordered::new();
+        let mut completed = Vec::new();
+
+        loop {
+            while running.len() < concurrency && !cancel.load(Ordering::SeqCst) {
+                match schedule.pop() {
+                    Some(index) => {
+                        let future = f(schedule.step(index));
+                        running.push(async move { (index, future.await) });
+                    }
+                    None => break,
+                }
+            }
+
+            match running.next().await {
+                Some((index, result)) => {
+                    result?;
+                    completed.push(schedule.step(index));
+                    schedule.complete(index);
+                }
+                None => {
+                    // Anything still ready at this point was held back by the cancellation.
+                    return Ok(Completion {
+                        completed,
+                        cancelled: !schedule.is_empty(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+        task::{Context, Poll, Wake, Waker},
+        thread::Thread,
+    };
+
+    use crate::tests::build_test_graph;
+    use crate::{DependencyGraph, Step};
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    /// Future which yields once before completing, forcing other futures to be polled in between.
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+
+        fn poll(mut self: std::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    #[test]
+    fn test_execute_async_respects_dependencies() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+        let finished = Mutex::new(Vec::new());
+
+        block_on(graph.execute_async(4, |step| {
+            let finished = &finished;
+            async move {
+                YieldOnce(false).await;
+                let name = match step {
+                    Step::Resolved(package) => {
+                        // Every dependency must have finished before we start.
+                        for dependency in &package.dependencies {
+                            assert!(finished.lock().unwrap().contains(&dependency.name));
+                        }
+                        package.name
+                    }
+                    Step::Unresolved(dependency) => dependency.name,
+                };
+                finished.lock().unwrap().push(name);
+                Ok::<(), ()>(())
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(finished.lock().unwrap().len(), packages.len() + 2);
+    }
+
+    #[test]
+    fn test_execute_async_stops_on_error() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+
+        let result = block_on(graph.execute_async(1, |step| async move {
+            match step {
+                Step::Unresolved(dependency) => Err(dependency.name),
+                Step::Resolved(_) => Ok(()),
+            }
+        }));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_execute_async_cancellable() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+        let cancel = AtomicBool::new(false);
+
+        let completion = block_on(graph.execute_async_cancellable(2, &cancel, |_| {
+            let cancel = &cancel;
+            async move {
+                cancel.store(true, Ordering::SeqCst);
+                YieldOnce(false).await;
+                Ok::<(), ()>(())
+            }
+        }))
+        .unwrap();
+
+        // Both steps dispatched before the cancellation are allowed to finish.
+        assert!(completion.cancelled);
+        assert_eq!(completion.completed.len(), 2);
+    }
+}
@@ -0,0 +1,166 @@
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use petgraph::stable_graph::NodeIndex;
+
+use crate::{collections::HashSet, schedule::Schedule, DependencyGraph, Node, Step};
+
+/// Record of which steps of a traversal have been completed, identified by a user-supplied key.
+/// Using the `serde` feature, a checkpoint can be persisted and used to resume a traversal later
+/// through [`DependencyGraph::resume`], for instance after the process has been restarted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkpoint<K: Eq + Hash> {
+    completed: HashSet<K>,
+}
+
+impl<K: Eq + Hash> Default for Checkpoint<K> {
+    fn default() -> Self {
+        Checkpoint {
+            completed: HashSet::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash> Checkpoint<K> {
+    /// An empty checkpoint, from which a traversal visits every step.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_completed(&self, key: &K) -> bool {
+        self.completed.contains(key)
+    }
+
+    /// Keys of all the completed steps, in no particular order.
+    pub fn completed(&self) -> impl Iterator<Item = &K> {
+        self.completed.iter()
+    }
+}
+
+/// A traversal which skips steps already recorded in a [`Checkpoint`], see [`DependencyGraph::resume`].
+pub struct Traversal<'g, 'a, N: Node, F, K: Eq + Hash> {
+    schedule: Schedule<'g, 'a, N>,
+    key: F,
+    checkpoint: Checkpoint<K>,
+    /// Steps handed out by [`Traversal::next_step`], but not yet completed.
+    in_progress: Vec<NodeIndex>,
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Starts a traversal of the graph which records every step's `key` in a [`Checkpoint`] as it is completed.
+    /// Any steps already completed according to `checkpoint` are skipped, but still count as completed for
+    /// the purposes of the steps depending on them. Use [`Checkpoint::new`] to traverse the graph from scratch.
+    pub fn resume<F, K>(&self, checkpoint: Checkpoint<K>, key: F) -> Traversal<'_, 'a, N, F, K>
+    where
+        F: FnMut(&Step<'a, N>) -> K,
+        K: Eq + Hash,
+    {
+        Traversal {
+            schedule: Schedule::new(self),
+            key,
+            checkpoint,
+            in_progress: Vec::new(),
+        }
+    }
+}
+
+impl<'g, 'a, N, F, K> Traversal<'g, 'a, N, F, K>
+where
+    N: Node,
+    F: FnMut(&Step<'a, N>) -> K,
+    K: Eq + Hash,
+{
+    /// Returns the next step which is ready and not already completed. The step is considered in progress
+    /// until it is passed to [`Traversal::complete`]. Multiple steps can be in progress at the same time,
+    /// so `None` only means that nothing is ready *right now*, not necessarily that the traversal is done.
+    pub fn next_step(&mut self) -> Option<Step<'a, N>> {
+        while let Some(index) = self.schedule.pop() {
+            let step = self.schedule.step(index);
+
+            if self.checkpoint.is_completed(&(self.key)(&step)) {
+                self.schedule.complete(index);
+            } else {
+                self.in_progress.push(index);
+                return Some(step);
+            }
+        }
+
+        None
+    }
+
+    /// Marks a step returned by [`Traversal::next_step`] as completed, recording it in the checkpoint.
+    /// Steps which aren't in progress are ignored.
+    pub fn complete(&mut self, step: Step<'a, N>) {
+        let schedule = &self.schedule;
+        let position = self
+            .in_progress
+            .iter()
+            .position(|index| schedule.step(*index).ptr_eq(&step));
+
+        if let Some(position) = position {
+            let index = self.in_progress.swap_remove(position);
+            self.checkpoint.completed.insert((self.key)(&step));
+            self.schedule.complete(index);
+        }
+    }
+
+    /// The current state of the traversal, which can be used to resume it later.
+    /// Steps in progress are not part of the checkpoint.
+    pub fn checkpoint(&self) -> &Checkpoint<K> {
+        &self.checkpoint
+    }
+
+    pub fn into_checkpoint(self) -> Checkpoint<K> {
+        self.checkpoint
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{build_test_graph, Package};
+    use crate::{Checkpoint, DependencyGraph, Step};
+
+    fn name(step: &Step<'_, Package>) -> &'static str {
+        match step {
+            Step::Resolved(package) => package.name,
+            Step::Unresolved(dependency) => dependency.name,
+        }
+    }
+
+    #[test]
+    fn test_resume_skips_completed() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+
+        // Complete the first three steps, then "crash".
+        let mut traversal = graph.resume(Checkpoint::new(), name);
+        let mut first_run = Vec::new();
+        for _ in 0..3 {
+            let step = traversal.next_step().unwrap();
+            first_run.push(name(&step));
+            traversal.complete(step);
+        }
+        let started = traversal.next_step().unwrap();
+        let checkpoint = traversal.into_checkpoint();
+        assert!(!checkpoint.is_completed(&name(&started)));
+
+        let mut traversal = graph.resume(checkpoint, name);
+        let mut second_run = Vec::new();
+        while let Some(step) = traversal.next_step() {
+            second_run.push(name(&step));
+            traversal.complete(step);
+        }
+
+        assert!(second_run.contains(&name(&started)));
+        assert!(first_run.iter().all(|name| !second_run.contains(name)));
+        assert_eq!(first_run.len() + second_run.len(), graph.node_count());
+        assert_eq!(
+            traversal.checkpoint().completed().count(),
+            graph.node_count()
+        );
+    }
+}
@@ -0,0 +1,5 @@
+// Hash based collections from `std` when it's available, and from `hashbrown` otherwise, which has the same api.
+#[cfg(not(feature = "std"))]
+pub(crate) use hashbrown::{HashMap, HashSet};
+#[cfg(feature = "std")]
+pub(crate) use std::collections::{HashMap, HashSet};
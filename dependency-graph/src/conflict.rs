@@ -0,0 +1,178 @@
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Display};
+
+use crate::{scan, skeleton_with, DependencyGraph, Node};
+
+/// A dependency which is matched by more than one node.
+pub struct Conflict<'a, N: Node> {
+    /// The node declaring the dependency.
+    pub dependent: &'a N,
+    /// The ambiguous dependency itself.
+    pub dependency: &'a N::DependencyType,
+    /// All the nodes matching the dependency, in the order they were provided.
+    pub candidates: Vec<&'a N>,
+}
+
+impl<'a, N> Debug for Conflict<'a, N>
+where
+    N: Node + Debug,
+    N::DependencyType: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Conflict")
+            .field("dependent", &self.dependent)
+            .field("dependency", &self.dependency)
+            .field("candidates", &self.candidates)
+            .finish()
+    }
+}
+
+/// Returned by [`DependencyGraph::try_from_unambiguous`] if one or more dependencies
+/// could be resolved by multiple nodes.
+pub struct ResolutionConflict<'a, N: Node> {
+    pub conflicts: Vec<Conflict<'a, N>>,
+}
+
+impl<'a, N> Debug for ResolutionConflict<'a, N>
+where
+    N: Node + Debug,
+    N::DependencyType: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResolutionConflict")
+            .field("conflicts", &self.conflicts)
+            .finish()
+    }
+}
+
+impl<'a, N: Node> Display for ResolutionConflict<'a, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} dependencies are matched by more than one node",
+            self.conflicts.len()
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, N> std::error::Error for ResolutionConflict<'a, N>
+where
+    N: Node + Debug,
+    N::DependencyType: Debug,
+{
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Same as `DependencyGraph::from`, but fails with a [`ResolutionConflict`] if any dependency
+    /// is matched by more than one node, rather than silently picking the first match.
+    pub fn try_from_unambiguous(nodes: &'a [N]) -> Result<Self, ResolutionConflict<'a, N>> {
+        let mut conflicts = Vec::new();
+
+        let skeleton = skeleton_with(nodes, scan(nodes), |dependent, dependency, candidates| {
+            conflicts.push(Conflict {
+                dependent: &nodes[dependent],
+                dependency: &nodes[dependent].dependencies()[dependency],
+                candidates: candidates
+                    .iter()
+                    .map(|candidate| &nodes[*candidate])
+                    .collect(),
+            });
+            0
+        });
+
+        if conflicts.is_empty() {
+            Ok(Self::from_skeleton(nodes, &skeleton))
+        } else {
+            Err(ResolutionConflict { conflicts })
+        }
+    }
+
+    /// Same as `DependencyGraph::from`, but if a dependency is matched by more than one node,
+    /// `select` is called with the dependency and the matching nodes, and must return the index
+    /// of the candidate to use. To pick the highest version of a package for instance:
+    /// ```ignore
+    /// DependencyGraph::from_nodes_with(&packages[..], |_, candidates| {
+    ///     (0..candidates.len())
+    ///         .max_by_key(|index| &candidates[*index].version)
+    ///         .unwrap()
+    /// })
+    /// ```
+    /// # Panics
+    /// If `select` returns an index which is out of bounds for the candidates.
+    pub fn from_nodes_with<F>(nodes: &'a [N], mut select: F) -> Self
+    where
+        F: FnMut(&'a N::DependencyType, &[&'a N]) -> usize,
+    {
+        let mut matches = Vec::new();
+
+        let skeleton = skeleton_with(nodes, scan(nodes), |dependent, dependency, candidates| {
+            matches.clear();
+            matches.extend(candidates.iter().map(|candidate| &nodes[*candidate]));
+            select(&nodes[dependent].dependencies()[dependency], &matches)
+        });
+
+        Self::from_skeleton(nodes, &skeleton)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{build_test_graph, package};
+    use crate::{DependencyGraph, Step};
+
+    fn conflicting() -> Vec<crate::tests::Package> {
+        vec![
+            package("base", "1.0.0", &[]),
+            package("base", "1.1.0", &[]),
+            package("derived", "1.0.0", &[("base", ">=1.0.0")]),
+            package("pinned", "1.0.0", &[("base", "=1.0.0")]),
+        ]
+    }
+
+    #[test]
+    fn test_unambiguous() {
+        let packages = build_test_graph();
+        assert!(DependencyGraph::try_from_unambiguous(&packages[..]).is_ok());
+    }
+
+    #[test]
+    fn test_conflict_detection() {
+        let packages = conflicting();
+
+        let error = match DependencyGraph::try_from_unambiguous(&packages[..]) {
+            Ok(_) => panic!("conflict was not detected"),
+            Err(error) => error,
+        };
+
+        assert_eq!(error.conflicts.len(), 1);
+        assert_eq!(error.conflicts[0].dependent.name, "derived");
+        assert_eq!(error.conflicts[0].candidates.len(), 2);
+    }
+
+    #[test]
+    fn test_custom_selection() {
+        let packages = conflicting();
+
+        let graph = DependencyGraph::from_nodes_with(&packages[..], |_, candidates| {
+            (0..candidates.len())
+                .max_by_key(|index| &candidates[*index].version)
+                .unwrap()
+        });
+
+        // `derived` now resolves to base@1.1.0, while `pinned` can only use base@1.0.0.
+        let order: Vec<_> = graph
+            .filter_map(|step| match step {
+                Step::Resolved(package) => Some(package.to_string()),
+                Step::Unresolved(_) => None,
+            })
+            .collect();
+
+        let position = |name: &str| order.iter().position(|p| p == name).unwrap();
+        assert!(position("base@1.1.0") < position("derived@1.0.0"));
+        assert!(position("base@1.0.0") < position("pinned@1.0.0"));
+    }
+}
@@ -0,0 +1,161 @@
+use alloc::{collections::BinaryHeap, vec::Vec};
+
+use petgraph::{stable_graph::NodeIndex, Direction};
+
+use crate::{collections::HashMap, schedule::Schedule, DependencyGraph, Node, Step};
+
+/// Non-consuming iterator which visits the step with the longest remaining critical path first,
+/// see [`DependencyGraph::critical_path_first`].
+pub struct CriticalPathIter<'g, 'a, N: Node> {
+    schedule: Schedule<'g, 'a, N>,
+    priorities: HashMap<NodeIndex, u64>,
+    /// Ready steps, highest priority first. Ties are broken by node index.
+    ready: BinaryHeap<(u64, NodeIndex)>,
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Like [`DependencyGraph::iter`], but whenever multiple steps are ready to be visited, the one
+    /// heading the most expensive chain of dependents goes first, as measured by `cost`. Starting the
+    /// longest chains as early as possible lets parallel builds finish sooner.
+    ///
+    /// Steps which are part of (or depend on) a cycle are never visited.
+    pub fn critical_path_first<F>(&self, cost: F) -> CriticalPathIter<'_, 'a, N>
+    where
+        F: FnMut(&Step<'a, N>) -> u64,
+    {
+        let mut iter = CriticalPathIter {
+            schedule: Schedule::new(self),
+            priorities: self.priorities(cost),
+            ready: BinaryHeap::new(),
+        };
+
+        iter.enqueue_ready();
+        iter
+    }
+
+    /// The most expensive chain of dependencies in the graph as measured by `cost`, starting with the
+    /// step which must be visited first. No schedule can finish in less time than the total cost of
+    /// this path, no matter how many steps are visited in parallel.
+    pub fn critical_path<F>(&self, cost: F) -> Vec<Step<'a, N>>
+    where
+        F: FnMut(&Step<'a, N>) -> u64,
+    {
+        let priorities = self.priorities(cost);
+        let highest = |indices: &mut dyn Iterator<Item = NodeIndex>| {
+            indices
+                .filter_map(|index| Some((*priorities.get(&index)?, index)))
+                .max()
+                .map(|(_, index)| index)
+        };
+
+        let mut path = Vec::new();
+        let mut next = highest(&mut self.graph.node_indices());
+
+        while let Some(index) = next {
+            path.push(self.graph[index]);
+            next = highest(&mut self.graph.neighbors_directed(index, Direction::Incoming));
+        }
+
+        path
+    }
+
+    /// Computes the cost of each step plus the cost of its most expensive chain of dependents.
+    fn priorities<F>(&self, mut cost: F) -> HashMap<NodeIndex, u64>
+    where
+        F: FnMut(&Step<'a, N>) -> u64,
+    {
+        let mut schedule = Schedule::new(self);
+        let mut order = Vec::new();
+
+        while let Some(index) = schedule.pop() {
+            order.push(index);
+            schedule.complete(index);
+        }
+
+        let mut priorities = HashMap::new();
+        for index in order.into_iter().rev() {
+            let dependents = self
+                .graph
+                .neighbors_directed(index, Direction::Incoming)
+                .map(|dependent| priorities[&dependent])
+                .max()
+                .unwrap_or(0);
+
+            priorities.insert(index, cost(&self.graph[index]) + dependents);
+        }
+
+        priorities
+    }
+}
+
+impl<'g, 'a, N> CriticalPathIter<'g, 'a, N>
+where
+    N: Node,
+{
+    fn enqueue_ready(&mut self) {
+        while let Some(index) = self.schedule.pop() {
+            self.ready.push((self.priorities[&index], index));
+        }
+    }
+}
+
+impl<'g, 'a, N> Iterator for CriticalPathIter<'g, 'a, N>
+where
+    N: Node,
+{
+    type Item = Step<'a, N>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, index) = self.ready.pop()?;
+        self.schedule.complete(index);
+        self.enqueue_ready();
+        Some(self.schedule.step(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{build_test_graph, Package};
+    use crate::{DependencyGraph, Step};
+
+    fn name(step: &Step<'_, Package>) -> &'static str {
+        match step {
+            Step::Resolved(package) => package.name,
+            Step::Unresolved(dependency) => dependency.name,
+        }
+    }
+
+    fn cost(step: &Step<'_, Package>) -> u64 {
+        match name(step) {
+            "converged" => 2,
+            _ if step.is_resolved() => 1,
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn test_critical_path() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+
+        let path: Vec<_> = graph.critical_path(cost).iter().map(name).collect();
+        assert_eq!(path, vec!["base", "derived", "converged"]);
+    }
+
+    #[test]
+    fn test_critical_path_first() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+
+        let order: Vec<_> = graph
+            .critical_path_first(cost)
+            .map(|step| name(&step))
+            .collect();
+
+        assert_eq!(order.len(), packages.len() + 2);
+        assert_eq!(order[..3], ["base", "derived", "converged"]);
+    }
+}
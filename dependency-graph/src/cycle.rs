@@ -0,0 +1,219 @@
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Display};
+
+use petgraph::{algo::tarjan_scc, visit::EdgeRef, Direction};
+
+use crate::{DependencyGraph, Node, Step};
+
+/// A single cycle within a [`DependencyGraph`]. Every node in `nodes` (transitively) depends on
+/// every other node in the cycle, which means none of them can ever be resolved.
+pub struct Cycle<'a, N: Node> {
+    /// The nodes which make up the cycle.
+    pub nodes: Vec<&'a N>,
+    /// The dependencies between the nodes of the cycle, as `(dependent, dependency, dependee)` tuples.
+    pub edges: Vec<(&'a N, &'a N::DependencyType, &'a N)>,
+}
+
+impl<'a, N> Debug for Cycle<'a, N>
+where
+    N: Node + Debug,
+    N::DependencyType: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cycle")
+            .field("nodes", &self.nodes)
+            .field("edges", &self.edges)
+            .finish()
+    }
+}
+
+/// Returned by [`DependencyGraph::try_from_nodes`] if the nodes contain one or more
+/// dependency cycles.
+pub struct CycleError<'a, N: Node> {
+    pub cycles: Vec<Cycle<'a, N>>,
+}
+
+impl<'a, N> Debug for CycleError<'a, N>
+where
+    N: Node + Debug,
+    N::DependencyType: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CycleError")
+            .field("cycles", &self.cycles)
+            .finish()
+    }
+}
+
+impl<'a, N: Node> Display for CycleError<'a, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "dependency graph contains {} cycle(s) between a total of {} node(s)",
+            self.cycles.len(),
+            self.cycles
+                .iter()
+                .map(|cycle| cycle.nodes.len())
+                .sum::<usize>()
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, N> std::error::Error for CycleError<'a, N>
+where
+    N: Node + Debug,
+    N::DependencyType: Debug,
+{
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Same as `DependencyGraph::from`, but fails with a [`CycleError`] if the dependencies
+    /// between the nodes form one or more cycles, since iterating over such a graph would
+    /// silently skip every node involved in (or depending on) a cycle.
+    pub fn try_from_nodes(nodes: &'a [N]) -> Result<Self, CycleError<'a, N>> {
+        let graph = Self::from(nodes);
+        let cycles = graph.cycles();
+
+        if cycles.is_empty() {
+            Ok(graph)
+        } else {
+            Err(CycleError { cycles })
+        }
+    }
+
+    /// Groups the steps of the graph into strongly connected components, meaning every step within a
+    /// component (transitively) depends on every other step in it. Any component with more than one step
+    /// is a dependency cycle, while acyclic steps end up in components of their own.
+    ///
+    /// Components are returned in dependency order, so no component depends on a component after it.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<Step<'a, N>>> {
+        tarjan_scc(&self.graph)
+            .into_iter()
+            .map(|component| {
+                component
+                    .into_iter()
+                    .map(|index| self.graph[index])
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Finds all cycles within the graph using Tarjan's strongly connected components algorithm.
+    /// A component is a cycle if it contains more than one node, or if its only node depends on itself.
+    pub fn cycles(&self) -> Vec<Cycle<'a, N>> {
+        instrument!(span debug_span, "find_cycles", steps = self.graph.node_count());
+
+        let cycles: Vec<_> = tarjan_scc(&self.graph)
+            .into_iter()
+            .filter_map(|component| {
+                let edges: Vec<_> = component
+                    .iter()
+                    .flat_map(|index| self.graph.edges_directed(*index, Direction::Outgoing))
+                    .filter(|edge| component.contains(&edge.target()))
+                    .filter_map(|edge| {
+                        Some((
+                            self.graph[edge.source()].as_resolved()?,
+                            *edge.weight(),
+                            self.graph[edge.target()].as_resolved()?,
+                        ))
+                    })
+                    .collect();
+
+                if edges.is_empty() {
+                    return None;
+                }
+
+                Some(Cycle {
+                    nodes: component
+                        .iter()
+                        .filter_map(|index| self.graph[*index].as_resolved())
+                        .collect(),
+                    edges,
+                })
+            })
+            .collect();
+
+        instrument!(debug, cycles = cycles.len(), "found cycles");
+        cycles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{build_test_graph, package};
+    use crate::DependencyGraph;
+
+    #[test]
+    fn test_acyclic_graph() {
+        let packages = build_test_graph();
+        assert!(DependencyGraph::try_from_nodes(&packages[..]).is_ok());
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        let packages = [
+            package("base", "1.0.0", &[]),
+            package(
+                "left",
+                "1.0.0",
+                &[("base", ">=1.0.0"), ("right", ">=1.0.0")],
+            ),
+            package("right", "1.0.0", &[("left", ">=1.0.0")]),
+            package("narcissist", "1.0.0", &[("narcissist", ">=1.0.0")]),
+        ];
+
+        let error = match DependencyGraph::try_from_nodes(&packages[..]) {
+            Ok(_) => panic!("cycles were not detected"),
+            Err(error) => error,
+        };
+
+        let mut cycles: Vec<Vec<_>> = error
+            .cycles
+            .iter()
+            .map(|cycle| {
+                let mut names: Vec<_> = cycle.nodes.iter().map(|node| node.name).collect();
+                names.sort_unstable();
+                names
+            })
+            .collect();
+        cycles.sort();
+
+        assert_eq!(cycles, vec![vec!["left", "right"], vec!["narcissist"]]);
+        assert_eq!(error.cycles.iter().map(|c| c.edges.len()).sum::<usize>(), 3);
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        let packages = [
+            package("base", "1.0.0", &[]),
+            package("a", "1.0.0", &[("b", ">=1.0.0")]),
+            package("b", "1.0.0", &[("c", ">=1.0.0")]),
+            package("c", "1.0.0", &[("a", ">=1.0.0"), ("base", ">=1.0.0")]),
+            package("top", "1.0.0", &[("a", ">=1.0.0")]),
+        ];
+
+        let graph = DependencyGraph::from(&packages[..]);
+        let components: Vec<Vec<_>> = graph
+            .strongly_connected_components()
+            .into_iter()
+            .map(|component| {
+                let mut names: Vec<_> = component
+                    .iter()
+                    .map(|step| step.as_resolved().unwrap().name)
+                    .collect();
+                names.sort_unstable();
+                names
+            })
+            .collect();
+
+        assert_eq!(
+            components,
+            vec![vec!["base"], vec!["a", "b", "c"], vec!["top"]]
+        );
+        assert_eq!(graph.cycles().len(), 1);
+    }
+}
@@ -0,0 +1,242 @@
+use alloc::vec::Vec;
+use core::{
+    fmt::{self, Debug},
+    hash::Hash,
+};
+
+use petgraph::Direction;
+
+use crate::{
+    collections::{HashMap, HashSet},
+    DependencyGraph, Node, Step,
+};
+
+/// Differences between two graphs, see [`DependencyGraph::diff`].
+pub struct GraphDiff<'a, N: Node, K> {
+    /// Steps which only exist in the other graph.
+    pub added: Vec<Step<'a, N>>,
+    /// Steps which only exist in this graph.
+    pub removed: Vec<Step<'a, N>>,
+    /// Steps which exist in both graphs, but are not equal, as `(ours, theirs)` pairs.
+    pub changed: Vec<(Step<'a, N>, Step<'a, N>)>,
+    /// Dependencies which only exist in the other graph, as `(dependent, dependency)` pairs of identities.
+    pub added_edges: Vec<(K, K)>,
+    /// Dependencies which only exist in this graph, as `(dependent, dependency)` pairs of identities.
+    pub removed_edges: Vec<(K, K)>,
+}
+
+impl<'a, N: Node, K> GraphDiff<'a, N, K> {
+    /// True if the two graphs were identical.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+    }
+}
+
+impl<'a, N, K> Debug for GraphDiff<'a, N, K>
+where
+    N: Node + Debug,
+    N::DependencyType: Debug,
+    K: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GraphDiff")
+            .field("added", &self.added)
+            .field("removed", &self.removed)
+            .field("changed", &self.changed)
+            .field("added_edges", &self.added_edges)
+            .field("removed_edges", &self.removed_edges)
+            .finish()
+    }
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node + PartialEq,
+    N::DependencyType: PartialEq,
+{
+    /// Compares this graph to `other`, matching up steps between the two using the `identity` function.
+    /// Steps with the same identity in both graphs are reported as changed if they are not equal, so
+    /// when comparing two lockfiles for instance, using the package name as identity reports version
+    /// bumps as changes, rather than as a removal and an addition.
+    ///
+    /// Identities are expected to be unique within each graph. If they are not, only the last step
+    /// with a given identity is taken into account.
+    pub fn diff<F, K>(&self, other: &DependencyGraph<'a, N>, mut identity: F) -> GraphDiff<'a, N, K>
+    where
+        F: FnMut(&Step<'a, N>) -> K,
+        K: Eq + Hash + Clone,
+    {
+        let (our_order, ours) = self.identities(&mut identity);
+        let (their_order, theirs) = other.identities(&mut identity);
+
+        let mut diff = GraphDiff {
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+            added_edges: Vec::new(),
+            removed_edges: Vec::new(),
+        };
+
+        for key in &our_order {
+            let step = ours[key];
+            match theirs.get(key) {
+                None => diff.removed.push(step),
+                Some(other) if !same(&step, other) => diff.changed.push((step, *other)),
+                Some(_) => {}
+            }
+        }
+
+        for key in &their_order {
+            if !ours.contains_key(key) {
+                diff.added.push(theirs[key]);
+            }
+        }
+
+        let our_edges = self.edge_identities(&mut identity);
+        let their_edges = other.edge_identities(&mut identity);
+        diff.removed_edges = difference(&our_edges, &their_edges);
+        diff.added_edges = difference(&their_edges, &our_edges);
+
+        diff
+    }
+
+    /// Identities of every step in the graph, in index order, along with the step they identify.
+    /// Later duplicates replace earlier ones.
+    fn identities<F, K>(&self, identity: &mut F) -> (Vec<K>, HashMap<K, Step<'a, N>>)
+    where
+        F: FnMut(&Step<'a, N>) -> K,
+        K: Eq + Hash + Clone,
+    {
+        let mut order = Vec::new();
+        let mut steps = HashMap::new();
+
+        for index in self.graph.node_indices() {
+            let step = self.graph[index];
+            let key = identity(&step);
+
+            if steps.insert(key.clone(), step).is_none() {
+                order.push(key);
+            }
+        }
+
+        (order, steps)
+    }
+
+    /// Identities of both sides of every dependency in the graph, without duplicates.
+    fn edge_identities<F, K>(&self, identity: &mut F) -> Vec<(K, K)>
+    where
+        F: FnMut(&Step<'a, N>) -> K,
+        K: Eq + Hash + Clone,
+    {
+        let mut edges = Vec::new();
+        let mut seen = HashSet::new();
+
+        for index in self.graph.node_indices() {
+            for dependency in self.graph.neighbors_directed(index, Direction::Outgoing) {
+                let edge = (
+                    identity(&self.graph[index]),
+                    identity(&self.graph[dependency]),
+                );
+                if seen.insert(edge.clone()) {
+                    edges.push(edge);
+                }
+            }
+        }
+
+        edges
+    }
+}
+
+/// Edges in `edges` which are not in `other`, preserving their order.
+fn difference<K: Eq + Hash + Clone>(edges: &[(K, K)], other: &[(K, K)]) -> Vec<(K, K)> {
+    let other: HashSet<_> = other.iter().collect();
+    edges
+        .iter()
+        .filter(|edge| !other.contains(edge))
+        .cloned()
+        .collect()
+}
+
+fn same<N>(ours: &Step<'_, N>, theirs: &Step<'_, N>) -> bool
+where
+    N: Node + PartialEq,
+    N::DependencyType: PartialEq,
+{
+    match (ours, theirs) {
+        (Step::Resolved(ours), Step::Resolved(theirs)) => ours == theirs,
+        (Step::Unresolved(ours), Step::Unresolved(theirs)) => ours == theirs,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{package, Package};
+    use crate::{DependencyGraph, Step};
+
+    fn name(step: &Step<'_, Package>) -> &'static str {
+        match step {
+            Step::Resolved(package) => package.name,
+            Step::Unresolved(dependency) => dependency.name,
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = [
+            package("base", "1.0.0", &[]),
+            package("derived", "1.0.0", &[("base", ">=1.0.0")]),
+            package("legacy", "1.0.0", &[("base", ">=1.0.0")]),
+        ];
+
+        let after = [
+            package("base", "1.1.0", &[]),
+            package(
+                "derived",
+                "1.0.0",
+                &[("base", ">=1.0.0"), ("remote", "=2.0.0")],
+            ),
+            package("new", "1.0.0", &[]),
+        ];
+
+        let before = DependencyGraph::from(&before[..]);
+        let after = DependencyGraph::from(&after[..]);
+
+        let diff = before.diff(&after, name);
+
+        assert_eq!(
+            diff.removed.iter().map(name).collect::<Vec<_>>(),
+            vec!["legacy"]
+        );
+        assert_eq!(
+            diff.added.iter().map(name).collect::<Vec<_>>(),
+            vec!["new", "remote"]
+        );
+        // derived is changed as well, since its own list of dependencies changed.
+        let changed: Vec<_> = diff
+            .changed
+            .iter()
+            .map(|(ours, theirs)| {
+                (
+                    ours.as_resolved().unwrap().to_string(),
+                    theirs.as_resolved().unwrap().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(changed.len(), 2);
+        assert_eq!(
+            changed[0],
+            ("base@1.0.0".to_string(), "base@1.1.0".to_string())
+        );
+        assert_eq!(diff.changed[1].0.as_resolved().unwrap().name, "derived");
+
+        assert_eq!(diff.removed_edges, vec![("legacy", "base")]);
+        assert_eq!(diff.added_edges, vec![("derived", "remote")]);
+
+        assert!(after.diff(&after, name).is_empty());
+    }
+}
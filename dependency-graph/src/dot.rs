@@ -0,0 +1,83 @@
+use alloc::string::{String, ToString};
+use core::fmt::{Display, Write};
+
+use petgraph::{visit::EdgeRef, Direction};
+
+use crate::{DependencyGraph, Node, Step};
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node + Display,
+    N::DependencyType: Display,
+{
+    /// Renders the graph in the Graphviz DOT format. Resolved nodes are labeled using their
+    /// [`Display`] implementation, while unresolved dependencies are drawn as dashed red boxes.
+    /// Edges point from a node to its dependency, and are labeled with the dependency itself.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph {\n");
+
+        for index in self.graph.node_indices() {
+            let _ = match self.graph[index] {
+                Step::Resolved(node) => {
+                    writeln!(dot, "    n{} [label=\"{}\"];", index.index(), escape(node))
+                }
+                Step::Unresolved(dependency) => writeln!(
+                    dot,
+                    "    n{} [label=\"{}\", shape=box, style=dashed, color=red];",
+                    index.index(),
+                    escape(dependency)
+                ),
+            };
+        }
+
+        for index in self.graph.node_indices() {
+            for edge in self.graph.edges_directed(index, Direction::Outgoing) {
+                let _ = writeln!(
+                    dot,
+                    "    n{} -> n{} [label=\"{}\"];",
+                    edge.source().index(),
+                    edge.target().index(),
+                    escape(edge.weight())
+                );
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Escapes a label for use within a double-quoted DOT string.
+pub(crate) fn escape<T: Display>(label: T) -> String {
+    label
+        .to_string()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::package;
+    use crate::DependencyGraph;
+
+    #[test]
+    fn test_to_dot() {
+        let packages = [
+            package("base", "1.0.0", &[]),
+            package(
+                "derived",
+                "1.0.0",
+                &[("base", ">=1.0.0"), ("remote", "=2.0.0")],
+            ),
+        ];
+
+        let dot = DependencyGraph::from(&packages[..]).to_dot();
+
+        assert!(dot.starts_with("digraph {\n"));
+        assert!(dot.contains("n0 [label=\"base@1.0.0\"];"));
+        assert!(dot.contains("n2 [label=\"remote =2.0.0\", shape=box, style=dashed, color=red];"));
+        assert!(dot.contains("n1 -> n0 [label=\"base >=1.0.0\"];"));
+        assert!(dot.contains("n1 -> n2 [label=\"remote =2.0.0\"];"));
+    }
+}
@@ -0,0 +1,111 @@
+use alloc::{vec, vec::Vec};
+use core::{
+    fmt::{self, Debug, Display},
+    hash::Hash,
+};
+
+use crate::{collections::HashMap, DependencyGraph, Node};
+
+/// Returned by [`DependencyGraph::try_from_distinct`] if multiple nodes share the same key.
+pub struct DuplicateNode<'a, N: Node> {
+    /// Groups of nodes sharing a key, in the order they were provided. Every group has at least two nodes.
+    pub duplicates: Vec<Vec<&'a N>>,
+}
+
+impl<'a, N> Debug for DuplicateNode<'a, N>
+where
+    N: Node + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DuplicateNode")
+            .field("duplicates", &self.duplicates)
+            .finish()
+    }
+}
+
+impl<'a, N: Node> Display for DuplicateNode<'a, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} nodes appear more than once", self.duplicates.len())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, N> std::error::Error for DuplicateNode<'a, N> where N: Node + Debug {}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Same as `DependencyGraph::from`, but fails with a [`DuplicateNode`] error if two or more nodes
+    /// produce the same `key`, rather than silently resolving dependencies to whichever comes first.
+    pub fn try_from_distinct<F, K>(nodes: &'a [N], key: F) -> Result<Self, DuplicateNode<'a, N>>
+    where
+        F: Fn(&'a N) -> K,
+        K: Eq + Hash,
+    {
+        let duplicates = duplicates(nodes, key);
+
+        if duplicates.is_empty() {
+            Ok(Self::from(nodes))
+        } else {
+            Err(DuplicateNode { duplicates })
+        }
+    }
+}
+
+/// Groups of nodes sharing the same `key`, in the order they were provided. Nodes with a unique key are left out.
+pub(crate) fn duplicates<'a, N, F, K>(nodes: &'a [N], key: F) -> Vec<Vec<&'a N>>
+where
+    F: Fn(&'a N) -> K,
+    K: Eq + Hash,
+{
+    let mut groups: HashMap<K, usize> = HashMap::new();
+    let mut nodes_by_key: Vec<Vec<&'a N>> = Vec::new();
+
+    for node in nodes {
+        match groups.get(&key(node)) {
+            Some(group) => nodes_by_key[*group].push(node),
+            None => {
+                groups.insert(key(node), nodes_by_key.len());
+                nodes_by_key.push(vec![node]);
+            }
+        }
+    }
+
+    nodes_by_key
+        .into_iter()
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{build_test_graph, package};
+    use crate::DependencyGraph;
+
+    #[test]
+    fn test_distinct() {
+        let packages = build_test_graph();
+        assert!(DependencyGraph::try_from_distinct(&packages[..], |package| package.name).is_ok());
+    }
+
+    #[test]
+    fn test_duplicate_detection() {
+        let packages = [
+            package("base", "1.0.0", &[]),
+            package("derived", "1.0.0", &[("base", ">=1.0.0")]),
+            package("base", "1.0.0", &[]),
+        ];
+
+        let error = match DependencyGraph::try_from_distinct(&packages[..], |package| {
+            (package.name, &package.version)
+        }) {
+            Ok(_) => panic!("duplicate was not detected"),
+            Err(error) => error,
+        };
+
+        assert_eq!(error.duplicates.len(), 1);
+        assert_eq!(error.duplicates[0].len(), 2);
+        assert!(std::ptr::eq(error.duplicates[0][1], &packages[2]));
+    }
+}
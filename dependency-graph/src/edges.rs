@@ -0,0 +1,107 @@
+use petgraph::{visit::EdgeRef, Direction};
+
+use crate::{DependencyGraph, Node, NodeHandle, Step};
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Same as `DependencyGraph::from`, but every edge carries the result of calling `metadata` with
+    /// the dependent node and its dependency, rather than just a reference to the dependency. Use this
+    /// to attach information such as whether a dependency is public, or where it was declared:
+    /// ```ignore
+    /// let graph = DependencyGraph::with_edge_metadata(&packages[..], |package, dependency| {
+    ///     (dependency, package.span_of(dependency))
+    /// });
+    /// ```
+    pub fn with_edge_metadata<F, E>(nodes: &'a [N], mut metadata: F) -> DependencyGraph<'a, N, E>
+    where
+        F: FnMut(&'a N, &'a N::DependencyType) -> E,
+    {
+        let graph = Self::from(nodes).graph;
+
+        let mapped = graph.map(
+            |_, step| *step,
+            |edge, dependency| {
+                // Only resolved nodes have dependencies, so the source is always resolved.
+                let (dependent, _) = graph.edge_endpoints(edge).unwrap();
+                metadata(graph[dependent].as_resolved().unwrap(), dependency)
+            },
+        );
+
+        DependencyGraph::from_graph(mapped)
+    }
+}
+
+impl<'a, N, E> DependencyGraph<'a, N, E>
+where
+    N: Node,
+{
+    /// Every edge in the graph as `(dependent, dependency, metadata)`.
+    pub fn edges(&self) -> impl Iterator<Item = (Step<'a, N>, Step<'a, N>, &E)> + '_ {
+        self.graph.node_indices().flat_map(move |index| {
+            self.graph
+                .edges_directed(index, Direction::Outgoing)
+                .map(move |edge| {
+                    (
+                        self.graph[edge.source()],
+                        self.graph[edge.target()],
+                        edge.weight(),
+                    )
+                })
+        })
+    }
+
+    /// The dependencies of the step referred to by `handle`, along with the metadata of the edge leading to them.
+    pub fn edges_of(&self, handle: NodeHandle) -> impl Iterator<Item = (Step<'a, N>, &E)> + '_ {
+        self.graph
+            .edges_directed(handle.0, Direction::Outgoing)
+            .map(move |edge| (self.graph[edge.target()], edge.weight()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{build_test_graph, package};
+    use crate::DependencyGraph;
+
+    #[test]
+    fn test_edge_metadata() {
+        let packages = [
+            package("base", "1.0.0", &[]),
+            package(
+                "derived",
+                "1.0.0",
+                &[("base", ">=1.0.0"), ("remote", "=2.0.0")],
+            ),
+        ];
+
+        let graph = DependencyGraph::with_edge_metadata(&packages[..], |package, dependency| {
+            format!("{} -> {}", package.name, dependency.name)
+        });
+
+        let mut edges: Vec<_> = graph.edges().map(|(_, _, label)| label.clone()).collect();
+        edges.sort_unstable();
+        assert_eq!(edges, vec!["derived -> base", "derived -> remote"]);
+
+        // Graphs with edge metadata are traversed just like any other.
+        assert_eq!(graph.count(), 3);
+    }
+
+    #[test]
+    fn test_edges_of() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+
+        let converged = graph.handle_of(&packages[3]).unwrap();
+        let mut dependencies: Vec<_> = graph
+            .edges_of(converged)
+            .map(|(step, dependency)| {
+                assert_eq!(step.as_resolved().unwrap().name, dependency.name);
+                dependency.name
+            })
+            .collect();
+        dependencies.sort_unstable();
+        assert_eq!(dependencies, vec!["base", "derived"]);
+    }
+}
@@ -0,0 +1,186 @@
+use alloc::vec::Vec;
+use core::{
+    fmt::{self, Debug},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use petgraph::Direction;
+
+use crate::{collections::HashSet, schedule::Schedule, DependencyGraph, Node, Step};
+
+/// Outcome of [`DependencyGraph::execute`].
+pub struct ExecutionReport<'a, N: Node, E> {
+    /// Steps which completed successfully, in the order they were executed.
+    pub completed: Vec<Step<'a, N>>,
+    /// Steps which failed, along with their errors.
+    pub failed: Vec<(Step<'a, N>, E)>,
+    /// Steps which were never executed, because one of their (transitive) dependencies failed.
+    pub skipped: Vec<Step<'a, N>>,
+    /// True if execution was cancelled before every step could be executed, see [`DependencyGraph::execute_cancellable`].
+    pub cancelled: bool,
+}
+
+impl<'a, N: Node, E> ExecutionReport<'a, N, E> {
+    /// True if no steps failed, and therefore none were skipped either.
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+impl<'a, N, E> Debug for ExecutionReport<'a, N, E>
+where
+    N: Node + Debug,
+    N::DependencyType: Debug,
+    E: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExecutionReport")
+            .field("completed", &self.completed)
+            .field("failed", &self.failed)
+            .field("skipped", &self.skipped)
+            .field("cancelled", &self.cancelled)
+            .finish()
+    }
+}
+
+/// Outcome of the cancellable parallel and asynchronous execution drivers, if no step failed.
+pub struct Completion<'a, N: Node> {
+    /// Steps which completed successfully, in the order they finished.
+    pub completed: Vec<Step<'a, N>>,
+    /// True if execution was cancelled before every step could be dispatched.
+    pub cancelled: bool,
+}
+
+impl<'a, N> Debug for Completion<'a, N>
+where
+    N: Node + Debug,
+    N::DependencyType: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Completion")
+            .field("completed", &self.completed)
+            .field("cancelled", &self.cancelled)
+            .finish()
+    }
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Visits every step of the graph in dependency order by calling `f`. If `f` fails for a step,
+    /// everything (transitively) depending on it is skipped, while unrelated parts of the graph are
+    /// still executed. Returns a report of which steps completed, failed and were skipped.
+    ///
+    /// Steps which are part of (or depend on) a cycle never become ready, and are not part of the report.
+    pub fn execute<F, E>(&self, f: F) -> ExecutionReport<'a, N, E>
+    where
+        F: FnMut(Step<'a, N>) -> Result<(), E>,
+    {
+        self.execute_cancellable(&AtomicBool::new(false), f)
+    }
+
+    /// Same as [`DependencyGraph::execute`], but once `cancel` is set, for instance by a Ctrl-C handler,
+    /// no further steps are started. The returned report covers the steps executed up until that point.
+    pub fn execute_cancellable<F, E>(
+        &self,
+        cancel: &AtomicBool,
+        mut f: F,
+    ) -> ExecutionReport<'a, N, E>
+    where
+        F: FnMut(Step<'a, N>) -> Result<(), E>,
+    {
+        let mut schedule = Schedule::new(self);
+        let mut skipped = HashSet::new();
+        let mut report = ExecutionReport {
+            completed: Vec::new(),
+            failed: Vec::new(),
+            skipped: Vec::new(),
+            cancelled: false,
+        };
+
+        while !cancel.load(Ordering::SeqCst) {
+            let index = match schedule.pop() {
+                Some(index) => index,
+                None => break,
+            };
+            let step = self.graph[index];
+
+            match f(step) {
+                Ok(()) => {
+                    report.completed.push(step);
+                    schedule.complete(index);
+                }
+                Err(error) => {
+                    report.failed.push((step, error));
+
+                    // The failed step never completes, so its dependents never become ready.
+                    // We only need to find them for the report.
+                    for dependent in self.reachable(Some(index), Direction::Incoming) {
+                        if dependent != index && skipped.insert(dependent) {
+                            report.skipped.push(self.graph[dependent]);
+                        }
+                    }
+                }
+            }
+        }
+
+        report.cancelled = !schedule.is_empty();
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::tests::{build_test_graph, Package};
+    use crate::{DependencyGraph, Step};
+
+    fn names(steps: &[Step<'_, Package>]) -> Vec<&'static str> {
+        let mut names: Vec<_> = steps
+            .iter()
+            .map(|step| match step {
+                Step::Resolved(package) => package.name,
+                Step::Unresolved(dependency) => dependency.name,
+            })
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn test_execute_skips_dependents() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+
+        let report = graph.execute(|step| match step {
+            Step::Resolved(package) if package.name == "derived" => Err("derived failed"),
+            _ => Ok(()),
+        });
+
+        assert!(!report.is_success());
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].1, "derived failed");
+        assert_eq!(names(&report.skipped), vec!["converged", "second_order"]);
+        assert_eq!(
+            names(&report.completed),
+            vec!["base", "external", "independent", "remote", "unknown"]
+        );
+    }
+
+    #[test]
+    fn test_execute_cancellable() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+        let cancel = AtomicBool::new(false);
+
+        let report = graph.execute_cancellable(&cancel, |_| {
+            cancel.store(true, Ordering::SeqCst);
+            Ok::<(), ()>(())
+        });
+
+        assert!(report.cancelled);
+        assert_eq!(report.completed.len(), 1);
+    }
+}
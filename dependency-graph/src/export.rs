@@ -0,0 +1,180 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{Display, Write};
+
+use petgraph::{visit::EdgeRef, Direction};
+
+use crate::{DependencyGraph, Node, Step};
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node + Display,
+    N::DependencyType: Display,
+{
+    /// Renders the graph as GraphML, which can be loaded into tools like Gephi or Cytoscape. Every node has
+    /// a `label` taken from its [`Display`] implementation, and a boolean `resolved` attribute. Like with
+    /// [`DependencyGraph::to_dot`], edges point from a node to its dependency, and are labeled with it.
+    pub fn to_graphml(&self) -> String {
+        let mut graphml = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"label\" for=\"all\" attr.name=\"label\" attr.type=\"string\"/>\n",
+            "  <key id=\"resolved\" for=\"node\" attr.name=\"resolved\" attr.type=\"boolean\"/>\n",
+            "  <graph id=\"dependencies\" edgedefault=\"directed\">\n",
+        ));
+
+        for index in self.graph.node_indices() {
+            let (label, resolved) = label(self.graph[index]);
+            let _ = write!(
+                graphml,
+                concat!(
+                    "    <node id=\"n{}\">\n",
+                    "      <data key=\"label\">{}</data>\n",
+                    "      <data key=\"resolved\">{}</data>\n",
+                    "    </node>\n",
+                ),
+                index.index(),
+                escape_xml(&label),
+                resolved
+            );
+        }
+
+        for index in self.graph.node_indices() {
+            for edge in self.graph.edges_directed(index, Direction::Outgoing) {
+                let _ = write!(
+                    graphml,
+                    concat!(
+                        "    <edge source=\"n{}\" target=\"n{}\">\n",
+                        "      <data key=\"label\">{}</data>\n",
+                        "    </edge>\n",
+                    ),
+                    edge.source().index(),
+                    edge.target().index(),
+                    escape_xml(&edge.weight().to_string())
+                );
+            }
+        }
+
+        graphml.push_str("  </graph>\n</graphml>\n");
+        graphml
+    }
+
+    /// Renders the graph in the node-link JSON format understood by d3 and networkx:
+    /// `{"nodes": [{"id", "label", "resolved"}], "links": [{"source", "target", "label"}]}`,
+    /// where `source` and `target` refer to the `id` of a node.
+    pub fn to_json(&self) -> String {
+        let nodes: Vec<_> = self
+            .graph
+            .node_indices()
+            .map(|index| {
+                let (label, resolved) = label(self.graph[index]);
+                format!(
+                    "{{\"id\":{},\"label\":\"{}\",\"resolved\":{}}}",
+                    index.index(),
+                    escape_json(&label),
+                    resolved
+                )
+            })
+            .collect();
+
+        let links: Vec<_> = self
+            .graph
+            .node_indices()
+            .flat_map(|index| self.graph.edges_directed(index, Direction::Outgoing))
+            .map(|edge| {
+                format!(
+                    "{{\"source\":{},\"target\":{},\"label\":\"{}\"}}",
+                    edge.source().index(),
+                    edge.target().index(),
+                    escape_json(&edge.weight().to_string())
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"nodes\":[{}],\"links\":[{}]}}",
+            nodes.join(","),
+            links.join(",")
+        )
+    }
+}
+
+fn label<N>(step: Step<'_, N>) -> (String, bool)
+where
+    N: Node + Display,
+    N::DependencyType: Display,
+{
+    match step {
+        Step::Resolved(node) => (node.to_string(), true),
+        Step::Unresolved(dependency) => (dependency.to_string(), false),
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::package;
+    use crate::DependencyGraph;
+
+    fn packages() -> Vec<crate::tests::Package> {
+        vec![
+            package("base", "1.0.0", &[]),
+            package(
+                "derived",
+                "1.0.0",
+                &[("base", ">=1.0.0"), ("remote", "<2.0.0")],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_to_graphml() {
+        let packages = packages();
+        let graphml = DependencyGraph::from(&packages[..]).to_graphml();
+
+        assert!(graphml.contains("<node id=\"n0\">\n      <data key=\"label\">base@1.0.0</data>\n      <data key=\"resolved\">true</data>"));
+        assert!(graphml.contains("<data key=\"label\">remote &lt;2.0.0</data>\n      <data key=\"resolved\">false</data>"));
+        assert!(graphml.contains(
+            "<edge source=\"n1\" target=\"n0\">\n      <data key=\"label\">base &gt;=1.0.0</data>"
+        ));
+        assert!(graphml.ends_with("</graphml>\n"));
+    }
+
+    #[test]
+    fn test_to_json() {
+        let packages = packages();
+        let json = DependencyGraph::from(&packages[..]).to_json();
+
+        assert!(
+            json.starts_with("{\"nodes\":[{\"id\":0,\"label\":\"base@1.0.0\",\"resolved\":true},")
+        );
+        assert!(json.contains("{\"id\":2,\"label\":\"remote <2.0.0\",\"resolved\":false}"));
+        assert!(json.contains("{\"source\":1,\"target\":0,\"label\":\"base >=1.0.0\"}"));
+        assert!(json.ends_with("]}"));
+    }
+}
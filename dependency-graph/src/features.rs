@@ -0,0 +1,129 @@
+use alloc::string::String;
+use core::iter::FromIterator;
+
+use crate::{collections::HashSet, DependencyGraph, Node};
+
+/// Set of enabled features, used by [`Node::dependency_enabled`] to decide which optional
+/// dependencies apply. See [`DependencyGraph::with_features`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeatureSet {
+    features: HashSet<String>,
+}
+
+impl FeatureSet {
+    /// An empty feature set, in which only unconditional dependencies apply.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn enable<S: Into<String>>(&mut self, feature: S) {
+        self.features.insert(feature.into());
+    }
+
+    pub fn is_enabled(&self, feature: &str) -> bool {
+        self.features.contains(feature)
+    }
+
+    /// All enabled features, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.features.iter().map(String::as_str)
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for FeatureSet {
+    fn from_iter<I: IntoIterator<Item = S>>(features: I) -> Self {
+        FeatureSet {
+            features: features.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Same as `DependencyGraph::from`, but dependencies for which [`Node::dependency_enabled`] returns
+    /// false given the active `features` are left out entirely, as if the nodes had never declared them.
+    pub fn with_features(nodes: &'a [N], features: &FeatureSet) -> Self {
+        let mut graph = Self::from(nodes);
+        graph.retain_dependencies(|node, dependency| node.dependency_enabled(dependency, features));
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DependencyGraph, FeatureSet, Node, Step};
+
+    struct Crate {
+        name: &'static str,
+        /// Dependencies, along with the feature required to enable them.
+        dependencies: Vec<(&'static str, Option<&'static str>)>,
+    }
+
+    impl Node for Crate {
+        type DependencyType = (&'static str, Option<&'static str>);
+
+        fn dependencies(&self) -> &[Self::DependencyType] {
+            &self.dependencies[..]
+        }
+
+        fn matches(&self, dependency: &Self::DependencyType) -> bool {
+            self.name == dependency.0
+        }
+
+        fn dependency_enabled(
+            &self,
+            dependency: &Self::DependencyType,
+            features: &FeatureSet,
+        ) -> bool {
+            dependency
+                .1
+                .is_none_or(|feature| features.is_enabled(feature))
+        }
+    }
+
+    fn crates() -> Vec<Crate> {
+        vec![
+            Crate {
+                name: "serde",
+                dependencies: vec![],
+            },
+            Crate {
+                name: "app",
+                dependencies: vec![
+                    ("serde", Some("serde")),
+                    ("rayon", Some("parallel")),
+                    ("log", None),
+                ],
+            },
+        ]
+    }
+
+    fn dependencies_of_app(graph: &DependencyGraph<'_, Crate>) -> Vec<&'static str> {
+        let mut names: Vec<_> = graph
+            .iter()
+            .filter_map(|step| match step {
+                Step::Resolved(krate) if krate.name != "app" => Some(krate.name),
+                Step::Resolved(_) => None,
+                Step::Unresolved(dependency) => Some(dependency.0),
+            })
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn test_with_features() {
+        let crates = crates();
+
+        let none = DependencyGraph::with_features(&crates[..], &FeatureSet::new());
+        assert_eq!(dependencies_of_app(&none), vec!["log", "serde"]);
+        assert_eq!(none.edge_count(), 1);
+
+        let all: FeatureSet = vec!["serde", "parallel"].into_iter().collect();
+        let all = DependencyGraph::with_features(&crates[..], &all);
+        assert_eq!(dependencies_of_app(&all), vec!["log", "rayon", "serde"]);
+        assert_eq!(all.edge_count(), 3);
+    }
+}
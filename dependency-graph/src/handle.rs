@@ -0,0 +1,63 @@
+use petgraph::stable_graph::NodeIndex;
+
+use crate::{DependencyGraph, Node, Step};
+
+/// Opaque reference to a step within a [`DependencyGraph`]. Handles remain valid until
+/// the step they refer to is removed from the graph, or visited by a consuming iterator.
+/// Positions are reused, so a stale handle may end up referring to a different step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeHandle(pub(crate) NodeIndex);
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Handles to every step in the graph, including unresolved dependencies.
+    pub fn handles(&self) -> impl Iterator<Item = NodeHandle> + '_ {
+        self.graph.node_indices().map(NodeHandle)
+    }
+
+    /// Looks up the step referred to by `handle`, or `None` if it is no longer part of the graph.
+    pub fn get(&self, handle: NodeHandle) -> Option<Step<'a, N>> {
+        self.graph.node_weight(handle.0).copied()
+    }
+
+    /// Finds the handle of `node`, which is identified by reference, so it must be one of the
+    /// nodes the graph was built from.
+    pub fn handle_of(&self, node: &N) -> Option<NodeHandle> {
+        self.graph
+            .node_indices()
+            .find(|index| {
+                self.graph[*index]
+                    .as_resolved()
+                    .is_some_and(|other| core::ptr::eq(other, node))
+            })
+            .map(NodeHandle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::build_test_graph;
+    use crate::DependencyGraph;
+
+    #[test]
+    fn test_handles() {
+        let packages = build_test_graph();
+        let mut graph = DependencyGraph::from(&packages[..]);
+
+        assert_eq!(graph.handles().count(), packages.len() + 2);
+
+        let handle = graph.handle_of(&packages[1]).unwrap();
+        assert_eq!(
+            graph.get(handle).unwrap().as_resolved().unwrap().name,
+            "derived"
+        );
+
+        let unrelated = build_test_graph();
+        assert!(graph.handle_of(&unrelated[1]).is_none());
+
+        graph.remove_node(handle);
+        assert!(graph.handle_of(&packages[1]).is_none());
+    }
+}
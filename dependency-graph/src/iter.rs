@@ -0,0 +1,142 @@
+use crate::collections::HashMap;
+
+use petgraph::{stable_graph::NodeIndex, Direction};
+
+use crate::{schedule::Schedule, DependencyGraph, Node, Step};
+
+/// Non-consuming iterator over a [`DependencyGraph`], as returned by [`DependencyGraph::iter`].
+///
+/// Visits the steps in exactly the same order as iterating over the graph itself would,
+/// but keeps track of the remaining dependencies on the side instead of removing nodes from the graph.
+pub struct Iter<'g, 'a, N: Node> {
+    schedule: Schedule<'g, 'a, N>,
+}
+
+/// Same as [`Iter`], but also yields the depth of each step, see [`DependencyGraph::iter_with_depth`].
+pub struct IterWithDepth<'g, 'a, N: Node> {
+    schedule: Schedule<'g, 'a, N>,
+    depths: HashMap<NodeIndex, usize>,
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Iterate over the graph without consuming it, in an order which ensures dependencies
+    /// are resolved before each Node is visited. The graph can be traversed any number of times.
+    pub fn iter(&self) -> Iter<'_, 'a, N> {
+        Iter {
+            schedule: Schedule::new(self),
+        }
+    }
+
+    /// Same as [`DependencyGraph::iter`], but every step is accompanied by its depth, meaning the length
+    /// of the longest chain of dependencies below it. Steps without dependencies have a depth of 0.
+    pub fn iter_with_depth(&self) -> IterWithDepth<'_, 'a, N> {
+        IterWithDepth {
+            schedule: Schedule::new(self),
+            depths: HashMap::new(),
+        }
+    }
+}
+
+impl<'g, 'a, N> Iterator for Iter<'g, 'a, N>
+where
+    N: Node,
+{
+    type Item = Step<'a, N>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.schedule.pop()?;
+        self.schedule.complete(index);
+        Some(self.schedule.step(index))
+    }
+}
+
+impl<'g, 'a, N> Iterator for IterWithDepth<'g, 'a, N>
+where
+    N: Node,
+{
+    type Item = (Step<'a, N>, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.schedule.pop()?;
+
+        // All dependencies have been visited by now, so their depths are known.
+        let graph = &self.schedule.graph().graph;
+        let depth = graph
+            .neighbors_directed(index, Direction::Outgoing)
+            .map(|dependency| self.depths[&dependency] + 1)
+            .max()
+            .unwrap_or(0);
+
+        self.depths.insert(index, depth);
+        self.schedule.complete(index);
+        Some((self.schedule.step(index), depth))
+    }
+}
+
+impl<'g, 'a, N> IntoIterator for &'g DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    type Item = Step<'a, N>;
+    type IntoIter = Iter<'g, 'a, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::build_test_graph;
+    use crate::{DependencyGraph, Step};
+
+    fn names<'a>(
+        steps: impl Iterator<Item = Step<'a, crate::tests::Package>>,
+    ) -> Vec<&'static str> {
+        steps
+            .map(|step| match step {
+                Step::Resolved(package) => package.name,
+                Step::Unresolved(dependency) => dependency.name,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_iter_matches_consuming_order() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+
+        let first = names(graph.iter());
+        let second = names((&graph).into_iter());
+
+        assert_eq!(first, second);
+        assert_eq!(first, names(DependencyGraph::from(&packages[..])));
+
+        // The graph is left intact.
+        assert_eq!(graph.unresolved_dependencies().count(), 2);
+    }
+
+    #[test]
+    fn test_iter_with_depth() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+
+        let depths: Vec<_> = graph
+            .iter_with_depth()
+            .map(|(step, depth)| (names(std::iter::once(step))[0], depth))
+            .collect();
+
+        assert_eq!(depths.len(), graph.node_count());
+        for (name, depth) in depths {
+            let expected = match name {
+                "derived" | "external" => 1,
+                "second_order" | "converged" => 2,
+                _ => 0,
+            };
+            assert_eq!(depth, expected, "{}", name);
+        }
+    }
+}
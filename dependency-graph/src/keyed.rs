@@ -0,0 +1,72 @@
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use crate::{collections::HashMap, skeleton_with, DependencyGraph, Node};
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Same as `DependencyGraph::from`, but instead of checking every dependency against every node,
+    /// nodes are indexed by `node_key` up front, and each dependency is only checked against the nodes
+    /// whose key equals its `dependency_key`. This turns graph construction from quadratic into roughly
+    /// linear time for large graphs.
+    ///
+    /// The keys only narrow down the candidates, [`Node::matches`] still has the final say. For packages
+    /// with versioned dependencies for instance, the package name makes for a good key:
+    /// ```ignore
+    /// DependencyGraph::from_nodes_keyed(&packages[..], |package| package.name, |dependency| dependency.name)
+    /// ```
+    pub fn from_nodes_keyed<K, FN, FD>(nodes: &'a [N], node_key: FN, dependency_key: FD) -> Self
+    where
+        K: Hash + Eq,
+        FN: Fn(&'a N) -> K,
+        FD: Fn(&'a N::DependencyType) -> K,
+    {
+        let mut index: HashMap<K, Vec<usize>> = HashMap::new();
+        for (position, node) in nodes.iter().enumerate() {
+            index.entry(node_key(node)).or_default().push(position);
+        }
+
+        let lookup = |dependency: &'a N::DependencyType, candidates: &mut Vec<usize>| {
+            if let Some(positions) = index.get(&dependency_key(dependency)) {
+                candidates.extend(
+                    positions
+                        .iter()
+                        .copied()
+                        .filter(|position| nodes[*position].matches(dependency)),
+                );
+            }
+        };
+
+        Self::from_skeleton(nodes, &skeleton_with(nodes, lookup, |_, _, _| 0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{build_test_graph, Package};
+    use crate::{DependencyGraph, Step};
+
+    fn order(graph: DependencyGraph<'_, Package>) -> Vec<String> {
+        graph
+            .map(|step| match step {
+                Step::Resolved(package) => package.to_string(),
+                Step::Unresolved(dependency) => dependency.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_keyed_matches_scan() {
+        let packages = build_test_graph();
+
+        let keyed = DependencyGraph::from_nodes_keyed(
+            &packages[..],
+            |package| package.name,
+            |dependency| dependency.name,
+        );
+
+        assert_eq!(order(keyed), order(DependencyGraph::from(&packages[..])));
+    }
+}
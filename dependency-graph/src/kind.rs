@@ -0,0 +1,152 @@
+use alloc::vec::Vec;
+
+use petgraph::{visit::EdgeRef, Direction};
+
+use crate::{DependencyGraph, Node, Step};
+
+/// Describes when a dependency is needed, see [`Node::dependency_kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DependencyKind {
+    /// Needed both when building and running the node. This is the default for all dependencies.
+    Normal,
+    /// Only needed while building the node.
+    Build,
+    /// Only needed when running the node.
+    Runtime,
+    /// Not strictly needed at all, but used if available.
+    Optional,
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Removes all dependencies whose [`DependencyKind`] is not in `kinds`. Unresolved dependencies
+    /// which are no longer depended upon are removed as well, while all nodes are kept.
+    ///
+    /// To get a graph containing only what is required at runtime for example, use:
+    /// ```ignore
+    /// graph.filtered(&[DependencyKind::Normal, DependencyKind::Runtime])
+    /// ```
+    pub fn filtered(mut self, kinds: &[DependencyKind]) -> Self {
+        self.retain_dependencies(|node, dependency| {
+            kinds.contains(&node.dependency_kind(dependency))
+        });
+        self
+    }
+
+    /// Removes all dependencies for which `keep` returns false, along with any unresolved
+    /// dependencies which are no longer depended upon.
+    pub(crate) fn retain_dependencies<F>(&mut self, keep: F)
+    where
+        F: Fn(&'a N, &'a N::DependencyType) -> bool,
+    {
+        self.ready = None;
+
+        let excluded: Vec<_> = self
+            .graph
+            .node_indices()
+            .filter_map(|index| Some((index, self.graph[index].as_resolved()?)))
+            .flat_map(|(index, node)| {
+                let keep = &keep;
+                self.graph
+                    .edges_directed(index, Direction::Outgoing)
+                    .filter(move |edge| !keep(node, edge.weight()))
+                    .map(|edge| edge.id())
+            })
+            .collect();
+
+        for edge in excluded {
+            self.graph.remove_edge(edge);
+        }
+
+        let orphaned: Vec<_> = self
+            .graph
+            .node_indices()
+            .filter(|index| {
+                matches!(self.graph[*index], Step::Unresolved(_))
+                    && self
+                        .graph
+                        .neighbors_directed(*index, Direction::Incoming)
+                        .next()
+                        .is_none()
+            })
+            .collect();
+
+        for index in orphaned {
+            self.graph.remove_node(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DependencyGraph, DependencyKind, Node, Step};
+
+    struct Task {
+        name: &'static str,
+        dependencies: Vec<(&'static str, DependencyKind)>,
+    }
+
+    impl Node for Task {
+        type DependencyType = (&'static str, DependencyKind);
+
+        fn dependencies(&self) -> &[Self::DependencyType] {
+            &self.dependencies[..]
+        }
+
+        fn matches(&self, dependency: &Self::DependencyType) -> bool {
+            self.name == dependency.0
+        }
+
+        fn dependency_kind(&self, dependency: &Self::DependencyType) -> DependencyKind {
+            dependency.1
+        }
+    }
+
+    #[test]
+    fn test_filtered() {
+        let tasks = [
+            Task {
+                name: "compiler",
+                dependencies: vec![],
+            },
+            Task {
+                name: "libc",
+                dependencies: vec![],
+            },
+            Task {
+                name: "app",
+                dependencies: vec![
+                    ("compiler", DependencyKind::Build),
+                    ("libc", DependencyKind::Normal),
+                    ("plugin", DependencyKind::Optional),
+                ],
+            },
+        ];
+
+        let runtime = DependencyGraph::from(&tasks[..])
+            .filtered(&[DependencyKind::Normal, DependencyKind::Runtime]);
+        assert!(runtime.is_internally_resolvable());
+
+        let app: Vec<_> = runtime
+            .filter_map(|step| match step {
+                Step::Resolved(task) => Some(task.name),
+                Step::Unresolved(_) => None,
+            })
+            .collect();
+        assert_eq!(app.len(), 3);
+        assert!(
+            app.iter().position(|name| *name == "libc")
+                < app.iter().position(|name| *name == "app")
+        );
+
+        let full = DependencyGraph::from(&tasks[..]).filtered(&[
+            DependencyKind::Normal,
+            DependencyKind::Build,
+            DependencyKind::Runtime,
+            DependencyKind::Optional,
+        ]);
+        assert_eq!(full.unresolved_dependencies().count(), 1);
+    }
+}
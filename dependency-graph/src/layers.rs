@@ -0,0 +1,107 @@
+use alloc::vec::Vec;
+
+use petgraph::Direction;
+
+use crate::{DependencyGraph, Node, Step};
+
+/// Iterator over the layers of a [`DependencyGraph`], as returned by [`DependencyGraph::into_layers`].
+pub struct Layers<'a, N: Node> {
+    graph: DependencyGraph<'a, N>,
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Turns the graph into an iterator over batches of [`Step`]s, where every step in a batch
+    /// only depends on steps from previous batches. All steps within a batch are independent
+    /// of each other, and can therefore be processed in parallel.
+    pub fn into_layers(self) -> Layers<'a, N> {
+        Layers { graph: self }
+    }
+}
+
+impl<'a, N> Iterator for Layers<'a, N>
+where
+    N: Node,
+{
+    type Item = Vec<Step<'a, N>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let graph = &mut self.graph.graph;
+
+        // Collect all terminal nodes before removing any of them, otherwise
+        // nodes depending on this layer would become terminal too early.
+        let terminal: Vec<_> = graph
+            .node_indices()
+            .rev()
+            .filter(|index| {
+                graph
+                    .neighbors_directed(*index, Direction::Outgoing)
+                    .next()
+                    .is_none()
+            })
+            .collect();
+
+        let layer: Vec<_> = terminal
+            .into_iter()
+            .filter_map(|index| graph.remove_node(index))
+            .collect();
+
+        if layer.is_empty() {
+            None
+        } else {
+            Some(layer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{build_test_graph, package};
+    use crate::DependencyGraph;
+
+    #[test]
+    fn test_layers() {
+        let packages = [
+            package("base", "1.0.0", &[]),
+            package("independent", "1.0.0", &[]),
+            package("left", "1.0.0", &[("base", ">=1.0.0")]),
+            package("right", "1.0.0", &[("base", ">=1.0.0")]),
+            package("top", "1.0.0", &[("left", ">=1.0.0"), ("right", ">=1.0.0")]),
+        ];
+
+        let layers: Vec<Vec<_>> = DependencyGraph::from(&packages[..])
+            .into_layers()
+            .map(|layer| {
+                let mut names: Vec<_> = layer
+                    .iter()
+                    .map(|step| step.as_resolved().unwrap().name)
+                    .collect();
+                names.sort_unstable();
+                names
+            })
+            .collect();
+
+        assert_eq!(
+            layers,
+            vec![
+                vec!["base", "independent"],
+                vec!["left", "right"],
+                vec!["top"]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_layers_include_unresolved() {
+        let packages = build_test_graph();
+        let steps: usize = DependencyGraph::from(&packages[..])
+            .into_layers()
+            .map(|layer| layer.len())
+            .sum();
+
+        // All 6 packages, plus the "unknown" and "remote" external dependencies.
+        assert_eq!(steps, 8);
+    }
+}
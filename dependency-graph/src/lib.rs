@@ -1,4 +1,99 @@
-use petgraph::{stable_graph::StableDiGraph, Direction};
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(all(feature = "rayon", not(feature = "std")))]
+compile_error!("the `rayon` feature requires the `std` feature");
+
+#[cfg(not(any(feature = "std", feature = "alloc")))]
+compile_error!("either the `std` or the `alloc` feature is required");
+
+use alloc::{collections::BinaryHeap, vec::Vec};
+use core::iter::FromIterator;
+
+use petgraph::{
+    stable_graph::{NodeIndex, StableDiGraph},
+    Direction,
+};
+
+/// Emits a `tracing` event or enters a span for the rest of the enclosing block, if the `tracing`
+/// feature is enabled. Expands to nothing otherwise.
+macro_rules! instrument {
+    (span $level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::$level!($($arg)*).entered();
+    };
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)*);
+    };
+}
+
+mod analysis;
+#[cfg(feature = "async")]
+mod asynchronous;
+mod checkpoint;
+mod collections;
+mod conflict;
+mod critical;
+mod cycle;
+mod diff;
+mod dot;
+mod duplicate;
+mod edges;
+mod execute;
+mod export;
+mod features;
+mod handle;
+mod iter;
+mod keyed;
+mod kind;
+mod layers;
+mod merge;
+mod mermaid;
+mod mutation;
+mod owned;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod paths;
+mod plan;
+mod policy;
+mod provided;
+mod prune;
+mod resolve;
+mod schedule;
+mod scheduler;
+mod snapshot;
+mod sorted;
+mod stats;
+mod subgraph;
+mod unresolved;
+mod validate;
+mod walk;
+
+pub use analysis::Analysis;
+pub use checkpoint::{Checkpoint, Traversal};
+pub use conflict::{Conflict, ResolutionConflict};
+pub use critical::CriticalPathIter;
+pub use cycle::{Cycle, CycleError};
+pub use diff::GraphDiff;
+pub use duplicate::DuplicateNode;
+pub use execute::{Completion, ExecutionReport};
+pub use features::FeatureSet;
+pub use handle::NodeHandle;
+pub use iter::{Iter, IterWithDepth};
+pub use kind::DependencyKind;
+pub use layers::Layers;
+pub use owned::OwnedDependencyGraph;
+pub use paths::DependencyPath;
+pub use plan::{BuildPlan, PlannedStep, StepKind};
+pub use policy::{ResolutionPolicy, Versioned};
+pub use resolve::ResolveWith;
+pub use scheduler::Scheduler;
+pub use sorted::SortedIter;
+pub use unresolved::{Unresolved, UnresolvedDependencies};
+pub use validate::ValidationReport;
+pub use walk::Visitor;
 
 /// Must be implemented by the type you wish
 /// to build a dependency graph for. See the README.md for an example
@@ -12,6 +107,22 @@ pub trait Node {
 
     /// Returns true if the `dependency` can be met by us.
     fn matches(&self, dependency: &Self::DependencyType) -> bool;
+
+    /// Returns the [`DependencyKind`] of one of our own dependencies, which can be used to filter
+    /// the graph using [`DependencyGraph::filtered`]. All dependencies are [`DependencyKind::Normal`] by default.
+    fn dependency_kind(&self, _dependency: &Self::DependencyType) -> DependencyKind {
+        DependencyKind::Normal
+    }
+
+    /// Returns false if one of our own dependencies only applies when a feature missing from `features`
+    /// is enabled. Only consulted by [`DependencyGraph::with_features`]. All dependencies are enabled by default.
+    fn dependency_enabled(
+        &self,
+        _dependency: &Self::DependencyType,
+        _features: &FeatureSet,
+    ) -> bool {
+        true
+    }
 }
 
 /// Wrapper around dependency graph nodes.
@@ -25,6 +136,28 @@ pub enum Step<'a, N: Node> {
     Unresolved(&'a N::DependencyType),
 }
 
+// Steps only hold references, so they can be copied regardless of whether `N` can.
+impl<'a, N: Node> Clone for Step<'a, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, N: Node> Copy for Step<'a, N> {}
+
+impl<'a, N> core::fmt::Debug for Step<'a, N>
+where
+    N: Node + core::fmt::Debug,
+    N::DependencyType: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Step::Resolved(node) => f.debug_tuple("Resolved").field(node).finish(),
+            Step::Unresolved(dependency) => f.debug_tuple("Unresolved").field(dependency).finish(),
+        }
+    }
+}
+
 impl<'a, N: Node> Step<'a, N> {
     pub fn is_resolved(&self) -> bool {
         match self {
@@ -33,14 +166,23 @@ impl<'a, N: Node> Step<'a, N> {
         }
     }
 
-    pub fn as_resolved(&self) -> Option<&N> {
+    pub fn as_resolved(&self) -> Option<&'a N> {
         match self {
             Step::Resolved(node) => Some(node),
             Step::Unresolved(_) => None,
         }
     }
 
-    pub fn as_unresolved(&self) -> Option<&N::DependencyType> {
+    /// True if both steps refer to the same node or dependency, by reference.
+    pub fn ptr_eq(&self, other: &Step<'_, N>) -> bool {
+        match (self, other) {
+            (Step::Resolved(a), Step::Resolved(b)) => core::ptr::eq(*a, *b),
+            (Step::Unresolved(a), Step::Unresolved(b)) => core::ptr::eq(*a, *b),
+            _ => false,
+        }
+    }
+
+    pub fn as_unresolved(&self) -> Option<&'a N::DependencyType> {
         match self {
             Step::Resolved(_) => None,
             Step::Unresolved(dependency) => Some(dependency),
@@ -50,44 +192,244 @@ impl<'a, N: Node> Step<'a, N> {
 
 /// The [`DependencyGraph`] structure builds an internal [Directed Graph](`petgraph::stable_graph::StableDiGraph`), which can then be traversed
 /// in an order which ensures that dependent Nodes are visited before their parents.
-pub struct DependencyGraph<'a, N: Node> {
-    graph: StableDiGraph<Step<'a, N>, &'a N::DependencyType>,
+///
+/// Edges carry a reference to the dependency they represent by default, but can carry any metadata
+/// of type `E` instead, see [`DependencyGraph::with_edge_metadata`].
+pub struct DependencyGraph<'a, N: Node, E = &'a <N as Node>::DependencyType> {
+    graph: StableDiGraph<Step<'a, N>, E>,
+    /// Terminal nodes, highest index first, used by the consuming [`Iterator`] implementation. It is only
+    /// computed once iteration starts, and must be reset to `None` whenever the graph is modified otherwise.
+    ready: Option<BinaryHeap<NodeIndex>>,
+}
+
+/// Position-based representation of a dependency graph, where nodes are referred to by their index
+/// within the input slice, and dependencies by `(node index, dependency index)`.
+/// Since it holds no references, it can be stored alongside the nodes it describes.
+pub(crate) type Skeleton = StableDiGraph<Slot, (usize, usize)>;
+
+#[derive(Clone, Copy)]
+pub(crate) enum Slot {
+    Resolved(usize),
+    Unresolved(usize, usize),
+}
+
+/// Resolves the dependencies between `nodes`, producing a [`Skeleton`] of the graph.
+/// If multiple nodes match a dependency, the first one is used.
+pub(crate) fn skeleton<N: Node>(nodes: &[N]) -> Skeleton {
+    skeleton_with(nodes, scan(nodes), |_, _, _| 0)
+}
+
+/// Finds candidates for a dependency by checking it against every single node.
+pub(crate) fn scan<'n, N: Node>(
+    nodes: &'n [N],
+) -> impl FnMut(&'n N::DependencyType, &mut Vec<usize>) {
+    move |dependency, candidates| {
+        candidates.extend(
+            nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, node)| node.matches(dependency))
+                .map(|(position, _)| position),
+        )
+    }
+}
+
+/// Same as [`skeleton`], but candidates for each dependency are found by `lookup`, which must push
+/// the positions of all nodes matching the dependency in ascending order. If there's more than one
+/// candidate, `select` is called with the position of the dependent node, the position of the
+/// dependency, and the positions of the candidates, and must return the index of the one to use.
+pub(crate) fn skeleton_with<'n, N, L, F>(nodes: &'n [N], mut lookup: L, mut select: F) -> Skeleton
+where
+    N: Node,
+    L: FnMut(&'n N::DependencyType, &mut Vec<usize>),
+    F: FnMut(usize, usize, &[usize]) -> usize,
+{
+    instrument!(span debug_span, "resolve_dependencies", nodes = nodes.len());
+    let mut graph = Skeleton::new();
+
+    // Insert the input nodes into the graph, and record their positions.
+    // We'll be adding the edges next, and filling in any unresolved
+    // steps we find along the way.
+    let indices: Vec<_> = (0..nodes.len())
+        .map(|position| graph.add_node(Slot::Resolved(position)))
+        .collect();
+
+    let mut candidates = Vec::new();
+    for (position, node) in nodes.iter().enumerate() {
+        for (dependency_position, dependency) in node.dependencies().iter().enumerate() {
+            let edge = (position, dependency_position);
+
+            // Check to see if we can resolve this dependency internally.
+            candidates.clear();
+            lookup(dependency, &mut candidates);
+
+            let dependent = match candidates.len() {
+                0 => None,
+                1 => Some(candidates[0]),
+                _ => Some(candidates[select(position, dependency_position, &candidates)]),
+            };
+
+            if let Some(dependent) = dependent {
+                instrument!(
+                    trace,
+                    node = position,
+                    dependency = dependency_position,
+                    candidates = candidates.len(),
+                    resolved_to = dependent,
+                    "resolved dependency"
+                );
+                // If we can, just add an edge between the two nodes.
+                graph.add_edge(indices[position], indices[dependent], edge);
+            } else {
+                instrument!(
+                    trace,
+                    node = position,
+                    dependency = dependency_position,
+                    "unresolved dependency"
+                );
+                // If not, create a new "Unresolved" node, and create an edge to that.
+                let unresolved = graph.add_node(Slot::Unresolved(position, dependency_position));
+                graph.add_edge(indices[position], unresolved, edge);
+            }
+        }
+    }
+
+    graph
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Builds the graph by looking up the positions of a [`Skeleton`] in `nodes`.
+    /// Node indices are preserved, so the resulting graph is traversed in the same order
+    /// regardless of whether it was built directly or through a skeleton.
+    pub(crate) fn from_skeleton(nodes: &'a [N], skeleton: &Skeleton) -> Self {
+        Self::map_skeleton(skeleton, |position| &nodes[position])
+    }
+
+    /// Same as [`DependencyGraph::from_skeleton`], but for skeletons built from references to nodes.
+    fn from_skeleton_refs(nodes: &[&'a N], skeleton: &Skeleton) -> Self {
+        Self::map_skeleton(skeleton, |position| nodes[position])
+    }
+
+    fn map_skeleton<F>(skeleton: &Skeleton, node: F) -> Self
+    where
+        F: Fn(usize) -> &'a N,
+    {
+        let graph = skeleton.map(
+            |_, slot| match *slot {
+                Slot::Resolved(position) => Step::Resolved(node(position)),
+                Slot::Unresolved(position, dependency) => {
+                    Step::Unresolved(&node(position).dependencies()[dependency])
+                }
+            },
+            |_, (position, dependency)| &node(*position).dependencies()[*dependency],
+        );
+
+        Self::from_graph(graph)
+    }
+}
+
+impl<'a, N, E> DependencyGraph<'a, N, E>
+where
+    N: Node,
+{
+    pub(crate) fn from_graph(graph: StableDiGraph<Step<'a, N>, E>) -> Self {
+        Self { graph, ready: None }
+    }
 }
 
 /// The only way to build a [`DependencyGraph`] is from a slice of objects implementing [`Node`].
 /// The graph references the original items, meaning the objects cannot be modified while
-/// the [`DependencyGraph`] holds a reference to them.
+/// the [`DependencyGraph`] holds a reference to them. See [`OwnedDependencyGraph`] if the
+/// graph needs to own its nodes instead.
 impl<'a, N> From<&'a [N]> for DependencyGraph<'a, N>
 where
     N: Node,
 {
     fn from(nodes: &'a [N]) -> Self {
-        let mut graph = StableDiGraph::<Step<'a, N>, &'a N::DependencyType>::new();
+        Self::from_skeleton(nodes, &skeleton(nodes))
+    }
+}
 
-        // Insert the input nodes into the graph, and record their positions.
-        // We'll be adding the edges next, and filling in any unresolved
-        // steps we find along the way.
-        let nodes: Vec<(_, _)> = nodes
-            .iter()
-            .map(|node| (node, graph.add_node(Step::Resolved(node))))
-            .collect();
+/// Builds the graph from a slice of references, for nodes which aren't stored contiguously.
+impl<'a, N> From<&[&'a N]> for DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    fn from(nodes: &[&'a N]) -> Self {
+        let refs: Vec<_> = nodes.iter().map(|node| NodeRef(*node)).collect();
+        Self::from_skeleton_refs(nodes, &skeleton(&refs[..]))
+    }
+}
 
-        for (node, index) in nodes.iter() {
-            for dependency in node.dependencies() {
-                // Check to see if we can resolve this dependency internally.
-                if let Some((_, dependent)) = nodes.iter().find(|(dep, _)| dep.matches(dependency))
-                {
-                    // If we can, just add an edge between the two nodes.
-                    graph.add_edge(*index, *dependent, dependency);
-                } else {
-                    // If not, create a new "Unresolved" node, and create an edge to that.
-                    let unresolved = graph.add_node(Step::Unresolved(dependency));
-                    graph.add_edge(*index, unresolved, dependency);
-                }
-            }
-        }
+/// Builds the graph from any iterator of references, such as the values of a `HashMap`:
+/// ```ignore
+/// let graph: DependencyGraph<_> = packages.values().collect();
+/// ```
+/// Traversal order depends on the order in which the iterator yields the nodes.
+impl<'a, N> FromIterator<&'a N> for DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    fn from_iter<I: IntoIterator<Item = &'a N>>(nodes: I) -> Self {
+        let nodes: Vec<_> = nodes.into_iter().collect();
+        Self::from(&nodes[..])
+    }
+}
+
+/// References to trait objects are nodes themselves, so nodes of different types sharing a dependency type
+/// can be mixed within one graph, without wrapping them in an enum:
+/// ```ignore
+/// let nodes: Vec<&dyn Node<DependencyType = Dependency>> = vec![&package, &task];
+/// let graph = DependencyGraph::from(&nodes[..]);
+/// ```
+impl<D> Node for &dyn Node<DependencyType = D> {
+    type DependencyType = D;
+
+    fn dependencies(&self) -> &[D] {
+        (**self).dependencies()
+    }
+
+    fn matches(&self, dependency: &D) -> bool {
+        (**self).matches(dependency)
+    }
+
+    fn dependency_kind(&self, dependency: &D) -> DependencyKind {
+        (**self).dependency_kind(dependency)
+    }
+
+    fn dependency_enabled(&self, dependency: &D, features: &FeatureSet) -> bool {
+        (**self).dependency_enabled(dependency, features)
+    }
+}
+
+/// A reference to a node, which allows building skeletons from slices of references. This is deliberately not a
+/// blanket implementation of [`Node`] for `&N`, which would keep dependents from implementing it for references
+/// to their own types.
+struct NodeRef<'n, N>(&'n N);
 
-        Self { graph }
+impl<'n, N> Node for NodeRef<'n, N>
+where
+    N: Node,
+{
+    type DependencyType = N::DependencyType;
+
+    fn dependencies(&self) -> &[Self::DependencyType] {
+        self.0.dependencies()
+    }
+
+    fn matches(&self, dependency: &Self::DependencyType) -> bool {
+        self.0.matches(dependency)
+    }
+
+    fn dependency_kind(&self, dependency: &Self::DependencyType) -> DependencyKind {
+        self.0.dependency_kind(dependency)
+    }
+
+    fn dependency_enabled(&self, dependency: &Self::DependencyType, features: &FeatureSet) -> bool {
+        self.0.dependency_enabled(dependency, features)
     }
 }
 
@@ -112,47 +454,102 @@ where
 /// Iterate over the DependencyGraph in an order which ensures dependencies are resolved before each Node is visited.
 /// Note: If a `Step::Unresolved` node is returned, it is the caller's responsibility to ensure the dependency is resolved
 /// before continuing.
-impl<'a, N> Iterator for DependencyGraph<'a, N>
+impl<'a, N, E> Iterator for DependencyGraph<'a, N, E>
 where
     N: Node,
 {
     type Item = Step<'a, N>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // Returns the first node, which does not have any Outgoing
-        // edges, which means it is terminal.
-        for index in self.graph.node_indices().rev() {
-            if self
-                .graph
-                .neighbors_directed(index, Direction::Outgoing)
-                .count()
-                == 0
+        // Visits the terminal node with the highest index, meaning the last one without any Outgoing edges.
+        // Rather than scanning the whole graph for every step, the terminal nodes are found once, and
+        // each removal then only needs to check whether any of its dependents became terminal.
+        let graph = &mut self.graph;
+        let ready = self.ready.get_or_insert_with(|| {
+            graph
+                .node_indices()
+                .filter(|index| {
+                    graph
+                        .neighbors_directed(*index, Direction::Outgoing)
+                        .next()
+                        .is_none()
+                })
+                .collect()
+        });
+
+        let index = ready.pop()?;
+        instrument!(trace, index = index.index(), "visiting step");
+        let mut dependents: Vec<_> = graph
+            .neighbors_directed(index, Direction::Incoming)
+            .collect();
+        // A node may depend on the same node more than once.
+        dependents.sort_unstable();
+        dependents.dedup();
+        let step = graph.remove_node(index);
+
+        for dependent in dependents {
+            if graph
+                .neighbors_directed(dependent, Direction::Outgoing)
+                .next()
+                .is_none()
             {
-                return self.graph.remove_node(index);
+                ready.push(dependent);
             }
         }
 
-        None
+        step
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
 
     use crate::{DependencyGraph, Node, Step};
+    use petgraph::Direction;
     use semver::{BuildMetadata, Prerelease, Version, VersionReq};
 
-    #[derive(Debug)]
-    struct Package {
-        name: &'static str,
-        version: Version,
-        dependencies: Vec<Dependency>,
+    #[derive(Debug, PartialEq)]
+    pub(crate) struct Package {
+        pub(crate) name: &'static str,
+        pub(crate) version: Version,
+        pub(crate) dependencies: Vec<Dependency>,
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub(crate) struct Dependency {
+        pub(crate) name: &'static str,
+        pub(crate) version: VersionReq,
+    }
+
+    impl core::fmt::Display for Package {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            write!(f, "{}@{}", self.name, self.version)
+        }
     }
 
-    #[derive(Debug)]
-    struct Dependency {
+    impl core::fmt::Display for Dependency {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            write!(f, "{} {}", self.name, self.version)
+        }
+    }
+
+    /// Shorthand for building test packages, used by the tests of the other modules.
+    pub(crate) fn package(
         name: &'static str,
-        version: VersionReq,
+        version: &str,
+        dependencies: &[(&'static str, &str)],
+    ) -> Package {
+        Package {
+            name,
+            version: version.parse().unwrap(),
+            dependencies: dependencies
+                .iter()
+                .map(|(name, version)| Dependency {
+                    name,
+                    version: version.parse().unwrap(),
+                })
+                .collect(),
+        }
     }
 
     impl Node for Package {
@@ -199,10 +596,10 @@ mod tests {
 
     #[test]
     fn test_generate_dependency_graph() {
-        DependencyGraph::from(&build_test_graph()[..]);
+        let _ = DependencyGraph::from(&build_test_graph()[..]);
     }
 
-    fn build_test_graph() -> Vec<Package> {
+    pub(crate) fn build_test_graph() -> Vec<Package> {
         vec![
             Package {
                 name: "base",
@@ -299,7 +696,7 @@ mod tests {
 
     #[test]
     fn test_internally_resolved() {
-        let packages = vec![
+        let packages = [
             Package {
                 name: "base",
                 version: semver::Version {
@@ -358,4 +755,96 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_from_references() {
+        let packages = build_test_graph();
+        let name = |step: Step<'_, Package>| match step {
+            Step::Resolved(package) => package.name,
+            Step::Unresolved(dependency) => dependency.name,
+        };
+
+        let by_slice: Vec<_> = DependencyGraph::from(&packages[..]).map(name).collect();
+        let by_iter: Vec<_> = packages
+            .iter()
+            .collect::<DependencyGraph<_>>()
+            .map(name)
+            .collect();
+        assert_eq!(by_iter, by_slice);
+
+        let references: Vec<&Package> = packages.iter().collect();
+        let by_references: Vec<_> = DependencyGraph::from(&references[..]).map(name).collect();
+        assert_eq!(by_references, by_slice);
+
+        let filtered: DependencyGraph<_> = packages
+            .iter()
+            .filter(|package| package.name != "base")
+            .collect();
+        assert_eq!(filtered.unresolved_dependencies().count(), 4);
+    }
+
+    #[test]
+    fn test_iteration_order_is_unchanged() {
+        let mut packages = build_test_graph();
+        packages.push(package(
+            "duplicated",
+            "1.0.0",
+            &[("base", ">=1.0.0"), ("base", "=1.2.3"), ("converged", "*")],
+        ));
+
+        // Reference implementation, which scans the whole graph for a terminal node on every step.
+        let mut graph = DependencyGraph::from(&packages[..]).graph;
+        let mut expected = Vec::new();
+        while let Some(index) = graph.node_indices().rev().find(|index| {
+            graph
+                .neighbors_directed(*index, Direction::Outgoing)
+                .next()
+                .is_none()
+        }) {
+            expected.push(graph.remove_node(index).unwrap());
+        }
+
+        let actual: Vec<_> = DependencyGraph::from(&packages[..]).collect();
+        assert_eq!(actual.len(), expected.len());
+        assert!(actual.iter().zip(&expected).all(|(a, b)| a.ptr_eq(b)));
+    }
+
+    #[test]
+    fn test_heterogeneous_nodes() {
+        /// A node of a different type than [`Package`], sharing its dependency type.
+        struct Task {
+            name: &'static str,
+            dependencies: Vec<Dependency>,
+        }
+
+        impl Node for Task {
+            type DependencyType = Dependency;
+
+            fn dependencies(&self) -> &[Self::DependencyType] {
+                &self.dependencies[..]
+            }
+
+            fn matches(&self, dependency: &Self::DependencyType) -> bool {
+                self.name == dependency.name
+            }
+        }
+
+        let base = package("base", "1.0.0", &[]);
+        let task = Task {
+            name: "test",
+            dependencies: vec![Dependency {
+                name: "base",
+                version: "*".parse().unwrap(),
+            }],
+        };
+
+        let nodes: Vec<&dyn Node<DependencyType = Dependency>> = vec![&task, &base];
+        let graph = DependencyGraph::from(&nodes[..]);
+
+        assert!(graph.is_internally_resolvable());
+        let order: Vec<_> = graph
+            .map(|step| step.as_resolved().unwrap().dependencies().len())
+            .collect();
+        assert_eq!(order, vec![0, 1]);
+    }
 }
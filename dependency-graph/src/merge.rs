@@ -0,0 +1,104 @@
+use alloc::vec::Vec;
+
+use petgraph::{visit::EdgeRef, Direction};
+
+use crate::{collections::HashMap, DependencyGraph, Node};
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Combines two graphs into one. Dependencies which are already resolved within either graph are kept
+    /// as they are, while unresolved dependencies are re-resolved against the nodes of the other graph,
+    /// becoming a regular dependency on the first node which matches them.
+    pub fn merge(mut self, other: DependencyGraph<'a, N>) -> Self {
+        self.ready = None;
+
+        let indices: HashMap<_, _> = other
+            .graph
+            .node_indices()
+            .map(|index| (index, self.graph.add_node(other.graph[index])))
+            .collect();
+
+        for index in other.graph.node_indices() {
+            for edge in other.graph.edges_directed(index, Direction::Outgoing) {
+                self.graph.add_edge(
+                    indices[&edge.source()],
+                    indices[&edge.target()],
+                    *edge.weight(),
+                );
+            }
+        }
+
+        let unresolved: Vec<_> = self
+            .graph
+            .node_indices()
+            .filter_map(|index| Some((index, self.graph[index].as_unresolved()?)))
+            .collect();
+
+        for (index, dependency) in unresolved {
+            let target = self.graph.node_indices().find(|other| {
+                self.graph[*other]
+                    .as_resolved()
+                    .is_some_and(|node| node.matches(dependency))
+            });
+
+            if let Some(target) = target {
+                let dependents: Vec<_> = self
+                    .graph
+                    .edges_directed(index, Direction::Incoming)
+                    .map(|edge| (edge.source(), *edge.weight()))
+                    .collect();
+
+                for (dependent, dependency) in dependents {
+                    self.graph.add_edge(dependent, target, dependency);
+                }
+
+                self.graph.remove_node(index);
+            }
+        }
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::package;
+    use crate::{DependencyGraph, Step};
+
+    #[test]
+    fn test_merge() {
+        let first = [
+            package("base", "1.0.0", &[]),
+            package("app", "1.0.0", &[("base", ">=1.0.0"), ("plugin", "*")]),
+        ];
+        let second = [package(
+            "plugin",
+            "1.0.0",
+            &[("base", "*"), ("remote", "=2.0.0")],
+        )];
+
+        let merged = DependencyGraph::from(&first[..]).merge(DependencyGraph::from(&second[..]));
+
+        assert_eq!(
+            merged
+                .unresolved_dependencies()
+                .map(|dependency| dependency.name)
+                .collect::<Vec<_>>(),
+            vec!["remote"]
+        );
+
+        let order: Vec<_> = merged
+            .map(|step| match step {
+                Step::Resolved(package) => package.name,
+                Step::Unresolved(dependency) => dependency.name,
+            })
+            .collect();
+
+        let position = |name| order.iter().position(|n| *n == name).unwrap();
+        assert_eq!(order.len(), 4);
+        assert!(position("base") < position("plugin"));
+        assert!(position("plugin") < position("app"));
+    }
+}
@@ -0,0 +1,84 @@
+use alloc::string::{String, ToString};
+use core::fmt::{Display, Write};
+
+use petgraph::{visit::EdgeRef, Direction};
+
+use crate::{DependencyGraph, Node, Step};
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node + Display,
+    N::DependencyType: Display,
+{
+    /// Renders the graph as a Mermaid `graph TD` definition, for embedding in Markdown. Like
+    /// [`DependencyGraph::to_dot`], edges point from a node to its dependency and are labeled with
+    /// the dependency, while unresolved dependencies are drawn with dashed red borders.
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("graph TD\n");
+
+        for index in self.graph.node_indices() {
+            let _ = match self.graph[index] {
+                Step::Resolved(node) => {
+                    writeln!(mermaid, "    n{}[\"{}\"]", index.index(), escape(node))
+                }
+                Step::Unresolved(dependency) => writeln!(
+                    mermaid,
+                    "    n{}[\"{}\"]:::unresolved",
+                    index.index(),
+                    escape(dependency)
+                ),
+            };
+        }
+
+        for index in self.graph.node_indices() {
+            for edge in self.graph.edges_directed(index, Direction::Outgoing) {
+                let _ = writeln!(
+                    mermaid,
+                    "    n{} -->|\"{}\"| n{}",
+                    edge.source().index(),
+                    escape(edge.weight()),
+                    edge.target().index()
+                );
+            }
+        }
+
+        mermaid.push_str("    classDef unresolved stroke:red,stroke-dasharray:5 5;\n");
+        mermaid
+    }
+}
+
+/// Escapes a label for use within a double-quoted Mermaid string, which supports neither
+/// backslash escapes nor line breaks, so HTML entities are used instead.
+fn escape<T: Display>(label: T) -> String {
+    label
+        .to_string()
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::package;
+    use crate::DependencyGraph;
+
+    #[test]
+    fn test_to_mermaid() {
+        let packages = [
+            package("base", "1.0.0", &[]),
+            package(
+                "derived",
+                "1.0.0",
+                &[("base", ">=1.0.0"), ("remote", "=2.0.0")],
+            ),
+        ];
+
+        let mermaid = DependencyGraph::from(&packages[..]).to_mermaid();
+
+        assert!(mermaid.starts_with("graph TD\n"));
+        assert!(mermaid.contains("n0[\"base@1.0.0\"]\n"));
+        assert!(mermaid.contains("n2[\"remote =2.0.0\"]:::unresolved\n"));
+        assert!(mermaid.contains("n1 -->|\"base >=1.0.0\"| n0\n"));
+        assert!(mermaid.contains("n1 -->|\"remote =2.0.0\"| n2\n"));
+    }
+}
@@ -0,0 +1,153 @@
+use alloc::vec::Vec;
+
+use petgraph::{stable_graph::NodeIndex, visit::EdgeRef, Direction};
+
+use crate::{DependencyGraph, Node, NodeHandle, Step};
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Adds a node to an existing graph. Its dependencies are resolved against the nodes already in
+    /// the graph, and any unresolved dependencies of other nodes which `node` satisfies are re-linked to it.
+    pub fn add_node(&mut self, node: &'a N) -> NodeHandle {
+        self.ready = None;
+        let index = self.graph.add_node(Step::Resolved(node));
+
+        let satisfied: Vec<_> = self
+            .graph
+            .node_indices()
+            .filter(|other| {
+                self.graph[*other]
+                    .as_unresolved()
+                    .is_some_and(|dependency| node.matches(dependency))
+            })
+            .collect();
+
+        for unresolved in satisfied {
+            let dependents: Vec<_> = self
+                .graph
+                .edges_directed(unresolved, Direction::Incoming)
+                .map(|edge| (edge.source(), *edge.weight()))
+                .collect();
+
+            for (dependent, dependency) in dependents {
+                self.graph.add_edge(dependent, index, dependency);
+            }
+
+            self.graph.remove_node(unresolved);
+        }
+
+        for dependency in node.dependencies() {
+            self.link(index, dependency);
+        }
+
+        NodeHandle(index)
+    }
+
+    /// Removes a node from the graph, returning it. Dependencies on the removed node are re-resolved
+    /// against the remaining nodes, becoming unresolved if no other node matches them. Returns `None`
+    /// if the handle does not refer to a node within the graph.
+    pub fn remove_node(&mut self, handle: NodeHandle) -> Option<&'a N> {
+        self.ready = None;
+        let index = handle.0;
+        let node = self.graph.node_weight(index)?.as_resolved()?;
+
+        let dependents: Vec<_> = self
+            .graph
+            .edges_directed(index, Direction::Incoming)
+            .map(|edge| (edge.source(), *edge.weight()))
+            .collect();
+
+        let dependencies: Vec<_> = self
+            .graph
+            .neighbors_directed(index, Direction::Outgoing)
+            .collect();
+
+        self.graph.remove_node(index);
+
+        // Unresolved dependencies belong to a single dependent, so they go away with it.
+        for dependency in dependencies {
+            if matches!(self.graph[dependency], Step::Unresolved(_))
+                && self
+                    .graph
+                    .neighbors_directed(dependency, Direction::Incoming)
+                    .next()
+                    .is_none()
+            {
+                self.graph.remove_node(dependency);
+            }
+        }
+
+        for (dependent, dependency) in dependents {
+            self.link(dependent, dependency);
+        }
+
+        Some(node)
+    }
+
+    /// Adds an edge from `index` to the first node matching `dependency`, or to a new unresolved step if there is none.
+    fn link(&mut self, index: NodeIndex, dependency: &'a N::DependencyType) {
+        let target = self.graph.node_indices().find(|other| {
+            self.graph[*other]
+                .as_resolved()
+                .is_some_and(|node| node.matches(dependency))
+        });
+
+        let target = target.unwrap_or_else(|| self.graph.add_node(Step::Unresolved(dependency)));
+        self.graph.add_edge(index, target, dependency);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{build_test_graph, package, Package};
+    use crate::DependencyGraph;
+
+    #[test]
+    fn test_add_node_relinks_unresolved() {
+        let packages = build_test_graph();
+        let remote = package("remote", "3.0.0", &[]);
+        let unknown = package("unknown", "1.5.0", &[("base", ">=1.0.0")]);
+
+        let mut graph = DependencyGraph::from(&packages[..]);
+        assert_eq!(graph.unresolved_dependencies().count(), 2);
+
+        graph.add_node(&remote);
+        graph.add_node(&unknown);
+        assert!(graph.is_internally_resolvable());
+
+        let order: Vec<_> = graph.map(|step| step.as_resolved().unwrap().name).collect();
+        let position = |name| order.iter().position(|n| *n == name).unwrap();
+
+        assert!(position("base") < position("unknown"));
+        assert!(position("unknown") < position("external"));
+        assert!(position("remote") < position("external"));
+    }
+
+    #[test]
+    fn test_remove_node_unresolves_dependents() {
+        let packages = build_test_graph();
+        let remote = package("remote", "3.0.0", &[("missing", "=1.0.0")]);
+
+        let mut graph = DependencyGraph::from(&packages[..]);
+        let handle = graph.add_node(&remote);
+
+        let unresolved = |graph: &DependencyGraph<'_, Package>| {
+            let mut names: Vec<_> = graph
+                .unresolved_dependencies()
+                .map(|dep| dep.name)
+                .collect();
+            names.sort_unstable();
+            names
+        };
+        assert_eq!(unresolved(&graph), vec!["missing", "unknown"]);
+
+        assert_eq!(
+            graph.remove_node(handle).map(|node| node.name),
+            Some("remote")
+        );
+        assert!(graph.remove_node(handle).is_none());
+        assert_eq!(unresolved(&graph), vec!["remote", "unknown"]);
+    }
+}
@@ -0,0 +1,132 @@
+use alloc::vec::Vec;
+
+use crate::{skeleton, DependencyGraph, Layers, Node, Skeleton, Slot, Step};
+
+/// Variant of [`DependencyGraph`] which owns its nodes, rather than borrowing them.
+///
+/// Dependencies are resolved once on construction, so unlike a [`DependencyGraph`] it can be
+/// built in one place and returned or sent to another thread together with its nodes.
+/// Traversal happens through a borrowed [`DependencyGraph`] view, see [`OwnedDependencyGraph::graph`].
+pub struct OwnedDependencyGraph<N: Node> {
+    nodes: Vec<N>,
+    skeleton: Skeleton,
+}
+
+impl<N> From<Vec<N>> for OwnedDependencyGraph<N>
+where
+    N: Node,
+{
+    fn from(nodes: Vec<N>) -> Self {
+        let skeleton = skeleton(&nodes[..]);
+        OwnedDependencyGraph { nodes, skeleton }
+    }
+}
+
+impl<N> OwnedDependencyGraph<N>
+where
+    N: Node,
+{
+    /// Returns a [`DependencyGraph`] borrowing the owned nodes. This does not re-resolve any
+    /// dependencies, so it is cheap enough to call for every traversal.
+    pub fn graph(&self) -> DependencyGraph<'_, N> {
+        DependencyGraph::from_skeleton(&self.nodes[..], &self.skeleton)
+    }
+
+    /// Iterate over the nodes in dependency order. See the [`Iterator`] implementation of [`DependencyGraph`].
+    pub fn iter(&self) -> DependencyGraph<'_, N> {
+        self.graph()
+    }
+
+    /// See [`DependencyGraph::into_layers`].
+    pub fn layers(&self) -> Layers<'_, N> {
+        self.graph().into_layers()
+    }
+
+    /// See [`DependencyGraph::is_internally_resolvable`].
+    pub fn is_internally_resolvable(&self) -> bool {
+        self.skeleton
+            .node_weights()
+            .all(|slot| matches!(slot, Slot::Resolved(_)))
+    }
+
+    /// See [`DependencyGraph::unresolved_dependencies`].
+    pub fn unresolved_dependencies(&self) -> impl Iterator<Item = &N::DependencyType> {
+        self.skeleton
+            .node_weights()
+            .filter_map(move |slot| match *slot {
+                Slot::Resolved(_) => None,
+                Slot::Unresolved(node, dependency) => {
+                    Some(&self.nodes[node].dependencies()[dependency])
+                }
+            })
+    }
+
+    /// The nodes of the graph, in the order they were provided.
+    pub fn nodes(&self) -> &[N] {
+        &self.nodes[..]
+    }
+
+    /// Gives back ownership of the nodes.
+    pub fn into_nodes(self) -> Vec<N> {
+        self.nodes
+    }
+}
+
+impl<'a, N> IntoIterator for &'a OwnedDependencyGraph<N>
+where
+    N: Node,
+{
+    type Item = Step<'a, N>;
+    type IntoIter = DependencyGraph<'a, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.graph()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{build_test_graph, Package};
+    use crate::{DependencyGraph, OwnedDependencyGraph, Step};
+
+    fn build_owned() -> OwnedDependencyGraph<Package> {
+        OwnedDependencyGraph::from(build_test_graph())
+    }
+
+    fn name(step: Step<'_, Package>) -> &'static str {
+        match step {
+            Step::Resolved(package) => package.name,
+            Step::Unresolved(dependency) => dependency.name,
+        }
+    }
+
+    #[test]
+    fn test_owned_matches_borrowed() {
+        let owned = build_owned();
+        let packages = build_test_graph();
+
+        let borrowed: Vec<_> = DependencyGraph::from(&packages[..]).map(name).collect();
+        assert_eq!(owned.iter().map(name).collect::<Vec<_>>(), borrowed);
+        assert_eq!((&owned).into_iter().map(name).collect::<Vec<_>>(), borrowed);
+
+        assert!(!owned.is_internally_resolvable());
+        assert_eq!(
+            owned
+                .unresolved_dependencies()
+                .map(|dep| dep.name)
+                .collect::<Vec<_>>(),
+            vec!["unknown", "remote"]
+        );
+    }
+
+    #[test]
+    fn test_owned_is_send() {
+        let owned = build_owned();
+
+        let count = std::thread::spawn(move || owned.iter().count())
+            .join()
+            .unwrap();
+
+        assert_eq!(count, 8);
+    }
+}
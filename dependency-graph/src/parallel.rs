@@ -0,0 +1,196 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+use petgraph::stable_graph::NodeIndex;
+
+use crate::{schedule::Schedule, Completion, DependencyGraph, Node, Step};
+
+/// State shared between all the jobs of a [`DependencyGraph::par_execute`] call.
+struct Shared<'g, 'a, 'c, N: Node, F, E> {
+    schedule: Mutex<Schedule<'g, 'a, N>>,
+    completed: Mutex<Vec<Step<'a, N>>>,
+    error: Mutex<Option<E>>,
+    cancel: &'c AtomicBool,
+    f: F,
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node + Sync,
+    N::DependencyType: Sync,
+{
+    /// Visits every step of the graph by calling `f` on the rayon thread pool. Each step is dispatched
+    /// as soon as all of its dependencies have completed, rather than in lockstep like [`DependencyGraph::into_layers`].
+    ///
+    /// Blocks until all steps have been visited. If `f` fails, no new steps are dispatched,
+    /// and the first error is returned once the steps already running have finished.
+    pub fn par_execute<F, E>(&self, f: F) -> Result<(), E>
+    where
+        F: Fn(Step<'a, N>) -> Result<(), E> + Sync,
+        E: Send,
+    {
+        self.par_execute_cancellable(&AtomicBool::new(false), f)
+            .map(|_| ())
+    }
+
+    /// Same as [`DependencyGraph::par_execute`], but once `cancel` is set, for instance by a Ctrl-C handler,
+    /// no new steps are dispatched. Steps already running are allowed to finish, after which the steps
+    /// completed so far are returned.
+    pub fn par_execute_cancellable<F, E>(
+        &self,
+        cancel: &AtomicBool,
+        f: F,
+    ) -> Result<Completion<'a, N>, E>
+    where
+        F: Fn(Step<'a, N>) -> Result<(), E> + Sync,
+        E: Send,
+    {
+        let mut schedule = Schedule::new(self);
+        let ready: Vec<_> = if cancel.load(Ordering::SeqCst) {
+            Vec::new()
+        } else {
+            std::iter::from_fn(|| schedule.pop()).collect()
+        };
+
+        let shared = Shared {
+            schedule: Mutex::new(schedule),
+            completed: Mutex::new(Vec::new()),
+            error: Mutex::new(None),
+            cancel,
+            f,
+        };
+
+        rayon::scope(|scope| {
+            for index in ready {
+                dispatch(scope, &shared, index);
+            }
+        });
+
+        if let Some(error) = shared.error.into_inner().unwrap() {
+            return Err(error);
+        }
+
+        // Anything still ready at this point was held back by the cancellation.
+        Ok(Completion {
+            cancelled: !shared.schedule.into_inner().unwrap().is_empty(),
+            completed: shared.completed.into_inner().unwrap(),
+        })
+    }
+}
+
+fn dispatch<'s, 'g, 'a, 'c, N, F, E>(
+    scope: &rayon::Scope<'s>,
+    shared: &'s Shared<'g, 'a, 'c, N, F, E>,
+    index: NodeIndex,
+) where
+    N: Node + Sync,
+    N::DependencyType: Sync,
+    F: Fn(Step<'a, N>) -> Result<(), E> + Sync,
+    E: Send,
+{
+    scope.spawn(move |scope| {
+        let step = shared.schedule.lock().unwrap().step(index);
+
+        if let Err(error) = (shared.f)(step) {
+            shared.error.lock().unwrap().get_or_insert(error);
+            return;
+        }
+
+        shared.completed.lock().unwrap().push(step);
+        let stopped =
+            shared.error.lock().unwrap().is_some() || shared.cancel.load(Ordering::SeqCst);
+
+        // Don't hold on to the lock while dispatching. When stopping, newly ready
+        // steps are left on the schedule instead.
+        let ready: Vec<_> = {
+            let mut schedule = shared.schedule.lock().unwrap();
+            schedule.complete(index);
+
+            if stopped {
+                return;
+            }
+            std::iter::from_fn(|| schedule.pop()).collect()
+        };
+
+        for index in ready {
+            dispatch(scope, shared, index);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    };
+
+    use crate::tests::build_test_graph;
+    use crate::{DependencyGraph, Step};
+
+    #[test]
+    fn test_par_execute_respects_dependencies() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+        let finished = Mutex::new(Vec::new());
+
+        graph
+            .par_execute(|step| {
+                let name = match step {
+                    Step::Resolved(package) => {
+                        for dependency in &package.dependencies {
+                            assert!(finished.lock().unwrap().contains(&dependency.name));
+                        }
+                        package.name
+                    }
+                    Step::Unresolved(dependency) => dependency.name,
+                };
+                finished.lock().unwrap().push(name);
+                Ok::<(), ()>(())
+            })
+            .unwrap();
+
+        assert_eq!(finished.into_inner().unwrap().len(), packages.len() + 2);
+    }
+
+    #[test]
+    fn test_par_execute_stops_on_error() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+
+        let result = graph.par_execute(|step| match step {
+            Step::Resolved(package) if package.name == "base" => Err(package.name),
+            _ => Ok(()),
+        });
+
+        assert_eq!(result, Err("base"));
+    }
+
+    #[test]
+    fn test_par_execute_cancellable() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+        let cancel = AtomicBool::new(false);
+
+        let completion = graph
+            .par_execute_cancellable(&cancel, |step| {
+                if step
+                    .as_resolved()
+                    .is_some_and(|package| package.name == "base")
+                {
+                    cancel.store(true, Ordering::SeqCst);
+                }
+                Ok::<(), ()>(())
+            })
+            .unwrap();
+
+        // Nothing depending on base can have been dispatched after it completed.
+        assert!(completion.cancelled);
+        assert!(completion.completed.len() < packages.len() + 2);
+        assert!(!completion.completed.iter().any(|step| step
+            .as_resolved()
+            .is_some_and(|package| package.name == "derived")));
+    }
+}
@@ -0,0 +1,133 @@
+use alloc::{vec, vec::Vec};
+use core::fmt::{self, Debug};
+
+use petgraph::{stable_graph::NodeIndex, visit::EdgeRef, Direction};
+
+use crate::{DependencyGraph, Node, Step};
+
+/// A chain of dependencies leading from one step of a [`DependencyGraph`] to another.
+pub struct DependencyPath<'a, N: Node> {
+    /// The steps along the path, starting with the dependent and ending with the dependency.
+    pub steps: Vec<Step<'a, N>>,
+    /// The dependencies between consecutive steps, so `dependencies[i]` leads from `steps[i]` to `steps[i + 1]`.
+    pub dependencies: Vec<&'a N::DependencyType>,
+}
+
+impl<'a, N> Debug for DependencyPath<'a, N>
+where
+    N: Node + Debug,
+    N::DependencyType: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DependencyPath")
+            .field("steps", &self.steps)
+            .field("dependencies", &self.dependencies)
+            .finish()
+    }
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Explains why `from` depends on `to`, by returning every chain of dependencies leading from one to the other.
+    /// `to` can be either another node, or an unresolved dependency, and both are identified by reference.
+    ///
+    /// Returns an empty list if `from` does not (transitively) depend on `to`. Note that the number of distinct
+    /// paths can grow exponentially with the size of the graph.
+    pub fn dependency_paths(&self, from: &N, to: Step<'_, N>) -> Vec<DependencyPath<'a, N>> {
+        let is_target = |step: &Step<'a, N>| step.ptr_eq(&to);
+
+        let start = match self.graph.node_indices().find(|index| {
+            self.graph[*index]
+                .as_resolved()
+                .is_some_and(|node| core::ptr::eq(node, from))
+        }) {
+            Some(start) => start,
+            None => return Vec::new(),
+        };
+
+        let mut paths = Vec::new();
+        let mut nodes = vec![start];
+        let mut dependencies = Vec::new();
+        self.collect_paths(&is_target, &mut nodes, &mut dependencies, &mut paths);
+        paths
+    }
+
+    /// Depth-first search for all simple paths from the last node in `nodes` to a target.
+    fn collect_paths<T>(
+        &self,
+        is_target: &T,
+        nodes: &mut Vec<NodeIndex>,
+        dependencies: &mut Vec<&'a N::DependencyType>,
+        paths: &mut Vec<DependencyPath<'a, N>>,
+    ) where
+        T: Fn(&Step<'a, N>) -> bool,
+    {
+        let current = *nodes.last().unwrap();
+
+        if nodes.len() > 1 && is_target(&self.graph[current]) {
+            paths.push(DependencyPath {
+                steps: nodes.iter().map(|index| self.graph[*index]).collect(),
+                dependencies: dependencies.clone(),
+            });
+            return;
+        }
+
+        for edge in self.graph.edges_directed(current, Direction::Outgoing) {
+            // Don't walk in circles.
+            if nodes.contains(&edge.target()) {
+                continue;
+            }
+
+            nodes.push(edge.target());
+            dependencies.push(*edge.weight());
+            self.collect_paths(is_target, nodes, dependencies, paths);
+            nodes.pop();
+            dependencies.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::build_test_graph;
+    use crate::{DependencyGraph, Step};
+
+    #[test]
+    fn test_dependency_paths() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+        let find = |name| packages.iter().find(|p| p.name == name).unwrap();
+
+        let mut paths: Vec<Vec<_>> = graph
+            .dependency_paths(find("converged"), Step::Resolved(find("base")))
+            .into_iter()
+            .map(|path| {
+                assert_eq!(path.dependencies.len() + 1, path.steps.len());
+                path.steps
+                    .iter()
+                    .map(|step| step.as_resolved().unwrap().name)
+                    .collect()
+            })
+            .collect();
+        paths.sort();
+
+        assert_eq!(
+            paths,
+            vec![
+                vec!["converged", "base"],
+                vec!["converged", "derived", "base"]
+            ]
+        );
+
+        let remote = &find("external").dependencies[1];
+        let paths = graph.dependency_paths(find("external"), Step::Unresolved(remote));
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].dependencies[0].name, "remote");
+
+        assert!(graph
+            .dependency_paths(find("base"), Step::Resolved(find("derived")))
+            .is_empty());
+    }
+}
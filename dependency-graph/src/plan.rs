@@ -0,0 +1,124 @@
+use alloc::vec::Vec;
+
+use petgraph::Direction;
+
+use crate::{collections::HashMap, schedule::Schedule, DependencyGraph, Node, Step};
+
+/// Whether a [`PlannedStep`] was resolved within the graph, see [`Step`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum StepKind {
+    Resolved,
+    Unresolved,
+}
+
+/// A single step of a [`BuildPlan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlannedStep<K> {
+    /// Identifies the node or unresolved dependency this step represents.
+    pub key: K,
+    pub kind: StepKind,
+    /// Positions of the steps within the plan that this step depends on. These always come before the step itself.
+    pub dependencies: Vec<usize>,
+}
+
+/// A plain-data snapshot of the traversal order of a [`DependencyGraph`], see [`DependencyGraph::plan`].
+///
+/// Since it holds no references to the original nodes, a plan can be stored (using the `serde` feature)
+/// and executed later, either in order or in parallel using the recorded dependencies, without resolving the graph again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BuildPlan<K> {
+    /// All steps in the order they would be visited when iterating over the graph.
+    pub steps: Vec<PlannedStep<K>>,
+}
+
+impl<K> BuildPlan<K> {
+    /// Keys of all the unresolved dependencies within the plan.
+    pub fn unresolved(&self) -> impl Iterator<Item = &K> {
+        self.steps
+            .iter()
+            .filter(|step| step.kind == StepKind::Unresolved)
+            .map(|step| &step.key)
+    }
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Computes the traversal order of the graph as a [`BuildPlan`], using `node_key` and `dependency_key`
+    /// to identify resolved nodes and unresolved dependencies respectively.
+    pub fn plan<K, FN, FD>(&self, mut node_key: FN, mut dependency_key: FD) -> BuildPlan<K>
+    where
+        FN: FnMut(&'a N) -> K,
+        FD: FnMut(&'a N::DependencyType) -> K,
+    {
+        let mut schedule = Schedule::new(self);
+        let mut positions = HashMap::new();
+        let mut steps = Vec::new();
+
+        while let Some(index) = schedule.pop() {
+            let (key, kind) = match self.graph[index] {
+                Step::Resolved(node) => (node_key(node), StepKind::Resolved),
+                Step::Unresolved(dependency) => (dependency_key(dependency), StepKind::Unresolved),
+            };
+
+            let dependencies = self
+                .graph
+                .neighbors_directed(index, Direction::Outgoing)
+                .map(|dependency| positions[&dependency])
+                .collect();
+
+            positions.insert(index, steps.len());
+            steps.push(PlannedStep {
+                key,
+                kind,
+                dependencies,
+            });
+            schedule.complete(index);
+        }
+
+        BuildPlan { steps }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::build_test_graph;
+    use crate::{DependencyGraph, StepKind};
+
+    #[test]
+    fn test_plan() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+
+        let plan = graph.plan(
+            |package| package.to_string(),
+            |dependency| dependency.to_string(),
+        );
+
+        assert_eq!(plan.steps.len(), graph.node_count());
+        assert_eq!(
+            plan.unresolved().collect::<Vec<_>>(),
+            vec!["remote =3.0.0", "unknown >=1.0.0"]
+        );
+
+        for (position, step) in plan.steps.iter().enumerate() {
+            assert!(step
+                .dependencies
+                .iter()
+                .all(|dependency| *dependency < position));
+        }
+
+        let external = plan
+            .steps
+            .iter()
+            .find(|step| step.key == "external@1.2.3")
+            .unwrap();
+        assert_eq!(external.kind, StepKind::Resolved);
+        assert_eq!(external.dependencies.len(), 2);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{DependencyGraph, Node};
+
+/// Exposes an ordering key for a [`Node`], used by [`ResolutionPolicy`] to choose between
+/// multiple nodes matching the same dependency.
+pub trait Versioned: Node {
+    /// Typically the version of a package, but any ordered key will do.
+    type Version: Ord;
+
+    fn version(&self) -> &Self::Version;
+}
+
+/// Decides which node a dependency is resolved to, when more than one node matches it.
+/// See [`DependencyGraph::from_nodes_with_policy`].
+pub enum ResolutionPolicy<N: Node> {
+    /// Prefer the node with the highest [`Versioned::version`].
+    HighestVersion,
+    /// Prefer the node with the lowest [`Versioned::version`].
+    LowestVersion,
+    /// Pick a candidate based on the dependency, by returning its index. See [`DependencyGraph::from_nodes_with`].
+    Custom(fn(&N::DependencyType, &[&N]) -> usize),
+}
+
+impl<N: Node> Clone for ResolutionPolicy<N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<N: Node> Copy for ResolutionPolicy<N> {}
+
+impl<N: Node> core::fmt::Debug for ResolutionPolicy<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ResolutionPolicy::HighestVersion => f.write_str("HighestVersion"),
+            ResolutionPolicy::LowestVersion => f.write_str("LowestVersion"),
+            ResolutionPolicy::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Versioned,
+{
+    /// Same as `DependencyGraph::from`, but if a dependency is matched by more than one node, the
+    /// candidate is chosen according to `policy`. Candidates with equal versions are resolved to
+    /// whichever was provided first, so the outcome only depends on the order of `nodes`.
+    pub fn from_nodes_with_policy(nodes: &'a [N], policy: ResolutionPolicy<N>) -> Self {
+        Self::from_nodes_with(nodes, |dependency, candidates| {
+            let indices = 0..candidates.len();
+            let version = |index: &usize| candidates[*index].version();
+
+            match policy {
+                ResolutionPolicy::HighestVersion => indices.rev().max_by_key(version),
+                ResolutionPolicy::LowestVersion => indices.min_by_key(version),
+                ResolutionPolicy::Custom(select) => Some(select(dependency, candidates)),
+            }
+            .unwrap()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{package, Package};
+    use crate::{DependencyGraph, ResolutionPolicy, Step, Versioned};
+
+    impl Versioned for Package {
+        type Version = semver::Version;
+
+        fn version(&self) -> &Self::Version {
+            &self.version
+        }
+    }
+
+    fn resolved_base(policy: ResolutionPolicy<Package>) -> String {
+        let packages = [
+            package("base", "1.1.0", &[]),
+            package("base", "1.0.0", &[]),
+            package("base", "1.2.0", &[]),
+            package("derived", "1.0.0", &[("base", ">=1.0.0")]),
+        ];
+
+        let graph = DependencyGraph::from_nodes_with_policy(&packages[..], policy);
+
+        // The chosen base is the only one depended upon, and therefore the last one to be
+        // visited before derived.
+        let order: Vec<_> = graph
+            .filter_map(|step| match step {
+                Step::Resolved(package) => Some(package.to_string()),
+                Step::Unresolved(_) => None,
+            })
+            .collect();
+
+        let derived = order.iter().position(|p| p == "derived@1.0.0").unwrap();
+        order[derived - 1].clone()
+    }
+
+    #[test]
+    fn test_resolution_policy() {
+        assert_eq!(
+            resolved_base(ResolutionPolicy::HighestVersion),
+            "base@1.2.0"
+        );
+        assert_eq!(resolved_base(ResolutionPolicy::LowestVersion), "base@1.0.0");
+        assert_eq!(
+            resolved_base(ResolutionPolicy::Custom(|_, _| 0)),
+            "base@1.1.0"
+        );
+    }
+}
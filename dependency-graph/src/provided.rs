@@ -0,0 +1,57 @@
+use alloc::vec::Vec;
+
+use crate::{DependencyGraph, Node, Step};
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Same as `DependencyGraph::from`, but dependencies which can't be resolved internally are
+    /// checked against `provided` first, and only become [`Step::Unresolved`] if it returns false.
+    /// Useful for dependencies that are known to be satisfied externally, for instance by the host system.
+    pub fn with_provided<P>(nodes: &'a [N], provided: P) -> Self
+    where
+        P: Fn(&N::DependencyType) -> bool,
+    {
+        let mut graph = Self::from(nodes);
+
+        let satisfied: Vec<_> = graph
+            .graph
+            .node_indices()
+            .filter(|index| match graph.graph[*index] {
+                Step::Unresolved(dependency) => provided(dependency),
+                Step::Resolved(_) => false,
+            })
+            .collect();
+
+        for index in satisfied {
+            graph.graph.remove_node(index);
+        }
+
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::build_test_graph;
+    use crate::DependencyGraph;
+
+    #[test]
+    fn test_with_provided() {
+        let packages = build_test_graph();
+
+        let graph = DependencyGraph::with_provided(&packages[..], |dependency| {
+            dependency.name == "unknown"
+        });
+
+        assert_eq!(
+            graph
+                .unresolved_dependencies()
+                .map(|dependency| dependency.name)
+                .collect::<Vec<_>>(),
+            vec!["remote"]
+        );
+        assert_eq!(graph.count(), packages.len() + 1);
+    }
+}
@@ -0,0 +1,112 @@
+use alloc::vec::Vec;
+
+use petgraph::Direction;
+
+use crate::{DependencyGraph, Node, Step};
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Removes every step for which `keep` returns false. Nodes depending on a removed step simply
+    /// lose that dependency, see [`DependencyGraph::retain_with_dependents`] to remove them as well.
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&Step<'a, N>) -> bool,
+    {
+        self.ready = None;
+
+        let removed: Vec<_> = self
+            .graph
+            .node_indices()
+            .filter(|index| !keep(&self.graph[*index]))
+            .collect();
+
+        for index in removed {
+            self.graph.remove_node(index);
+        }
+    }
+
+    /// Same as [`DependencyGraph::retain`], but everything (transitively) depending on a removed
+    /// step is removed too, so no remaining node is ever missing one of its dependencies.
+    pub fn retain_with_dependents<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&Step<'a, N>) -> bool,
+    {
+        self.ready = None;
+
+        let removed: Vec<_> = self
+            .graph
+            .node_indices()
+            .filter(|index| !keep(&self.graph[*index]))
+            .collect();
+
+        for index in self.reachable(removed, Direction::Incoming) {
+            self.graph.remove_node(index);
+        }
+    }
+
+    /// Removes all unresolved dependencies, treating them as satisfied.
+    pub fn prune_unresolved(&mut self) {
+        self.retain(Step::is_resolved);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{build_test_graph, Package};
+    use crate::{DependencyGraph, Step};
+
+    fn names(graph: &DependencyGraph<'_, Package>) -> Vec<&'static str> {
+        let mut names: Vec<_> = graph
+            .iter()
+            .map(|step| match step {
+                Step::Resolved(package) => package.name,
+                Step::Unresolved(dependency) => dependency.name,
+            })
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn test_retain() {
+        let packages = build_test_graph();
+        let is_derived = |step: &Step<'_, Package>| {
+            step.as_resolved()
+                .is_some_and(|package| package.name == "derived")
+        };
+
+        let mut graph = DependencyGraph::from(&packages[..]);
+        graph.retain(|step| !is_derived(step));
+        assert_eq!(
+            names(&graph),
+            vec![
+                "base",
+                "converged",
+                "external",
+                "independent",
+                "remote",
+                "second_order",
+                "unknown"
+            ]
+        );
+
+        let mut graph = DependencyGraph::from(&packages[..]);
+        graph.retain_with_dependents(|step| !is_derived(step));
+        assert_eq!(
+            names(&graph),
+            vec!["base", "external", "independent", "remote", "unknown"]
+        );
+    }
+
+    #[test]
+    fn test_prune_unresolved() {
+        let packages = build_test_graph();
+        let mut graph = DependencyGraph::from(&packages[..]);
+
+        graph.prune_unresolved();
+        assert!(graph.is_internally_resolvable());
+        assert_eq!(graph.iter().count(), packages.len());
+    }
+}
@@ -0,0 +1,117 @@
+use crate::collections::HashSet;
+
+use petgraph::{stable_graph::NodeIndex, Direction};
+
+use crate::{DependencyGraph, Node, Step};
+
+/// Iterator returned by [`DependencyGraph::resolve_with`].
+pub struct ResolveWith<'a, N: Node, F> {
+    graph: DependencyGraph<'a, N>,
+    resolver: F,
+    /// Unresolved dependencies which the resolver failed to resolve. These are
+    /// kept in the graph, so that nodes depending on them are never emitted.
+    failed: HashSet<NodeIndex>,
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Traverse the graph, handing every unresolved dependency to `resolver` as soon as it is encountered.
+    ///
+    /// If the resolver succeeds, the dependency is considered satisfied, and traversal continues as normal.
+    /// If it fails, the error is yielded instead, and any nodes (transitively) depending on the dependency
+    /// are withheld, since they can no longer be resolved. Nodes are only ever yielded once all of their
+    /// dependencies, internal or external, have been handled.
+    pub fn resolve_with<F, E>(self, resolver: F) -> ResolveWith<'a, N, F>
+    where
+        F: FnMut(&'a N::DependencyType) -> Result<(), E>,
+    {
+        ResolveWith {
+            graph: self,
+            resolver,
+            failed: HashSet::new(),
+        }
+    }
+}
+
+impl<'a, N, F, E> Iterator for ResolveWith<'a, N, F>
+where
+    N: Node,
+    F: FnMut(&'a N::DependencyType) -> Result<(), E>,
+{
+    type Item = Result<&'a N, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let graph = &mut self.graph.graph;
+            let failed = &self.failed;
+
+            let index = graph.node_indices().rev().find(|index| {
+                !failed.contains(index)
+                    && graph
+                        .neighbors_directed(*index, Direction::Outgoing)
+                        .next()
+                        .is_none()
+            })?;
+
+            match graph[index] {
+                Step::Resolved(node) => {
+                    graph.remove_node(index);
+                    return Some(Ok(node));
+                }
+                Step::Unresolved(dependency) => match (self.resolver)(dependency) {
+                    Ok(()) => {
+                        graph.remove_node(index);
+                    }
+                    Err(error) => {
+                        self.failed.insert(index);
+                        return Some(Err(error));
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::build_test_graph;
+    use crate::DependencyGraph;
+
+    #[test]
+    fn test_resolve_with() {
+        let packages = build_test_graph();
+        let mut lookups = Vec::new();
+
+        let built: Vec<_> = DependencyGraph::from(&packages[..])
+            .resolve_with(|dependency| {
+                lookups.push(dependency.name);
+                Ok::<(), ()>(())
+            })
+            .map(|package| package.unwrap().name)
+            .collect();
+
+        lookups.sort_unstable();
+        assert_eq!(lookups, vec!["remote", "unknown"]);
+        assert_eq!(built.len(), packages.len());
+        assert!(built.contains(&"external"));
+    }
+
+    #[test]
+    fn test_failed_resolution_withholds_dependents() {
+        let packages = build_test_graph();
+
+        let results: Vec<_> = DependencyGraph::from(&packages[..])
+            .resolve_with(|dependency| match dependency.name {
+                "remote" => Err(dependency.name),
+                _ => Ok(()),
+            })
+            .map(|result| result.map(|package| package.name))
+            .collect();
+
+        assert!(results.contains(&Err("remote")));
+        assert!(!results.contains(&Ok("external")));
+        assert_eq!(results.len(), packages.len());
+    }
+}
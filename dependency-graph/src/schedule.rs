@@ -0,0 +1,79 @@
+use alloc::collections::BinaryHeap;
+
+use petgraph::{stable_graph::NodeIndex, visit::EdgeRef, Direction};
+
+use crate::{collections::HashMap, DependencyGraph, Node, Step};
+
+/// Keeps track of which nodes of a [`DependencyGraph`] are ready to be visited, without modifying the graph.
+/// A node becomes ready once all of its dependencies have been marked as completed.
+pub(crate) struct Schedule<'g, 'a, N: Node> {
+    graph: &'g DependencyGraph<'a, N>,
+    /// Number of dependencies not yet completed, for each node which isn't ready.
+    remaining: HashMap<NodeIndex, usize>,
+    /// Nodes whose dependencies have all been completed. Popping the highest index
+    /// first matches the order of the consuming iterator.
+    ready: BinaryHeap<NodeIndex>,
+}
+
+impl<'g, 'a, N> Schedule<'g, 'a, N>
+where
+    N: Node,
+{
+    pub(crate) fn new(graph: &'g DependencyGraph<'a, N>) -> Self {
+        let mut remaining = HashMap::new();
+        let mut ready = BinaryHeap::new();
+
+        for index in graph.graph.node_indices() {
+            let dependencies = graph
+                .graph
+                .edges_directed(index, Direction::Outgoing)
+                .count();
+
+            if dependencies == 0 {
+                ready.push(index);
+            } else {
+                remaining.insert(index, dependencies);
+            }
+        }
+
+        Schedule {
+            graph,
+            remaining,
+            ready,
+        }
+    }
+
+    /// Takes the next ready node off the schedule. It is up to the caller to [`Schedule::complete`] it.
+    pub(crate) fn pop(&mut self) -> Option<NodeIndex> {
+        let index = self.ready.pop()?;
+        instrument!(trace, index = index.index(), "visiting step");
+        Some(index)
+    }
+
+    /// Marks a node as completed, making any dependents without other outstanding dependencies ready.
+    pub(crate) fn complete(&mut self, index: NodeIndex) {
+        for edge in self.graph.graph.edges_directed(index, Direction::Incoming) {
+            let dependent = edge.source();
+            if let Some(remaining) = self.remaining.get_mut(&dependent) {
+                *remaining -= 1;
+                if *remaining == 0 {
+                    self.remaining.remove(&dependent);
+                    self.ready.push(dependent);
+                }
+            }
+        }
+    }
+
+    /// True if no nodes are currently ready.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.ready.is_empty()
+    }
+
+    pub(crate) fn graph(&self) -> &'g DependencyGraph<'a, N> {
+        self.graph
+    }
+
+    pub(crate) fn step(&self, index: NodeIndex) -> Step<'a, N> {
+        self.graph.graph[index]
+    }
+}
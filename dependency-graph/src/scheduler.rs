@@ -0,0 +1,111 @@
+use alloc::vec::Vec;
+
+use petgraph::{stable_graph::NodeIndex, Direction};
+
+use crate::{collections::HashSet, schedule::Schedule, DependencyGraph, Node, NodeHandle, Step};
+
+/// Pull-based scheduler which leaves execution entirely up to the caller, see [`DependencyGraph::scheduler`].
+pub struct Scheduler<'g, 'a, N: Node> {
+    schedule: Schedule<'g, 'a, N>,
+    /// Steps handed out by [`Scheduler::ready`], but not yet completed.
+    in_progress: HashSet<NodeIndex>,
+    completed: HashSet<NodeIndex>,
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Returns a [`Scheduler`] for integrating the dependency order of the graph with an executor of your own.
+    /// Take the steps which are ready using [`Scheduler::ready`], execute them however you like, and report
+    /// each of them back through [`Scheduler::complete`] to unlock the steps depending on them.
+    pub fn scheduler(&self) -> Scheduler<'_, 'a, N> {
+        Scheduler {
+            schedule: Schedule::new(self),
+            in_progress: HashSet::new(),
+            completed: HashSet::new(),
+        }
+    }
+}
+
+impl<'g, 'a, N> Scheduler<'g, 'a, N>
+where
+    N: Node,
+{
+    /// Takes all the steps which are ready to run, marking them as in progress. Every step is only
+    /// returned once, so an empty result means nothing new is ready until more steps are completed.
+    pub fn ready(&mut self) -> Vec<(NodeHandle, Step<'a, N>)> {
+        let mut ready = Vec::new();
+
+        while let Some(index) = self.schedule.pop() {
+            self.in_progress.insert(index);
+            ready.push((NodeHandle(index), self.schedule.step(index)));
+        }
+
+        ready
+    }
+
+    /// Marks a step returned by [`Scheduler::ready`] as completed. Returns false and does nothing if the
+    /// step is not in progress, for instance because it has already been completed.
+    pub fn complete(&mut self, handle: NodeHandle) -> bool {
+        let completed = self.in_progress.remove(&handle.0);
+        if completed {
+            self.completed.insert(handle.0);
+            self.schedule.complete(handle.0);
+        }
+
+        completed
+    }
+
+    /// Number of steps returned by [`Scheduler::ready`] which have not been completed yet.
+    pub fn in_progress(&self) -> usize {
+        self.in_progress.len()
+    }
+
+    /// The dependencies of the step referred to by `handle` which have not been completed yet,
+    /// for showing what a step is waiting on.
+    pub fn blocked_by(&self, handle: NodeHandle) -> Vec<Step<'a, N>> {
+        let graph = &self.schedule.graph().graph;
+        graph
+            .neighbors_directed(handle.0, Direction::Outgoing)
+            .filter(|dependency| !self.completed.contains(dependency))
+            .map(|dependency| graph[dependency])
+            .collect()
+    }
+
+    /// True once nothing is in progress and no more steps can become ready. If the graph contains
+    /// cycles, the steps involved will never have been returned.
+    pub fn is_finished(&self) -> bool {
+        self.in_progress.is_empty() && self.schedule.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::build_test_graph;
+    use crate::DependencyGraph;
+
+    #[test]
+    fn test_scheduler() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+        let mut scheduler = graph.scheduler();
+
+        let mut visited = 0;
+        while !scheduler.is_finished() {
+            let ready = scheduler.ready();
+            assert!(!ready.is_empty());
+
+            // Completing a batch at once mimics an executor running them in parallel.
+            for (handle, _) in &ready {
+                assert!(scheduler.blocked_by(*handle).is_empty());
+                assert!(scheduler.complete(*handle));
+                assert!(!scheduler.complete(*handle));
+            }
+
+            visited += ready.len();
+        }
+
+        assert_eq!(visited, graph.node_count());
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{DependencyGraph, Node};
+
+// The graph only holds references to the nodes, so it can be cloned regardless of whether `N` can.
+impl<'a, N, E> Clone for DependencyGraph<'a, N, E>
+where
+    N: Node,
+    E: Clone,
+{
+    fn clone(&self) -> Self {
+        DependencyGraph {
+            graph: self.graph.clone(),
+            ready: self.ready.clone(),
+        }
+    }
+}
+
+impl<'a, N, E> DependencyGraph<'a, N, E>
+where
+    N: Node,
+    E: Clone,
+{
+    /// Captures the steps which are left to visit, which is the entire graph if iteration has not started yet.
+    /// Since iterating over the graph consumes it, a snapshot taken mid-iteration can be used to fork the
+    /// traversal, for instance to work out what would remain if a step were to fail:
+    /// ```ignore
+    /// let mut fork = graph.snapshot();
+    /// fork.retain_with_dependents(|step| !step.ptr_eq(&failing));
+    /// ```
+    pub fn snapshot(&self) -> Self {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{build_test_graph, Package};
+    use crate::{DependencyGraph, Step};
+
+    fn names<'a>(steps: impl Iterator<Item = Step<'a, Package>>) -> Vec<&'static str> {
+        let mut names: Vec<_> = steps
+            .map(|step| match step {
+                Step::Resolved(package) => package.name,
+                Step::Unresolved(dependency) => dependency.name,
+            })
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn test_snapshot_mid_iteration() {
+        let packages = build_test_graph();
+        let mut graph = DependencyGraph::from(&packages[..]);
+
+        // Consume the graph up to and including base.
+        for step in graph.by_ref() {
+            if step
+                .as_resolved()
+                .is_some_and(|package| package.name == "base")
+            {
+                break;
+            }
+        }
+
+        // Simulate derived failing in a fork, which takes everything depending on it with it.
+        let mut fork = graph.snapshot();
+        fork.retain_with_dependents(|step| {
+            step.as_resolved()
+                .is_none_or(|package| package.name != "derived")
+        });
+
+        let remaining = names(graph.clone());
+        assert!(remaining.contains(&"derived"));
+        assert!(!remaining.contains(&"base"));
+
+        let forked = names(fork);
+        assert!(!forked.contains(&"derived"));
+        assert!(!forked.contains(&"second_order"));
+        assert!(!forked.contains(&"converged"));
+
+        // The real graph was left untouched by both.
+        assert_eq!(names(graph), remaining);
+    }
+}
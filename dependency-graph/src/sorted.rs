@@ -0,0 +1,114 @@
+use alloc::collections::BinaryHeap;
+use core::cmp::Reverse;
+
+use petgraph::stable_graph::NodeIndex;
+
+use crate::{schedule::Schedule, DependencyGraph, Node, Step};
+
+/// Non-consuming iterator which visits independent steps ordered by a key, see [`DependencyGraph::sorted_by_key`].
+pub struct SortedIter<'g, 'a, N: Node, F, K> {
+    schedule: Schedule<'g, 'a, N>,
+    key: F,
+    /// Ready steps, smallest key first. Ties are broken by node index.
+    ready: BinaryHeap<Reverse<(K, NodeIndex)>>,
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Like [`DependencyGraph::iter`], but whenever multiple steps are ready to be visited, the one with
+    /// the smallest `key` goes first. As long as keys are unique, the traversal order is fully determined
+    /// by the keys, regardless of the order in which the nodes were provided.
+    pub fn sorted_by_key<F, K>(&self, key: F) -> SortedIter<'_, 'a, N, F, K>
+    where
+        F: FnMut(&Step<'a, N>) -> K,
+        K: Ord,
+    {
+        let mut iter = SortedIter {
+            schedule: Schedule::new(self),
+            key,
+            ready: BinaryHeap::new(),
+        };
+
+        iter.enqueue_ready();
+        iter
+    }
+}
+
+impl<'g, 'a, N, F, K> SortedIter<'g, 'a, N, F, K>
+where
+    N: Node,
+    F: FnMut(&Step<'a, N>) -> K,
+    K: Ord,
+{
+    fn enqueue_ready(&mut self) {
+        while let Some(index) = self.schedule.pop() {
+            let key = (self.key)(&self.schedule.step(index));
+            self.ready.push(Reverse((key, index)));
+        }
+    }
+}
+
+impl<'g, 'a, N, F, K> Iterator for SortedIter<'g, 'a, N, F, K>
+where
+    N: Node,
+    F: FnMut(&Step<'a, N>) -> K,
+    K: Ord,
+{
+    type Item = Step<'a, N>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((_, index)) = self.ready.pop()?;
+        self.schedule.complete(index);
+        self.enqueue_ready();
+        Some(self.schedule.step(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{build_test_graph, Package};
+    use crate::{DependencyGraph, Step};
+
+    fn name(step: &Step<'_, Package>) -> &'static str {
+        match step {
+            Step::Resolved(package) => package.name,
+            Step::Unresolved(dependency) => dependency.name,
+        }
+    }
+
+    #[test]
+    fn test_sorted_order_is_independent_of_input_order() {
+        let packages = build_test_graph();
+        let mut reversed = build_test_graph();
+        reversed.reverse();
+
+        let forward = DependencyGraph::from(&packages[..]);
+        let backward = DependencyGraph::from(&reversed[..]);
+
+        let forward: Vec<_> = forward
+            .sorted_by_key(name)
+            .map(|step| name(&step))
+            .collect();
+        let backward: Vec<_> = backward
+            .sorted_by_key(name)
+            .map(|step| name(&step))
+            .collect();
+
+        assert_eq!(forward, backward);
+        assert_eq!(
+            forward,
+            vec![
+                "base",
+                "derived",
+                "converged",
+                "independent",
+                "remote",
+                "second_order",
+                "unknown",
+                "external"
+            ]
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use alloc::vec::Vec;
+
+use petgraph::{stable_graph::NodeIndex, Direction};
+
+use crate::{collections::HashMap, schedule::Schedule, DependencyGraph, Node, Step};
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Number of steps in the graph, including unresolved dependencies.
+    pub fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    /// Number of dependencies between steps in the graph.
+    pub fn edge_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    /// Steps which nothing else in the graph depends on.
+    pub fn roots(&self) -> impl Iterator<Item = Step<'a, N>> + '_ {
+        self.externals(Direction::Incoming)
+    }
+
+    /// Steps which have no dependencies of their own. This includes all unresolved dependencies.
+    pub fn leaves(&self) -> impl Iterator<Item = Step<'a, N>> + '_ {
+        self.externals(Direction::Outgoing)
+    }
+
+    /// Steps which can be visited right away, because nothing they depend on is left in the graph. While
+    /// consuming the graph as an [`Iterator`], this is what the traversal is able to yield next.
+    pub fn ready_nodes(&self) -> Vec<Step<'a, N>> {
+        self.leaves().collect()
+    }
+
+    /// The dependencies of `node` which are still part of the graph, and therefore keep it from being visited.
+    /// While consuming the graph as an [`Iterator`], dependencies which have already been yielded are not included.
+    /// The node is identified by reference, so it must be one of the nodes the graph was built from.
+    pub fn blocked_by(&self, node: &N) -> Vec<Step<'a, N>> {
+        match self.handle_of(node) {
+            Some(handle) => self
+                .graph
+                .neighbors_directed(handle.0, Direction::Outgoing)
+                .map(|dependency| self.graph[dependency])
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Length of the longest chain of dependencies in the graph, so a graph with no dependencies
+    /// between its nodes has a depth of 0. Steps which are part of (or depend on) a cycle are ignored.
+    pub fn max_depth(&self) -> usize {
+        self.depths().into_values().max().unwrap_or(0)
+    }
+
+    /// Computes the depth of each step, meaning the length of the longest chain of dependencies below it.
+    /// Steps which are part of (or depend on) a cycle have no depth, since they never become ready.
+    pub(crate) fn depths(&self) -> HashMap<NodeIndex, usize> {
+        let mut schedule = Schedule::new(self);
+        let mut depths = HashMap::new();
+
+        while let Some(index) = schedule.pop() {
+            let depth = self
+                .graph
+                .neighbors_directed(index, Direction::Outgoing)
+                .map(|dependency| depths[&dependency] + 1)
+                .max()
+                .unwrap_or(0);
+
+            depths.insert(index, depth);
+            schedule.complete(index);
+        }
+
+        depths
+    }
+
+    fn externals(&self, direction: Direction) -> impl Iterator<Item = Step<'a, N>> + '_ {
+        self.graph
+            .node_indices()
+            .filter(move |index| {
+                self.graph
+                    .neighbors_directed(*index, direction)
+                    .next()
+                    .is_none()
+            })
+            .map(move |index| self.graph[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::build_test_graph;
+    use crate::{DependencyGraph, Step};
+
+    fn names<'a>(
+        steps: impl Iterator<Item = Step<'a, crate::tests::Package>>,
+    ) -> Vec<&'static str> {
+        let mut names: Vec<_> = steps
+            .map(|step| match step {
+                Step::Resolved(package) => package.name,
+                Step::Unresolved(dependency) => dependency.name,
+            })
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn test_statistics() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+
+        assert_eq!(graph.node_count(), 8);
+        assert_eq!(graph.edge_count(), 6);
+        assert_eq!(graph.max_depth(), 2);
+        assert_eq!(
+            names(graph.roots()),
+            vec!["converged", "external", "independent", "second_order"]
+        );
+        assert_eq!(
+            names(graph.leaves()),
+            vec!["base", "independent", "remote", "unknown"]
+        );
+    }
+
+    #[test]
+    fn test_blocked_by() {
+        let packages = build_test_graph();
+        let mut graph = DependencyGraph::from(&packages[..]);
+        let converged = &packages[3];
+
+        assert_eq!(
+            names(graph.blocked_by(converged).into_iter()),
+            vec!["base", "derived"]
+        );
+
+        // Consume the graph up to and including base, which leaves derived ready.
+        for step in graph.by_ref() {
+            if step
+                .as_resolved()
+                .is_some_and(|package| package.name == "base")
+            {
+                break;
+            }
+        }
+
+        assert_eq!(
+            names(graph.blocked_by(converged).into_iter()),
+            vec!["derived"]
+        );
+        assert!(names(graph.ready_nodes().into_iter()).contains(&"derived"));
+    }
+}
@@ -0,0 +1,132 @@
+use alloc::vec::Vec;
+
+use petgraph::{stable_graph::NodeIndex, Direction};
+
+use crate::{collections::HashSet, schedule::Schedule, DependencyGraph, Node, Step};
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Returns a new graph containing only `target` and everything it (transitively) depends on.
+    /// The target is identified by reference, so it must be one of the nodes the graph was built from.
+    pub fn subgraph_for(&self, target: &N) -> Self {
+        self.for_target(|node| core::ptr::eq(node, target))
+    }
+
+    /// Returns a new graph containing only the nodes matching `predicate`, along with
+    /// everything they (transitively) depend on.
+    pub fn for_target<P>(&self, predicate: P) -> Self
+    where
+        P: Fn(&N) -> bool,
+    {
+        let targets: Vec<_> = self
+            .graph
+            .node_indices()
+            .filter(|index| self.graph[*index].as_resolved().is_some_and(&predicate))
+            .collect();
+
+        let reachable = self.reachable(targets, Direction::Outgoing);
+
+        let mut graph = self.graph.clone();
+        for index in self.graph.node_indices() {
+            if !reachable.contains(&index) {
+                graph.remove_node(index);
+            }
+        }
+
+        Self::from_graph(graph)
+    }
+
+    /// Everything `node` (transitively) depends on, in dependency order, not including `node` itself.
+    /// The node is identified by reference, so it must be one of the nodes the graph was built from,
+    /// otherwise nothing is returned.
+    pub fn transitive_dependencies_of(&self, node: &N) -> impl Iterator<Item = Step<'a, N>> + '_ {
+        let root = self.handle_of(node).map(|handle| handle.0);
+        let mut dependencies = self.reachable(root, Direction::Outgoing);
+        if let Some(root) = root {
+            dependencies.remove(&root);
+        }
+
+        let mut schedule = Schedule::new(self);
+        core::iter::from_fn(move || {
+            // Stop as soon as every dependency has been visited, rather than traversing the rest of the graph.
+            while !dependencies.is_empty() {
+                let index = schedule.pop()?;
+                schedule.complete(index);
+
+                if dependencies.remove(&index) {
+                    return Some(schedule.step(index));
+                }
+            }
+
+            None
+        })
+    }
+
+    /// Finds all nodes reachable from `roots` by following edges in the given `direction`, including the roots themselves.
+    /// Following [`Direction::Outgoing`] edges finds dependencies, while [`Direction::Incoming`] finds dependents.
+    pub(crate) fn reachable(
+        &self,
+        roots: impl IntoIterator<Item = NodeIndex>,
+        direction: Direction,
+    ) -> HashSet<NodeIndex> {
+        let mut visited = HashSet::new();
+        let mut stack: Vec<_> = roots.into_iter().collect();
+
+        while let Some(index) = stack.pop() {
+            if visited.insert(index) {
+                stack.extend(self.graph.neighbors_directed(index, direction));
+            }
+        }
+
+        visited
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::build_test_graph;
+    use crate::{DependencyGraph, Step};
+
+    fn names(graph: DependencyGraph<'_, crate::tests::Package>) -> Vec<&'static str> {
+        graph
+            .map(|step| match step {
+                Step::Resolved(package) => package.name,
+                Step::Unresolved(dependency) => dependency.name,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_subgraph_for() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+
+        let second_order = packages.iter().find(|p| p.name == "second_order").unwrap();
+        assert_eq!(
+            names(graph.subgraph_for(second_order)),
+            vec!["base", "derived", "second_order"]
+        );
+
+        let mut external = names(graph.for_target(|package| package.name == "external"));
+        external.sort_unstable();
+        assert_eq!(external, vec!["external", "remote", "unknown"]);
+    }
+
+    #[test]
+    fn test_transitive_dependencies_of() {
+        let packages = build_test_graph();
+        let graph = DependencyGraph::from(&packages[..]);
+
+        let converged = packages.iter().find(|p| p.name == "converged").unwrap();
+        let dependencies: Vec<_> = graph
+            .transitive_dependencies_of(converged)
+            .map(|step| step.as_resolved().unwrap().name)
+            .collect();
+        assert_eq!(dependencies, vec!["base", "derived"]);
+
+        let unrelated = build_test_graph();
+        assert_eq!(graph.transitive_dependencies_of(&unrelated[0]).count(), 0);
+    }
+}
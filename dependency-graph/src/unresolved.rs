@@ -0,0 +1,216 @@
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Display};
+
+use petgraph::{visit::EdgeRef, Direction};
+
+use crate::{DependencyGraph, Node};
+
+/// How dependencies which can't be resolved internally are treated, see [`DependencyGraph::with_unresolved`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unresolved {
+    /// Keep them in the graph as [`Step::Unresolved`](crate::Step::Unresolved), to be yielded during traversal.
+    /// This is what `DependencyGraph::from` does.
+    Yield,
+    /// Fail construction with an [`UnresolvedDependencies`] error.
+    Error,
+    /// Leave them out of the graph entirely, treating them as satisfied.
+    Ignore,
+}
+
+/// Returned by [`DependencyGraph::with_unresolved`] when using [`Unresolved::Error`].
+pub struct UnresolvedDependencies<'a, N: Node> {
+    /// Every dependency which could not be resolved, along with the node declaring it.
+    pub missing: Vec<(&'a N, &'a N::DependencyType)>,
+}
+
+impl<'a, N> Debug for UnresolvedDependencies<'a, N>
+where
+    N: Node + Debug,
+    N::DependencyType: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnresolvedDependencies")
+            .field("missing", &self.missing)
+            .finish()
+    }
+}
+
+impl<'a, N: Node> Display for UnresolvedDependencies<'a, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} dependencies could not be resolved",
+            self.missing.len()
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, N> std::error::Error for UnresolvedDependencies<'a, N>
+where
+    N: Node + Debug,
+    N::DependencyType: Debug,
+{
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Same as `DependencyGraph::from`, but unresolved dependencies are handled according to `behavior`,
+    /// so strict consumers can reject them upfront, and lenient ones never have to match on them.
+    pub fn with_unresolved(
+        nodes: &'a [N],
+        behavior: Unresolved,
+    ) -> Result<Self, UnresolvedDependencies<'a, N>> {
+        let mut graph = Self::from(nodes);
+
+        match behavior {
+            Unresolved::Yield => {}
+            Unresolved::Ignore => graph.prune_unresolved(),
+            Unresolved::Error => {
+                let missing = graph.unresolved_dependents();
+
+                if !missing.is_empty() {
+                    return Err(UnresolvedDependencies { missing });
+                }
+            }
+        }
+
+        Ok(graph)
+    }
+
+    /// Every unresolved dependency in the graph, along with the node declaring it.
+    pub(crate) fn unresolved_dependents(&self) -> Vec<(&'a N, &'a N::DependencyType)> {
+        let mut missing = Vec::new();
+
+        for index in self.graph.node_indices() {
+            if self.graph[index].as_unresolved().is_none() {
+                continue;
+            }
+
+            for edge in self.graph.edges_directed(index, Direction::Incoming) {
+                if let Some(dependent) = self.graph[edge.source()].as_resolved() {
+                    missing.push((dependent, *edge.weight()));
+                }
+            }
+        }
+
+        missing
+    }
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+    N::DependencyType: PartialEq,
+{
+    /// Same as `DependencyGraph::from`, but equal unresolved dependencies are merged into a single
+    /// [`Step::Unresolved`](crate::Step::Unresolved), rather than getting one for every node declaring
+    /// them. This way, a missing package depended upon by ten nodes is only yielded once, before any of the ten.
+    pub fn with_unresolved_merged(nodes: &'a [N]) -> Self {
+        let mut graph = Self::from(nodes);
+        let mut kept = Vec::new();
+
+        let unresolved: Vec<_> = graph
+            .graph
+            .node_indices()
+            .filter_map(|index| Some((index, graph.graph[index].as_unresolved()?)))
+            .collect();
+
+        for (index, dependency) in unresolved {
+            let existing = kept
+                .iter()
+                .find(|(_, other)| *other == dependency)
+                .map(|(existing, _)| *existing);
+
+            let existing = match existing {
+                Some(existing) => existing,
+                None => {
+                    kept.push((index, dependency));
+                    continue;
+                }
+            };
+
+            let dependents: Vec<_> = graph
+                .graph
+                .edges_directed(index, Direction::Incoming)
+                .map(|edge| (edge.source(), *edge.weight()))
+                .collect();
+
+            for (dependent, dependency) in dependents {
+                graph.graph.add_edge(dependent, existing, dependency);
+            }
+
+            graph.graph.remove_node(index);
+        }
+
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{build_test_graph, package};
+    use crate::{DependencyGraph, Step, Unresolved};
+
+    #[test]
+    fn test_with_unresolved() {
+        let packages = build_test_graph();
+
+        let yielded = DependencyGraph::with_unresolved(&packages[..], Unresolved::Yield).unwrap();
+        assert_eq!(yielded.unresolved_dependencies().count(), 2);
+
+        let ignored = DependencyGraph::with_unresolved(&packages[..], Unresolved::Ignore).unwrap();
+        assert!(ignored.is_internally_resolvable());
+        assert_eq!(ignored.count(), packages.len());
+
+        let error = match DependencyGraph::with_unresolved(&packages[..], Unresolved::Error) {
+            Ok(_) => panic!("unresolved dependencies were not reported"),
+            Err(error) => error,
+        };
+
+        let mut missing: Vec<_> = error
+            .missing
+            .iter()
+            .map(|(package, dependency)| (package.name, dependency.name))
+            .collect();
+        missing.sort_unstable();
+        assert_eq!(
+            missing,
+            vec![("external", "remote"), ("external", "unknown")]
+        );
+    }
+
+    #[test]
+    fn test_unresolved_merged() {
+        let packages = [
+            package("first", "1.0.0", &[("remote", "=2.0.0")]),
+            package("second", "1.0.0", &[("remote", "=2.0.0"), ("other", "*")]),
+            package("third", "1.0.0", &[("remote", ">=2.0.0")]),
+        ];
+
+        assert_eq!(
+            DependencyGraph::from(&packages[..])
+                .unresolved_dependencies()
+                .count(),
+            4
+        );
+
+        let graph = DependencyGraph::with_unresolved_merged(&packages[..]);
+        assert_eq!(graph.unresolved_dependencies().count(), 3);
+        assert_eq!(graph.edge_count(), 4);
+
+        let order: Vec<_> = graph
+            .map(|step| match step {
+                Step::Resolved(package) => package.name.to_string(),
+                Step::Unresolved(dependency) => dependency.to_string(),
+            })
+            .collect();
+
+        // The merged dependency is yielded before either of its dependents.
+        let position = |name: &str| order.iter().position(|step| step == name).unwrap();
+        assert!(position("remote =2.0.0") < position("first"));
+        assert!(position("remote =2.0.0") < position("second"));
+    }
+}
@@ -0,0 +1,151 @@
+use alloc::vec::Vec;
+use core::{
+    fmt::{self, Debug},
+    hash::Hash,
+};
+
+use crate::{duplicate::duplicates, scan, skeleton_with, Conflict, Cycle, DependencyGraph, Node};
+
+/// Every problem found by [`DependencyGraph::validate`], so they can all be reported at once.
+pub struct ValidationReport<'a, N: Node> {
+    /// Dependency cycles between two or more nodes. Nodes depending on themselves are
+    /// reported in `self_dependencies` instead.
+    pub cycles: Vec<Cycle<'a, N>>,
+    /// Groups of nodes sharing the same key, in the order they were provided.
+    pub duplicates: Vec<Vec<&'a N>>,
+    /// Dependencies matched by more than one node.
+    pub conflicts: Vec<Conflict<'a, N>>,
+    /// Dependencies which could not be resolved internally, along with the node declaring them.
+    pub unresolved: Vec<(&'a N, &'a N::DependencyType)>,
+    /// Dependencies which are matched by the very node declaring them.
+    pub self_dependencies: Vec<(&'a N, &'a N::DependencyType)>,
+}
+
+impl<'a, N: Node> ValidationReport<'a, N> {
+    /// True if no problems were found at all.
+    pub fn is_valid(&self) -> bool {
+        self.cycles.is_empty()
+            && self.duplicates.is_empty()
+            && self.conflicts.is_empty()
+            && self.unresolved.is_empty()
+            && self.self_dependencies.is_empty()
+    }
+}
+
+impl<'a, N> Debug for ValidationReport<'a, N>
+where
+    N: Node + Debug,
+    N::DependencyType: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ValidationReport")
+            .field("cycles", &self.cycles)
+            .field("duplicates", &self.duplicates)
+            .field("conflicts", &self.conflicts)
+            .field("unresolved", &self.unresolved)
+            .field("self_dependencies", &self.self_dependencies)
+            .finish()
+    }
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Checks `nodes` for every problem the stricter constructors would fail on, such as
+    /// [`DependencyGraph::try_from_nodes`], [`DependencyGraph::try_from_distinct`] and
+    /// [`DependencyGraph::try_from_unambiguous`], without stopping at the first one.
+    /// Nodes are considered duplicates if they produce the same `key`.
+    pub fn validate<F, K>(nodes: &'a [N], key: F) -> ValidationReport<'a, N>
+    where
+        F: Fn(&'a N) -> K,
+        K: Eq + Hash,
+    {
+        let mut conflicts = Vec::new();
+
+        let skeleton = skeleton_with(nodes, scan(nodes), |dependent, dependency, candidates| {
+            conflicts.push(Conflict {
+                dependent: &nodes[dependent],
+                dependency: &nodes[dependent].dependencies()[dependency],
+                candidates: candidates
+                    .iter()
+                    .map(|candidate| &nodes[*candidate])
+                    .collect(),
+            });
+            0
+        });
+
+        let graph = Self::from_skeleton(nodes, &skeleton);
+
+        let mut cycles = Vec::new();
+        let mut self_dependencies = Vec::new();
+        for cycle in graph.cycles() {
+            if cycle.nodes.len() == 1 {
+                self_dependencies.extend(
+                    cycle
+                        .edges
+                        .into_iter()
+                        .map(|(dependent, dependency, _)| (dependent, dependency)),
+                );
+            } else {
+                cycles.push(cycle);
+            }
+        }
+
+        ValidationReport {
+            cycles,
+            duplicates: duplicates(nodes, key),
+            conflicts,
+            unresolved: graph.unresolved_dependents(),
+            self_dependencies,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{build_test_graph, package};
+    use crate::DependencyGraph;
+
+    #[test]
+    fn test_validate() {
+        let packages = build_test_graph();
+        let report = DependencyGraph::validate(&packages[..], |package| package.name);
+
+        assert_eq!(report.unresolved.len(), 2);
+        assert!(report.cycles.is_empty());
+        assert!(report.duplicates.is_empty());
+        assert!(report.conflicts.is_empty());
+        assert!(report.self_dependencies.is_empty());
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn test_validate_reports_everything() {
+        let packages = [
+            package("base", "1.0.0", &[]),
+            package("base", "1.1.0", &[]),
+            package("derived", "1.0.0", &[("base", ">=1.0.0")]),
+            package("narcissist", "1.0.0", &[("narcissist", "=1.0.0")]),
+            package("left", "1.0.0", &[("right", ">=1.0.0")]),
+            package("right", "1.0.0", &[("left", ">=1.0.0")]),
+        ];
+
+        let report = DependencyGraph::validate(&packages[..], |package| package.name);
+
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(report.duplicates[0].len(), 2);
+
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].dependent.name, "derived");
+
+        assert_eq!(report.self_dependencies.len(), 1);
+        assert_eq!(report.self_dependencies[0].0.name, "narcissist");
+
+        assert_eq!(report.cycles.len(), 1);
+        assert_eq!(report.cycles[0].nodes.len(), 2);
+
+        assert!(report.unresolved.is_empty());
+        assert!(!report.is_valid());
+    }
+}
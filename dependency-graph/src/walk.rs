@@ -0,0 +1,139 @@
+use alloc::vec::Vec;
+
+use petgraph::{stable_graph::NodeIndex, visit::EdgeRef, Direction};
+
+use crate::{DependencyGraph, Node, Step};
+
+/// Callbacks for [`DependencyGraph::walk`]. All methods do nothing by default, so only the
+/// relevant ones need to be implemented. `depth` is 0 for roots, 1 for their direct dependencies and so on.
+pub trait Visitor<'a, N: Node> {
+    /// Called when entering a resolved node, before any of its dependencies are visited.
+    fn on_node(&mut self, _node: &'a N, _depth: usize) {}
+
+    /// Called when encountering a dependency which could not be resolved.
+    fn on_unresolved(&mut self, _dependency: &'a N::DependencyType, _depth: usize) {}
+
+    /// Called for each dependency of `dependent`, right before the dependency itself is visited at `depth + 1`.
+    fn on_edge(&mut self, _dependent: &'a N, _dependency: &'a N::DependencyType, _depth: usize) {}
+
+    /// Called when leaving a step, once all of its dependencies have been visited.
+    fn on_exit(&mut self, _step: Step<'a, N>, _depth: usize) {}
+}
+
+impl<'a, N> DependencyGraph<'a, N>
+where
+    N: Node,
+{
+    /// Walks the graph as a tree, starting from each root (see [`DependencyGraph::roots`]), descending
+    /// into dependencies in the order they are declared. Steps depended upon by multiple nodes are
+    /// visited once for each of them, like in `cargo tree`.
+    ///
+    /// Dependencies leading back into a step which is currently being visited are reported through
+    /// [`Visitor::on_edge`], but not descended into. Cycles which no root depends on are not visited at all.
+    pub fn walk<V>(&self, visitor: &mut V)
+    where
+        V: Visitor<'a, N>,
+    {
+        let mut path = Vec::new();
+
+        for index in self.graph.node_indices() {
+            if self
+                .graph
+                .neighbors_directed(index, Direction::Incoming)
+                .next()
+                .is_none()
+            {
+                self.visit(index, 0, &mut path, visitor);
+            }
+        }
+    }
+
+    fn visit<V>(&self, index: NodeIndex, depth: usize, path: &mut Vec<NodeIndex>, visitor: &mut V)
+    where
+        V: Visitor<'a, N>,
+    {
+        let node = match self.graph[index] {
+            Step::Resolved(node) => node,
+            Step::Unresolved(dependency) => {
+                visitor.on_unresolved(dependency, depth);
+                visitor.on_exit(self.graph[index], depth);
+                return;
+            }
+        };
+
+        visitor.on_node(node, depth);
+        path.push(index);
+
+        let mut edges: Vec<_> = self
+            .graph
+            .edges_directed(index, Direction::Outgoing)
+            .map(|edge| (*edge.weight(), edge.target()))
+            .collect();
+        edges.sort_by_key(|(dependency, _)| {
+            node.dependencies()
+                .iter()
+                .position(|declared| core::ptr::eq(declared, *dependency))
+        });
+
+        for (dependency, target) in edges {
+            visitor.on_edge(node, dependency, depth);
+            if !path.contains(&target) {
+                self.visit(target, depth + 1, path, visitor);
+            }
+        }
+
+        path.pop();
+        visitor.on_exit(self.graph[index], depth);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{package, Dependency, Package};
+    use crate::{DependencyGraph, Visitor};
+
+    /// Renders the graph like `cargo tree`.
+    struct Tree(Vec<String>);
+
+    impl<'a> Visitor<'a, Package> for Tree {
+        fn on_node(&mut self, node: &'a Package, depth: usize) {
+            self.0.push(format!("{}{}", "  ".repeat(depth), node));
+        }
+
+        fn on_unresolved(&mut self, dependency: &'a Dependency, depth: usize) {
+            self.0
+                .push(format!("{}{} (missing)", "  ".repeat(depth), dependency));
+        }
+    }
+
+    #[test]
+    fn test_walk() {
+        let packages = [
+            package("base", "1.0.0", &[]),
+            package("derived", "1.0.0", &[("base", ">=1.0.0")]),
+            package(
+                "app",
+                "1.0.0",
+                &[
+                    ("derived", ">=1.0.0"),
+                    ("base", ">=1.0.0"),
+                    ("remote", "=2.0.0"),
+                ],
+            ),
+        ];
+
+        let mut tree = Tree(Vec::new());
+        DependencyGraph::from(&packages[..]).walk(&mut tree);
+
+        assert_eq!(
+            tree.0,
+            vec![
+                "app@1.0.0",
+                "  derived@1.0.0",
+                "    base@1.0.0",
+                "  base@1.0.0",
+                "  remote =2.0.0 (missing)",
+            ]
+        );
+    }
+}
@@ -9,6 +9,6 @@ impl Node for BuildSpec {
     }
 
     fn matches(&self, dependency: &Self::DependencyType) -> bool {
-        dependency.name == self.name && dependency.version.matches(&self.version)
+        dependency.name == self.name && dependency.matches(&self.version)
     }
 }
@@ -0,0 +1,592 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read},
+    path::{Component, Path, PathBuf},
+    process::{self, ExitStatus, Stdio},
+    thread,
+    time::Instant,
+};
+
+use log::info;
+use thiserror::Error;
+
+use crate::{
+    cache::{self, Artifact, Cache, Package},
+    identifier,
+    logs::{self, BuildLog},
+    output::Reporter,
+    registry::is_artifact_name,
+    target,
+};
+
+use super::{
+    executor::{Container, Executor},
+    sandbox::{Environment, Sandbox, Session},
+    spec::{BuildSpec, BuildStep, Command, Hooks},
+    timings::{Phase, Timings},
+};
+
+/// Directory within the build directory which the artifacts of dependencies are linked into.
+pub const DEPS: &str = "deps";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    IO(#[from] std::io::Error),
+    #[error("cache error: {0}")]
+    Cache(#[from] cache::Error),
+    #[error("empty command")]
+    EmptyCommand,
+    #[error("command `{0}` failed: {1}, see {log} for its output", log = .2.display())]
+    Failed(String, ExitStatus, PathBuf),
+    #[error("invalid artifact pattern: {0}")]
+    Pattern(#[from] glob::PatternError),
+    #[error("failed to collect artifact: {0}")]
+    Glob(#[from] glob::GlobError),
+    #[error("no artifacts matching {0}")]
+    MissingArtifact(String),
+    #[error("artifact pattern {0} matched {path}, which is outside the build directory", path = .1.display())]
+    OutsideBuildDirectory(String, PathBuf),
+    #[error("artifact {0} of {1} points outside of its package")]
+    InvalidDependencyArtifact(String, String),
+}
+
+/// Runs the builds of individual specs, and stores the results in the cache.
+pub struct Engine<'c> {
+    cache: &'c Cache,
+    build_directory: PathBuf,
+    reporter: Reporter,
+    /// Global hooks, which run for every spec.
+    hooks: &'c Hooks,
+    timings: Option<&'c Timings>,
+    sandbox: Sandbox,
+    executor: Executor,
+    environment: Environment,
+    /// Directory the output of every build is logged to, see [`BuildLog`].
+    logs: PathBuf,
+}
+
+impl<'c> Engine<'c> {
+    pub fn new(
+        cache: &'c Cache,
+        build_directory: PathBuf,
+        reporter: Reporter,
+        hooks: &'c Hooks,
+        timings: Option<&'c Timings>,
+    ) -> Self {
+        Engine {
+            cache,
+            build_directory,
+            reporter,
+            hooks,
+            timings,
+            sandbox: Sandbox::Off,
+            executor: Executor::Local,
+            environment: Environment::default(),
+            logs: PathBuf::from(logs::LOGS),
+        }
+    }
+
+    /// Runs build steps in `sandbox`, see [`Sandbox`].
+    pub fn with_sandbox(mut self, sandbox: Sandbox) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// Runs the build steps of specs with a `builder_image` using `executor`, see [`Executor`].
+    pub fn with_executor(mut self, executor: Executor) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    /// Runs build steps in `environment`, see [`Environment`].
+    pub fn with_environment(mut self, environment: Environment) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    /// Directory in which the given spec is built. Artifacts are collected from here
+    /// once the build has finished. Target specific specs get a directory for each target.
+    pub fn directory(&self, spec: &BuildSpec) -> PathBuf {
+        let directory = spec
+            .name
+            .split(identifier::SEPARATOR)
+            .fold(self.build_directory.clone(), |path, segment| {
+                path.join(segment)
+            })
+            .join(spec.version.to_string());
+
+        match spec.package_target() {
+            Some(target) => directory.join(target),
+            None => directory,
+        }
+    }
+
+    /// Builds `spec` by running each of its steps, and puts the resulting artifacts into the cache.
+    /// The spec's hooks and the global ones run before and after, see [`Hooks`]. The artifacts of
+    /// `dependencies` are made available to the steps in the `deps` directory, see [`BuildSpec::dependencies`].
+    /// The output of every step and hook is written to a new log of the build, see [`BuildLog`].
+    pub fn build(&self, spec: &BuildSpec, dependencies: &[Package]) -> Result<Package, Error> {
+        let started = Instant::now();
+        let log = BuildLog::create(&self.logs, &spec.name, &spec.version)?;
+        let directory = self.directory(spec);
+        let session = self.sandbox.enter(&spec.to_string(), &directory)?;
+        std::fs::create_dir_all(&directory)?;
+        let directory = directory.canonicalize()?;
+        let dependencies = materialize(&directory, dependencies)?;
+
+        let mut metadata = dependencies.clone();
+        metadata.insert("ORCA_PACKAGE_NAME".to_string(), spec.name.clone());
+        metadata.insert("ORCA_PACKAGE_VERSION".to_string(), spec.version.to_string());
+        metadata.insert(
+            "ORCA_TARGET".to_string(),
+            spec.target.clone().unwrap_or_else(target::host),
+        );
+        metadata.insert(
+            "ORCA_BUILD_DIRECTORY".to_string(),
+            directory.to_string_lossy().to_string(),
+        );
+
+        let pre_build = self.hooks.pre_build.iter().chain(&spec.hooks.pre_build);
+        self.hook(spec, "pre_build", pre_build, &directory, &metadata, &log)?;
+
+        let environment = self.environment.of(spec);
+        let mut env = environment.env.clone();
+        env.extend(dependencies);
+
+        let context = Context {
+            directory: &directory,
+            env,
+            environment: Some(environment),
+            log: &log,
+            session: session.as_ref(),
+            container: match &spec.builder_image {
+                Some(image) if self.executor != Executor::Local => Some(Container {
+                    image,
+                    directory: &directory,
+                    mounts: vec![self.cache.path()],
+                }),
+                _ => None,
+            },
+        };
+
+        for step in &spec.steps {
+            self.run(spec, step, &context)?;
+        }
+
+        let artifacts = collect(spec, &directory)?;
+
+        let paths: Vec<_> = artifacts
+            .iter()
+            .map(|artifact| artifact.path.to_string_lossy().to_string())
+            .collect();
+        metadata.insert("ORCA_ARTIFACTS".to_string(), paths.join("\n"));
+
+        let post_build = spec.hooks.post_build.iter().chain(&self.hooks.post_build);
+        self.hook(spec, "post_build", post_build, &directory, &metadata, &log)?;
+
+        let package = Package {
+            name: identifier::parse_name(&spec.name).map_err(cache::Error::from)?,
+            version: spec.version.clone(),
+            target: spec.package_target().map(String::from),
+            signature: None,
+            description: spec.description.clone(),
+            dependencies: spec
+                .dependencies
+                .iter()
+                .map(|dependency| (dependency.name.clone(), dependency.version.clone()))
+                .collect(),
+            yanked: false,
+            artifacts,
+        };
+        self.record(spec, Phase::Build, started);
+
+        let started = Instant::now();
+        let package = self.cache.put(&package)?;
+        self.record(spec, Phase::Store, started);
+
+        Ok(package)
+    }
+
+    fn record(&self, spec: &BuildSpec, phase: Phase, started: Instant) {
+        if let Some(timings) = self.timings {
+            timings.record(&spec.to_string(), phase, started);
+        }
+    }
+
+    /// Runs the given hook commands in order, exposing the build's metadata to them.
+    fn hook<'h, I>(
+        &self,
+        spec: &BuildSpec,
+        hook: &str,
+        commands: I,
+        directory: &Path,
+        metadata: &HashMap<String, String>,
+        log: &BuildLog,
+    ) -> Result<(), Error>
+    where
+        I: Iterator<Item = &'h Command>,
+    {
+        let mut env = metadata.clone();
+        env.insert("ORCA_HOOK".to_string(), hook.to_string());
+
+        let context = Context {
+            directory,
+            env,
+            environment: None,
+            log,
+            session: None,
+            container: None,
+        };
+
+        for command in commands {
+            let step = BuildStep {
+                run: command.clone(),
+                working_directory: None,
+                env: HashMap::new(),
+            };
+            self.run(spec, &step, &context)?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs a single build step within `context`, streaming its output to the build log, and to the reporter
+    /// prefixed with the name of the package.
+    fn run(&self, spec: &BuildSpec, step: &BuildStep, context: &Context) -> Result<(), Error> {
+        let (program, arguments) = match &step.run {
+            Command::Shell(script) => ("sh", vec!["-c", script.as_str()]),
+            Command::Exec(arguments) => {
+                let (program, arguments) = arguments.split_first().ok_or(Error::EmptyCommand)?;
+                (
+                    program.as_str(),
+                    arguments.iter().map(String::as_str).collect(),
+                )
+            }
+        };
+
+        let working_directory = match &step.working_directory {
+            Some(path) => context.directory.join(path),
+            None => context.directory.to_path_buf(),
+        };
+
+        let mut env = context.env.clone();
+        env.extend(spec.env.clone());
+        env.extend(step.env.clone());
+
+        let containerized = context.container.as_ref().and_then(|container| {
+            self.executor
+                .command(container, &working_directory, &env, program, &arguments)
+        });
+        let mut command = match (containerized, context.session) {
+            (Some(command), _) => command,
+            (None, Some(session)) => session.command(program, &arguments),
+            (None, None) => {
+                let mut command = process::Command::new(program);
+                command.args(&arguments);
+                command
+            }
+        };
+        if let (Some(environment), None) = (&context.environment, &context.container) {
+            environment.isolate(&mut command, context.session.is_some());
+        }
+
+        info!("[{}] running `{}`", spec.name, step.run);
+        context.log.write(&format!("$ {}", step.run));
+        let mut child = command
+            .current_dir(&working_directory)
+            .envs(&env)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let stdout = stream(
+            &self.reporter,
+            context.log,
+            &spec.name,
+            child.stdout.take(),
+            false,
+        );
+        let stderr = stream(
+            &self.reporter,
+            context.log,
+            &spec.name,
+            child.stderr.take(),
+            true,
+        );
+        let status = child.wait()?;
+
+        for output in vec![stdout, stderr].into_iter().flatten() {
+            // The output threads only fail if printing does, which isn't worth failing the build over.
+            let _ = output.join();
+        }
+
+        if status.success() {
+            Ok(())
+        } else {
+            Err(Error::Failed(
+                step.run.to_string(),
+                status,
+                context.log.path().to_path_buf(),
+            ))
+        }
+    }
+}
+
+/// Where and how the commands of a single build run.
+struct Context<'a> {
+    directory: &'a Path,
+    /// Variables set for every command, which the spec's and the step's own variables take precedence over.
+    env: HashMap<String, String>,
+    /// Which host variables commands don't inherit, or `None` to inherit all of them, as hooks do.
+    environment: Option<Environment>,
+    /// The log of the build, which every command's output is written to.
+    log: &'a BuildLog,
+    /// The sandbox commands run in, unless there is no sandbox.
+    session: Option<&'a Session>,
+    /// The container commands run in, in place of the sandbox.
+    container: Option<Container<'a>>,
+}
+
+/// Links the artifacts of every one of `dependencies` into the `deps` directory of the build directory, under
+/// the name of the package, returning the variables which describe them to the build.
+fn materialize(
+    directory: &Path,
+    dependencies: &[Package],
+) -> Result<HashMap<String, String>, Error> {
+    let deps = directory.join(DEPS);
+    if deps.exists() {
+        std::fs::remove_dir_all(&deps)?;
+    }
+
+    let mut env = HashMap::new();
+    env.insert(
+        "ORCA_DEPS_DIR".to_string(),
+        deps.to_string_lossy().to_string(),
+    );
+
+    for package in dependencies {
+        let package_directory = package
+            .name
+            .iter()
+            .fold(deps.clone(), |path, segment| path.join(segment));
+        std::fs::create_dir_all(&package_directory)?;
+
+        for artifact in &package.artifacts {
+            if !is_artifact_name(&artifact.name) {
+                return Err(Error::InvalidDependencyArtifact(
+                    artifact.name.clone(),
+                    package.to_string(),
+                ));
+            }
+
+            let link = package_directory.join(&artifact.name);
+            if let Some(parent) = link.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            symlink(&artifact.path, &link)?;
+        }
+
+        let variable = variable_name(&package.name.join("_"));
+        env.insert(
+            format!("ORCA_DEP_{}_DIR", variable),
+            package_directory.to_string_lossy().to_string(),
+        );
+        env.insert(
+            format!("ORCA_DEP_{}_VERSION", variable),
+            package.version.to_string(),
+        );
+    }
+
+    Ok(env)
+}
+
+/// Name of a package as it appears in environment variables, such as `ORG_LIB_FOO` for `org/lib-foo`.
+fn variable_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn symlink(original: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+#[cfg(windows)]
+fn symlink(original: &Path, link: &Path) -> std::io::Result<()> {
+    if original.is_dir() {
+        std::os::windows::fs::symlink_dir(original, link)
+    } else {
+        std::os::windows::fs::symlink_file(original, link)
+    }
+}
+
+/// Finds the artifacts declared by `spec` within the build directory, named after their paths relative to it, such as
+/// `lib/libfoo.so`. Every declared pattern must match at least one file, and nothing outside the build directory.
+/// Without any declared patterns, everything but the dependencies in `deps` is collected.
+fn collect(spec: &BuildSpec, directory: &Path) -> Result<Vec<Artifact>, Error> {
+    if spec.artifacts.is_empty() {
+        let mut artifacts = cache::list_artifacts(directory)?;
+        artifacts.retain(|artifact| artifact.name != DEPS);
+        return Ok(artifacts);
+    }
+
+    let mut artifacts = Vec::new();
+    for pattern in &spec.artifacts {
+        let full_pattern = directory.join(pattern);
+        let matches = glob::glob(&full_pattern.to_string_lossy())?
+            .map(|path| relative_artifact(directory, pattern, path?))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        if matches.is_empty() {
+            return Err(Error::MissingArtifact(pattern.clone()));
+        }
+
+        artifacts.extend(matches);
+    }
+
+    Ok(artifacts)
+}
+
+/// The artifact at `path`, which `pattern` matched, named after its path relative to the build `directory`.
+fn relative_artifact(directory: &Path, pattern: &str, path: PathBuf) -> Result<Artifact, Error> {
+    let outside = || Error::OutsideBuildDirectory(pattern.to_string(), path.clone());
+    let relative = path.strip_prefix(directory).map_err(|_| outside())?;
+
+    let segments = relative
+        .components()
+        .map(|component| match component {
+            Component::Normal(segment) => Ok(segment.to_string_lossy()),
+            _ => Err(outside()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    // A pattern matching the build directory itself would name the artifact after nothing at all.
+    if segments.is_empty() {
+        return Err(outside());
+    }
+
+    let name = segments.join("/");
+    Ok(Artifact {
+        name,
+        path,
+        digest: None,
+    })
+}
+
+/// Forwards every line of `output` to `reporter` from a separate thread, see [`Reporter::output`].
+fn stream<R: Read + Send + 'static>(
+    reporter: &Reporter,
+    log: &BuildLog,
+    name: &str,
+    output: Option<R>,
+    stderr: bool,
+) -> Option<thread::JoinHandle<()>> {
+    let output = output?;
+    let reporter = reporter.clone();
+    let log = log.clone();
+    let name = name.to_string();
+
+    Some(thread::spawn(move || {
+        for line in BufReader::new(output).lines().map_while(Result::ok) {
+            log.write(&line);
+            reporter.output(&name, &line, stderr);
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use semver::Version;
+
+    use super::{collect, materialize, variable_name};
+    use crate::build::spec::BuildSpec;
+    use crate::cache::{Artifact, Package};
+
+    #[test]
+    fn test_materialize() {
+        let root = std::env::temp_dir().join(format!("orca-deps-test-{}", std::process::id()));
+        let build = root.join("build");
+        std::fs::create_dir_all(&build).unwrap();
+        std::fs::write(root.join("libfoo.so"), b"foo").unwrap();
+
+        let dependency = Package {
+            name: vec!["org".to_string(), "lib-foo".to_string()],
+            version: Version::parse("1.2.0").unwrap(),
+            target: None,
+            signature: None,
+            description: None,
+            dependencies: BTreeMap::new(),
+            yanked: false,
+            artifacts: vec![Artifact {
+                name: "lib/libfoo.so".to_string(),
+                path: root.join("libfoo.so"),
+                digest: None,
+            }],
+        };
+
+        // Leftovers of an earlier build are replaced.
+        std::fs::create_dir_all(build.join("deps/old")).unwrap();
+        let env = materialize(&build, &[dependency]).unwrap();
+
+        assert!(!build.join("deps/old").exists());
+        assert_eq!(
+            std::fs::read(build.join("deps/org/lib-foo/lib/libfoo.so")).unwrap(),
+            b"foo"
+        );
+        assert_eq!(
+            env["ORCA_DEP_ORG_LIB_FOO_DIR"],
+            build.join("deps/org/lib-foo").to_string_lossy()
+        );
+        assert_eq!(env["ORCA_DEP_ORG_LIB_FOO_VERSION"], "1.2.0");
+        assert_eq!(variable_name("zlib"), "ZLIB");
+
+        let mut escaping = dependency.clone();
+        escaping.artifacts[0].name = "../../escaped.so".to_string();
+        assert!(materialize(&build, &[escaping]).is_err());
+        assert!(!build.join("escaped.so").exists());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_collect() {
+        let root = std::env::temp_dir().join(format!("orca-collect-test-{}", std::process::id()));
+        let build = root.join("build");
+        std::fs::create_dir_all(build.join("lib")).unwrap();
+        std::fs::create_dir_all(build.join("bin")).unwrap();
+        std::fs::write(build.join("lib/foo"), b"lib").unwrap();
+        std::fs::write(build.join("bin/foo"), b"bin").unwrap();
+        std::fs::write(root.join("outside"), b"outside").unwrap();
+
+        let spec = |artifacts: &str| -> BuildSpec {
+            serde_yaml::from_str(&format!(
+                "name: foo\nversion: 1.0.0\nartifacts: {}",
+                artifacts
+            ))
+            .unwrap()
+        };
+
+        // Artifacts with the same file name in different directories keep apart.
+        let mut names: Vec<_> = collect(&spec("['*/foo']"), &build)
+            .unwrap()
+            .into_iter()
+            .map(|artifact| artifact.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["bin/foo", "lib/foo"]);
+
+        assert!(collect(&spec("['../outside']"), &build).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,159 @@
+use std::{collections::HashMap, path::Path, process, str::FromStr};
+
+use serde::Deserialize;
+
+/// Where build steps run, configured using `executor` in the config or `--executor`. Specs which declare a
+/// `builder_image` run their steps inside a container of that image when using a container engine, while specs
+/// without one always run on the host.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Executor {
+    /// Steps run on the host, ignoring any `builder_image`.
+    #[default]
+    Local,
+    Docker,
+    Podman,
+}
+
+impl FromStr for Executor {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "local" => Ok(Executor::Local),
+            "docker" => Ok(Executor::Docker),
+            "podman" => Ok(Executor::Podman),
+            other => Err(format!("unknown executor: {}", other)),
+        }
+    }
+}
+
+/// Everything a step running in a container has access to. Directories are mounted at the same paths they have on
+/// the host, so paths such as `ORCA_BUILD_DIRECTORY` mean the same thing inside the container.
+pub struct Container<'a> {
+    pub image: &'a str,
+    /// The build directory, which is mounted read-write.
+    pub directory: &'a Path,
+    /// Directories steps can read from, such as the cache holding dependency artifacts.
+    pub mounts: Vec<&'a Path>,
+}
+
+impl Executor {
+    fn program(self) -> Option<&'static str> {
+        match self {
+            Executor::Local => None,
+            Executor::Docker => Some("docker"),
+            Executor::Podman => Some("podman"),
+        }
+    }
+
+    /// A command running `program` with `arguments` inside `container` using the container engine, or `None`
+    /// if steps run on the host. The engine itself runs with the host's environment, while the step only sees
+    /// the variables in `env`.
+    pub fn command(
+        self,
+        container: &Container,
+        working_directory: &Path,
+        env: &HashMap<String, String>,
+        program: &str,
+        arguments: &[&str],
+    ) -> Option<process::Command> {
+        let mut command = process::Command::new(self.program()?);
+        command.args(&["run", "--rm", "--init"]);
+
+        command
+            .arg("--volume")
+            .arg(format!("{0}:{0}", container.directory.display()));
+        for mount in &container.mounts {
+            command
+                .arg("--volume")
+                .arg(format!("{0}:{0}:ro", mount.display()));
+        }
+
+        // Rootless podman maps root in the container to the current user already, while docker has to be told
+        // to run as the owner of the build directory, or it leaves behind files owned by root.
+        #[cfg(unix)]
+        if self == Executor::Docker {
+            use std::os::unix::fs::MetadataExt;
+
+            if let Ok(metadata) = std::fs::metadata(container.directory) {
+                command
+                    .arg("--user")
+                    .arg(format!("{}:{}", metadata.uid(), metadata.gid()));
+            }
+        }
+
+        let mut variables: Vec<_> = env.iter().collect();
+        variables.sort();
+        for (key, value) in variables {
+            command.arg("--env").arg(format!("{}={}", key, value));
+        }
+
+        command
+            .arg("--workdir")
+            .arg(working_directory)
+            .arg(container.image)
+            .arg(program)
+            .args(arguments);
+
+        Some(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::Path};
+
+    use super::{Container, Executor};
+
+    #[test]
+    fn test_container_command() {
+        let container = Container {
+            image: "rust:1.75",
+            directory: Path::new("/build/app"),
+            mounts: vec![Path::new("/cache")],
+        };
+        let mut env = HashMap::new();
+        env.insert("CARGO_HOME".to_string(), "/build/app/.cargo".to_string());
+
+        let command = |executor: Executor| {
+            executor.command(
+                &container,
+                Path::new("/build/app/src"),
+                &env,
+                "sh",
+                &["-c", "cargo build"],
+            )
+        };
+
+        assert!(command(Executor::Local).is_none());
+
+        let podman = command(Executor::Podman).unwrap();
+        let arguments: Vec<_> = podman
+            .get_args()
+            .map(|argument| argument.to_string_lossy().to_string())
+            .collect();
+
+        assert_eq!(podman.get_program(), "podman");
+        assert_eq!(
+            arguments,
+            vec![
+                "run",
+                "--rm",
+                "--init",
+                "--volume",
+                "/build/app:/build/app",
+                "--volume",
+                "/cache:/cache:ro",
+                "--env",
+                "CARGO_HOME=/build/app/.cargo",
+                "--workdir",
+                "/build/app/src",
+                "rust:1.75",
+                "sh",
+                "-c",
+                "cargo build",
+            ]
+        );
+    }
+}
@@ -1,22 +1,582 @@
 mod deps;
-mod spec;
+mod engine;
+pub mod executor;
+pub mod overrides;
+mod plan;
+mod resolve;
+pub mod sandbox;
+pub mod sources;
+pub mod spec;
+pub mod template;
+pub mod timings;
+mod watch;
 
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{mpsc, Mutex},
+    thread,
+    time::Instant,
+};
 
 use clap::Clap;
+use dependency_graph::{DependencyGraph, NodeHandle, Step};
 use indoc::indoc;
+use log::error;
+use thiserror::Error;
 
-use crate::Opts;
+use crate::{
+    cache::{self, Cache, Package},
+    config::Config,
+    lockfile::{self, Lockfile, LOCKFILE},
+    output::{OutputFormat, Reporter, Verbosity},
+    registry::{self, Registries},
+    target,
+};
+
+use engine::Engine;
+use executor::Executor;
+use overrides::Override;
+pub(crate) use resolve::Resolver;
+use sandbox::{Environment, Sandbox};
+use spec::{BuildSpec, Format, Hooks};
+use template::Variables;
+use timings::{Timings, TimingsFormat};
+use watch::Watcher;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}")]
+    Spec(#[from] spec::Error),
+    #[error("cache error: {0}")]
+    Cache(#[from] cache::Error),
+    #[error("registry error: {0}")]
+    Registry(#[from] registry::Error),
+    #[error("{0}")]
+    Lockfile(#[from] lockfile::Error),
+    #[error("{0}")]
+    Template(#[from] template::Error),
+    #[error("{0}")]
+    Override(#[from] overrides::Error),
+    #[error("{0}")]
+    Source(#[from] sources::Error),
+    #[error("no package in the cache or registry satisfies {0}")]
+    Unresolved(String),
+    #[error("{0} is not in the lockfile, run without --locked to update it")]
+    NotLocked(String),
+    #[error("resolution does not match the lockfile: {0}")]
+    LockMismatch(String),
+    #[error("failed to build {package}: {source}")]
+    Step {
+        package: String,
+        source: engine::Error,
+    },
+    #[error("{failed} packages failed, and {skipped} depending on them were skipped")]
+    Incomplete { failed: usize, skipped: usize },
+}
 
 #[derive(Clap)]
 pub struct BuildCmd {
-    #[clap(long, about = "Path(s) to one or more BuildSpec json files.", long_about = indoc!{"
-        A BuildSpec Json file contains one or more build specifications.
+    #[clap(long, about = "Path(s) to one or more BuildSpec files.", long_about = indoc!{"
+        A BuildSpec file contains one or more build specifications, written in json, yaml or toml.
         Multiple BuildSpec files can be provided, and dependencies will be resolved automatically.
+        Glob patterns such as `specs/**/*.json` are expanded, and directories are scanned recursively.
+        Defaults to the workspace members listed in orca.toml.
     "})]
     pub spec: Vec<String>,
+    #[clap(long, possible_values = &["json", "yaml", "toml"], about = "Format of the spec files, instead of guessing it from their extensions.")]
+    pub format: Option<Format>,
+    #[clap(
+        long,
+        parse(from_os_str),
+        default_value = ".orca/build",
+        about = "Directory in which packages are built."
+    )]
+    pub build_directory: PathBuf,
+    #[clap(
+        long,
+        about = "Fail unless dependencies resolve to exactly the packages in orca.lock."
+    )]
+    pub locked: bool,
+    #[clap(
+        long,
+        short,
+        about = "Number of packages to build at the same time, defaults to the number of CPUs."
+    )]
+    pub jobs: Option<usize>,
+    #[clap(
+        long,
+        about = "Print what would be built and fetched, without doing either."
+    )]
+    pub dry_run: bool,
+    #[clap(
+        long = "define",
+        short = 'D',
+        parse(try_from_str = template::parse_define),
+        about = "Define a variable for use as ${KEY} in the spec files, as KEY=VALUE."
+    )]
+    pub defines: Vec<(String, String)>,
+    #[clap(
+        long,
+        about = "Target to build for, such as linux-x86_64. Defaults to the host."
+    )]
+    pub target: Option<String>,
+    #[clap(
+        long = "override",
+        parse(try_from_str = overrides::parse_override),
+        about = "Resolve a dependency to a pinned version or a local spec instead, as NAME=VERSION or NAME=PATH."
+    )]
+    pub overrides: Vec<(String, Override)>,
+    #[clap(
+        long,
+        short,
+        about = "Only build the named package(s) and their dependencies, instead of every spec."
+    )]
+    pub package: Vec<String>,
+    #[clap(
+        long,
+        possible_values = &["json", "html"],
+        about = "Write a report of how long each package took into the build directory, as json or an html timeline."
+    )]
+    pub timings: Option<TimingsFormat>,
+    #[clap(
+        long,
+        about = "Run build steps with the host's environment and whatever is left in the build directory."
+    )]
+    pub no_sandbox: bool,
+    #[clap(
+        long,
+        possible_values = &["local", "docker", "podman"],
+        about = "Run the steps of specs with a builder_image in a container using docker or podman, or on the host."
+    )]
+    pub executor: Option<Executor>,
+    #[clap(
+        long,
+        conflicts_with = "dry-run",
+        about = "Keep running, and rebuild packages whenever their spec files or sources change, along with everything depending on them."
+    )]
+    pub watch: bool,
+    #[clap(
+        long,
+        about = "Let pre-releases such as 1.0.0-rc.1 satisfy the requirements of every dependency."
+    )]
+    pub include_prereleases: bool,
+    #[clap(
+        long,
+        about = "Keep building the packages which don't depend on a failed one, instead of stopping at the first failure."
+    )]
+    pub keep_going: bool,
 }
 
 impl BuildCmd {
-    pub(crate) fn execute(&self, opts: &Opts) {}
+    pub(crate) fn execute(&self, config: &Config) -> Result<(), Error> {
+        let target = self.target.clone().unwrap_or_else(target::host);
+        if self.watch {
+            return self.rebuild_on_change(config, &target);
+        }
+
+        let specs = self.load(config, &target)?;
+        self.build(config, &target, &specs, &mut HashMap::new())
+    }
+
+    /// Builds everything once, and then again every time something changes. Packages which aren't affected by
+    /// the changes are reused from the previous build, and failures only stop the current build, not the watch.
+    fn rebuild_on_change(&self, config: &Config, target: &str) -> Result<(), Error> {
+        let arguments = config.specs(&self.spec);
+        let mut watcher = Watcher::default();
+        let mut built = HashMap::new();
+        // Changes are kept around until the specs load again, so nothing is missed while they're broken.
+        let mut changed = Vec::new();
+
+        loop {
+            match self.load(config, target) {
+                Ok(specs) => {
+                    for package in watch::affected(&specs, &changed) {
+                        built.remove(&package);
+                    }
+                    changed.clear();
+
+                    watcher.watch(watch::paths(&arguments, &specs));
+                    match self.build(config, target, &specs, &mut built) {
+                        Ok(()) => {}
+                        // Failed builds have been reported already.
+                        Err(Error::Step { .. }) | Err(Error::Incomplete { .. }) => {}
+                        Err(error) => error!("{}", error),
+                    }
+                }
+                Err(error) => error!("{}", error),
+            }
+
+            // Json output is a stream of events, which a line of text would break.
+            if config.output == OutputFormat::Text && config.verbosity > Verbosity::Quiet {
+                println!("watching for changes, press ctrl-c to stop");
+            }
+            changed.extend(watcher.wait());
+        }
+    }
+
+    /// Loads the specs to build for `target`, with overrides and variables applied.
+    fn load(&self, config: &Config, target: &str) -> Result<Vec<BuildSpec>, Error> {
+        let mut specs = spec::load_all(&config.specs(&self.spec), self.format)?;
+        sources::resolve(&mut specs, Path::new(sources::CHECKOUTS), target)?;
+        overrides::apply(&mut specs, &config.overrides_with(&self.overrides))?;
+        let mut specs = spec::select_packages(specs, &self.package)?;
+        spec::select_all(&mut specs, target)?;
+        if self.include_prereleases {
+            spec::include_prereleases(&mut specs);
+        }
+
+        let variables = Variables::new(self.defines.clone());
+        for spec in &mut specs {
+            variables.apply(spec)?;
+        }
+
+        Ok(specs)
+    }
+
+    /// Builds `specs`, except for the ones which already have a package in `built`, see [`build_changed`].
+    fn build(
+        &self,
+        config: &Config,
+        target: &str,
+        specs: &[BuildSpec],
+        built: &mut HashMap<String, Package>,
+    ) -> Result<(), Error> {
+        let cache = Cache::configured(config)?.with_target(target);
+        let registries = Registries::configured(config)?.with_target(target);
+
+        let locked = if self.locked {
+            Some(Lockfile::load(LOCKFILE)?)
+        } else {
+            None
+        };
+
+        if self.dry_run {
+            let actions = plan::plan(specs, &cache, &registries, locked.as_ref())?;
+
+            match config.output {
+                OutputFormat::Text => {
+                    for action in actions {
+                        println!("{}", action);
+                    }
+                }
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&actions).map_err(cache::Error::from)?
+                ),
+            }
+
+            return Ok(());
+        }
+
+        let timings = Timings::new();
+        let mut resolver = Resolver::new(&cache, &registries).with_timings(&timings);
+        if let Some(lockfile) = locked {
+            resolver = resolver.locked(lockfile);
+        }
+
+        let options = BuildOptions {
+            build_directory: self.build_directory.clone(),
+            jobs: self.jobs.or(config.jobs).unwrap_or_else(default_jobs),
+            reporter: Reporter::new(config.output, config.verbosity),
+            hooks: config.hooks.clone(),
+            sandbox: if self.no_sandbox {
+                Sandbox::Off
+            } else {
+                config.sandbox
+            },
+            executor: self.executor.unwrap_or(config.executor),
+            environment: config.environment.clone(),
+            keep_going: self.keep_going,
+        };
+        let result = build_changed(specs, &mut resolver, &options, built);
+
+        // Timings are most useful when the build is slow, or broken, so they're reported either way.
+        if config.output == OutputFormat::Text
+            && config.verbosity > Verbosity::Quiet
+            && !timings.records().is_empty()
+        {
+            println!("{}", timings.summary());
+        }
+        if let Some(format) = self.timings {
+            let path = timings
+                .write(format, &self.build_directory)
+                .map_err(cache::Error::from)?;
+            println!("wrote timings to {}", path.display());
+        }
+        result?;
+
+        let lockfile = resolver.finish()?;
+        if !self.locked {
+            lockfile.save(LOCKFILE)?;
+        }
+
+        if config.gc.is_limited() {
+            Self::collect_garbage(config, &cache, &lockfile, built)?;
+        }
+
+        Ok(())
+    }
+
+    /// Enforces the configured cache limits, keeping the packages the build just used or produced.
+    fn collect_garbage(
+        config: &Config,
+        cache: &Cache,
+        lockfile: &Lockfile,
+        built: &HashMap<String, Package>,
+    ) -> Result<(), Error> {
+        let mut policy = config.gc.clone();
+        policy.keep.extend(lockfile.pinned());
+        policy.keep.extend(
+            built
+                .values()
+                .map(|package| (package.name.join("/"), package.version.clone())),
+        );
+
+        let report = cache.gc(&policy)?;
+        if !report.removed.is_empty()
+            && config.output == OutputFormat::Text
+            && config.verbosity > Verbosity::Quiet
+        {
+            println!(
+                "removed {} packages from the cache, freeing {} bytes",
+                report.removed.len(),
+                report.freed
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Settings shared by everything which builds packages.
+pub(crate) struct BuildOptions {
+    pub build_directory: PathBuf,
+    /// Number of packages to build at the same time.
+    pub jobs: usize,
+    pub reporter: Reporter,
+    /// Global hooks, which run for every spec.
+    pub hooks: Hooks,
+    pub sandbox: Sandbox,
+    pub executor: Executor,
+    pub environment: Environment,
+    /// Whether to keep building the packages which don't depend on a failed one, see [`build_changed`].
+    pub keep_going: bool,
+}
+
+/// Builds every one of `specs` in dependency order, resolving external dependencies along the way.
+/// Up to `options.jobs` packages are built at the same time, as long as they don't depend on each other.
+/// Once a build fails, no new builds are started, but the ones already running are allowed to finish, unless
+/// `options.keep_going` is set, see [`build_changed`].
+pub(crate) fn build_all(
+    specs: &[BuildSpec],
+    resolver: &mut Resolver,
+    options: &BuildOptions,
+) -> Result<(), Error> {
+    build_changed(specs, resolver, options, &mut HashMap::new())
+}
+
+/// Like [`build_all`], except that specs which already have a package in `built`, by `name@version`, aren't built
+/// again, and are depended on as they are. Every package built successfully is added to `built`.
+///
+/// With `options.keep_going`, a failure only stops the packages depending on the failed one, directly or not,
+/// which are skipped, while everything else is still built. The packages built, failed and skipped are
+/// summarized at the end, and any failure fails with [`Error::Incomplete`].
+pub(crate) fn build_changed(
+    specs: &[BuildSpec],
+    resolver: &mut Resolver,
+    options: &BuildOptions,
+    built: &mut HashMap<String, Package>,
+) -> Result<(), Error> {
+    let reporter = &options.reporter;
+    let engine = Engine::new(
+        resolver.cache(),
+        options.build_directory.clone(),
+        reporter.clone(),
+        &options.hooks,
+        resolver.timings(),
+    )
+    .with_sandbox(options.sandbox)
+    .with_executor(options.executor)
+    .with_environment(options.environment.clone());
+    let graph = DependencyGraph::from(specs);
+    // Dependencies are only resolved, so just the specs to reuse or build count towards the plan.
+    reporter.planned(
+        graph
+            .iter()
+            .filter(|step| matches!(step, Step::Resolved(_)))
+            .count(),
+    );
+    let mut scheduler = graph.scheduler();
+
+    let (job_sender, job_receiver) = mpsc::channel::<(NodeHandle, &BuildSpec, Vec<Package>)>();
+    let (result_sender, result_receiver) = mpsc::channel();
+    let job_receiver = Mutex::new(job_receiver);
+
+    thread::scope(|scope| {
+        for _ in 0..options.jobs.max(1) {
+            let job_receiver = &job_receiver;
+            let result_sender = result_sender.clone();
+            let engine = &engine;
+
+            scope.spawn(move || loop {
+                let job = job_receiver.lock().expect("job queue poisoned").recv();
+                let (handle, spec, dependencies) = match job {
+                    Ok(job) => job,
+                    Err(_) => break,
+                };
+
+                let package = spec.to_string();
+                let started = Instant::now();
+                reporter.started(&package);
+
+                let result = engine.build(spec, &dependencies);
+                match &result {
+                    Ok(built) => reporter.finished(&package, built, started.elapsed()),
+                    Err(error) => reporter.failed(&package, error, started.elapsed()),
+                }
+
+                let result = result.map_err(|source| Error::Step { package, source });
+
+                if result_sender.send((handle, spec, result)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(result_sender);
+
+        let mut failure = None;
+        // Packages which failed to build or resolve, and the ones built successfully, for the summary.
+        let mut failed: Vec<String> = Vec::new();
+        let mut succeeded: Vec<String> = Vec::new();
+        let mut running = 0;
+        // Every package resolved or built so far, which the specs depending on them are built against.
+        let mut available: Vec<Package> = Vec::new();
+
+        loop {
+            if failure.is_none() || options.keep_going {
+                let mut resolved = false;
+
+                for (handle, step) in scheduler.ready() {
+                    match step {
+                        // Packages built before are reused right away, just like resolved dependencies.
+                        Step::Resolved(spec) if built.contains_key(&spec.to_string()) => {
+                            reporter.unchanged(&spec.to_string());
+                            available.push(built[&spec.to_string()].clone());
+                            scheduler.complete(handle);
+                            resolved = true;
+                        }
+                        Step::Resolved(spec) => {
+                            let dependencies = dependencies(spec, &available);
+                            job_sender
+                                .send((handle, spec, dependencies))
+                                .expect("workers outlive the scheduler");
+                            running += 1;
+                        }
+                        // Dependencies are resolved right away, since they are recorded in the lockfile.
+                        Step::Unresolved(dependency) => {
+                            reporter.resolving(&dependency.to_string());
+                            match resolver.resolve(dependency) {
+                                Ok(package) => {
+                                    reporter.resolved(&dependency.to_string(), &package);
+                                    available.push(package);
+                                    scheduler.complete(handle);
+                                    resolved = true;
+                                }
+                                Err(error) => {
+                                    if options.keep_going {
+                                        error!("{}", error);
+                                        failed.push(dependency.to_string());
+                                    }
+                                    failure.get_or_insert(error);
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // Resolving dependencies or reusing packages may have unlocked more steps.
+                if resolved {
+                    continue;
+                }
+            }
+
+            if running == 0 {
+                break;
+            }
+
+            let (handle, spec, result) = result_receiver
+                .recv()
+                .expect("workers outlive the scheduler");
+            running -= 1;
+
+            match result {
+                Ok(package) => {
+                    succeeded.push(spec.to_string());
+                    built.insert(spec.to_string(), package.clone());
+                    available.push(package);
+                    scheduler.complete(handle);
+                }
+                Err(error) => {
+                    failed.push(spec.to_string());
+                    failure.get_or_insert(error);
+                }
+            }
+        }
+
+        // Closing the queue stops the workers.
+        drop(job_sender);
+
+        if !options.keep_going {
+            reporter.done();
+            return failure.map_or(Ok(()), Err);
+        }
+
+        // Whatever wasn't built, failed or reused depends on a failure, since everything else was built.
+        let skipped: Vec<String> = graph
+            .iter()
+            .filter_map(|step| match step {
+                Step::Resolved(spec) => Some(spec.to_string()),
+                Step::Unresolved(_) => None,
+            })
+            .filter(|package| !built.contains_key(package) && !failed.contains(package))
+            .collect();
+        for package in &skipped {
+            reporter.skipped(package);
+        }
+        reporter.summary(&succeeded, &failed, &skipped);
+        reporter.done();
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Incomplete {
+                failed: failed.len(),
+                skipped: skipped.len(),
+            })
+        }
+    })
+}
+
+/// The packages satisfying each of the dependencies of `spec`, out of the ones `available`. Since specs are
+/// only built once everything they depend on is, every dependency is satisfied by the time the spec is built.
+fn dependencies(spec: &BuildSpec, available: &[Package]) -> Vec<Package> {
+    spec.dependencies
+        .iter()
+        .filter_map(|dependency| {
+            available
+                .iter()
+                .filter(|package| {
+                    package.name.join("/") == dependency.name
+                        && dependency.matches(&package.version)
+                })
+                .max_by(|a, b| a.version.cmp(&b.version))
+                .cloned()
+        })
+        .collect()
+}
+
+/// Number of builds to run at the same time unless told otherwise, which is one per CPU.
+pub(crate) fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, |jobs| jobs.get())
 }
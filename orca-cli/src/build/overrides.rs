@@ -0,0 +1,159 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::spec::{self, BuildSpec};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}")]
+    Spec(#[from] spec::Error),
+    #[error("override for {name} points at {path}, which has no spec named {name}")]
+    NotFound { name: String, path: PathBuf },
+}
+
+/// Forces every dependency on a package to resolve to something other than what the specs ask for, configured
+/// in the `[overrides]` section of the config or using `--override NAME=VALUE`. A value which is a version pins
+/// the package to exactly that version, while anything else is a path to a spec file or directory, from which
+/// the package is built instead.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(from = "String")]
+pub enum Override {
+    Version(Version),
+    Path(PathBuf),
+}
+
+impl From<String> for Override {
+    fn from(value: String) -> Self {
+        match Version::parse(&value) {
+            Ok(version) => Override::Version(version),
+            Err(_) => Override::Path(PathBuf::from(value)),
+        }
+    }
+}
+
+/// Parses an `--override NAME=VALUE` argument.
+pub fn parse_override(value: &str) -> Result<(String, Override), String> {
+    match value.split_once('=') {
+        Some((name, value)) if !name.is_empty() && !value.is_empty() => {
+            Ok((name.to_string(), Override::from(value.to_string())))
+        }
+        _ => Err(format!(
+            "invalid override, expected NAME=VERSION or NAME=PATH: {}",
+            value
+        )),
+    }
+}
+
+/// Applies `overrides` to `specs`. Specs loaded from an override's path replace any spec of the same name, and
+/// every dependency on an overridden package is changed to require exactly the overriding version.
+pub fn apply(
+    specs: &mut Vec<BuildSpec>,
+    overrides: &BTreeMap<String, Override>,
+) -> Result<(), Error> {
+    for (name, value) in overrides {
+        let version = match value {
+            Override::Version(version) => version.clone(),
+            Override::Path(path) => {
+                let replacement = spec::load_all(&[path.to_string_lossy()], None)?
+                    .into_iter()
+                    .find(|spec| &spec.name == name)
+                    .ok_or_else(|| Error::NotFound {
+                        name: name.clone(),
+                        path: path.clone(),
+                    })?;
+
+                let version = replacement.version.clone();
+                specs.retain(|spec| &spec.name != name);
+                specs.push(replacement);
+                version
+            }
+        };
+
+        let requirement =
+            VersionReq::parse(&format!("={}", version)).expect("exact requirements are valid");
+
+        for dependency in specs
+            .iter_mut()
+            .flat_map(|spec| spec.dependencies.iter_mut())
+        {
+            if &dependency.name == name {
+                dependency.version = requirement.clone();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use semver::{Version, VersionReq};
+
+    use super::{apply, parse_override, Override};
+    use crate::build::spec::BuildSpec;
+
+    fn spec(toml: &str) -> BuildSpec {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_overrides() {
+        let directory =
+            std::env::temp_dir().join(format!("orca-overrides-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(
+            directory.join("libbar.toml"),
+            "name = \"libbar\"\nversion = \"2.1.0-patched\"\n",
+        )
+        .unwrap();
+
+        let mut specs = vec![
+            spec(
+                r#"
+                name = "app"
+                version = "1.0.0"
+                dependencies = [
+                    { name = "libfoo", version = "^1" },
+                    { name = "libbar", version = "^2" },
+                ]
+                "#,
+            ),
+            spec("name = \"libbar\"\nversion = \"2.0.0\"\n"),
+        ];
+
+        let mut overrides = BTreeMap::new();
+        overrides.insert(
+            "libfoo".to_string(),
+            parse_override("libfoo=1.4.2").unwrap().1,
+        );
+        overrides.insert(
+            "libbar".to_string(),
+            parse_override(&format!("libbar={}", directory.display()))
+                .unwrap()
+                .1,
+        );
+        apply(&mut specs, &overrides).unwrap();
+
+        assert_eq!(specs.len(), 2);
+        assert_eq!(specs[1].version, Version::parse("2.1.0-patched").unwrap());
+        assert_eq!(
+            specs[0].dependencies[0].version,
+            VersionReq::parse("=1.4.2").unwrap()
+        );
+        assert_eq!(
+            specs[0].dependencies[1].version,
+            VersionReq::parse("=2.1.0-patched").unwrap()
+        );
+
+        overrides.insert("libbaz".to_string(), Override::Path(directory.clone()));
+        assert!(apply(&mut specs, &overrides).is_err());
+        assert!(parse_override("libfoo").is_err());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+
+use dependency_graph::{DependencyGraph, Step};
+use serde::Serialize;
+
+use crate::{cache::Cache, lockfile::Lockfile, registry::Registries};
+
+use super::{sources, spec::BuildSpec, Error};
+
+/// What a build would do about a single step, see `orca build --dry-run`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// Build the package from its spec.
+    Build { package: String },
+    /// Use a package which is already cached.
+    Cached { dependency: String, package: String },
+    /// Download a package from the registry.
+    Fetch {
+        dependency: String,
+        package: String,
+        registry: String,
+    },
+    /// Download the artifact of a url dependency.
+    Download { dependency: String, url: String },
+    /// Nothing is able to satisfy the dependency, so the build would fail.
+    Missing { dependency: String },
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Build { package } => write!(f, "build    {}", package),
+            Action::Cached {
+                dependency,
+                package,
+            } => write!(f, "cached   {} -> {}", dependency, package),
+            Action::Fetch {
+                dependency,
+                package,
+                registry,
+            } => write!(
+                f,
+                "fetch    {} -> {} from {}",
+                dependency, package, registry
+            ),
+            Action::Download { dependency, url } => {
+                write!(f, "download {} from {}", dependency, url)
+            }
+            Action::Missing { dependency } => write!(f, "missing  {}", dependency),
+        }
+    }
+}
+
+/// Works out what building `specs` would involve, in the order it would happen, without building or downloading anything.
+/// When given a lockfile, dependencies are planned using the versions it records.
+pub fn plan(
+    specs: &[BuildSpec],
+    cache: &Cache,
+    registries: &Registries,
+    locked: Option<&Lockfile>,
+) -> Result<Vec<Action>, Error> {
+    let graph = DependencyGraph::from(specs);
+    let mut actions = Vec::new();
+
+    for step in graph.iter() {
+        let dependency = match step {
+            Step::Resolved(spec) => {
+                actions.push(Action::Build {
+                    package: spec.to_string(),
+                });
+                continue;
+            }
+            Step::Unresolved(dependency) => dependency,
+        };
+
+        if let Some(url) = &dependency.url {
+            actions.push(match sources::cached_download(dependency, cache)? {
+                Some(package) => Action::Cached {
+                    dependency: dependency.to_string(),
+                    package: package.to_string(),
+                },
+                None => Action::Download {
+                    dependency: dependency.to_string(),
+                    url: url.clone(),
+                },
+            });
+            continue;
+        }
+
+        let pinned = match locked {
+            Some(lockfile) => Some(
+                lockfile
+                    .get(&dependency.name, &dependency.version)
+                    .ok_or_else(|| Error::NotLocked(dependency.to_string()))?
+                    .version
+                    .clone(),
+            ),
+            None => None,
+        };
+
+        let cached = match &pinned {
+            Some(version) => cache.get(&dependency.name, version)?,
+            None => cache.find(
+                &dependency.name,
+                &dependency.version,
+                dependency.prereleases,
+            )?,
+        };
+
+        if let Some(package) = cached {
+            actions.push(Action::Cached {
+                dependency: dependency.to_string(),
+                package: package.to_string(),
+            });
+            continue;
+        }
+
+        let remote = match &pinned {
+            Some(version) => registries.get(&dependency.name, version)?,
+            None => registries.find(
+                &dependency.name,
+                &dependency.version,
+                dependency.prereleases,
+            )?,
+        };
+
+        actions.push(match remote {
+            Some((registry, remote)) => Action::Fetch {
+                dependency: dependency.to_string(),
+                package: format!("{}@{}", dependency.name, remote.version),
+                registry: registry.url().to_string(),
+            },
+            None => Action::Missing {
+                dependency: dependency.to_string(),
+            },
+        });
+    }
+
+    Ok(actions)
+}
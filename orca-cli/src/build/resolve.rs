@@ -0,0 +1,172 @@
+use std::time::Instant;
+
+use semver::Version;
+
+use crate::{
+    cache::{Cache, Package},
+    lockfile::{LockedPackage, Lockfile},
+    registry::{Registries, Registry, RemoteVersion},
+};
+
+use super::{
+    sources,
+    spec::Dependency,
+    timings::{Phase, Timings},
+    Error,
+};
+
+/// Resolves external dependencies against the cache, downloading them from the registries if they aren't
+/// cached yet. Every resolved dependency is recorded in a [`Lockfile`]. When resolving against an existing
+/// lockfile, dependencies must resolve to exactly the packages it records.
+pub(crate) struct Resolver<'c> {
+    cache: &'c Cache,
+    registries: &'c Registries,
+    locked: Option<Lockfile>,
+    resolved: Lockfile,
+    timings: Option<&'c Timings>,
+}
+
+impl<'c> Resolver<'c> {
+    pub fn new(cache: &'c Cache, registries: &'c Registries) -> Self {
+        Resolver {
+            cache,
+            registries,
+            locked: None,
+            resolved: Lockfile::default(),
+            timings: None,
+        }
+    }
+
+    /// Only allows dependencies to resolve to the packages recorded in `lockfile`.
+    pub fn locked(mut self, lockfile: Lockfile) -> Self {
+        self.locked = Some(lockfile);
+        self
+    }
+
+    /// Records how long resolving and downloading each dependency takes in `timings`.
+    pub fn with_timings(mut self, timings: &'c Timings) -> Self {
+        self.timings = Some(timings);
+        self
+    }
+
+    pub fn cache(&self) -> &'c Cache {
+        self.cache
+    }
+
+    pub fn timings(&self) -> Option<&'c Timings> {
+        self.timings
+    }
+
+    pub fn resolve(&mut self, dependency: &Dependency) -> Result<Package, Error> {
+        let package = match &self.locked {
+            Some(lockfile) => {
+                let locked = lockfile
+                    .get(&dependency.name, &dependency.version)
+                    .ok_or_else(|| Error::NotLocked(dependency.to_string()))?;
+
+                let package = if dependency.url.is_some() {
+                    self.download_url(dependency)?
+                } else {
+                    self.exact(locked)?
+                };
+                if LockedPackage::new(&dependency.version, &package) != *locked {
+                    return Err(Error::LockMismatch(format!(
+                        "{}@{}",
+                        locked.name, locked.version
+                    )));
+                }
+                package
+            }
+            None => self.latest(dependency)?,
+        };
+
+        self.resolved
+            .insert(LockedPackage::new(&dependency.version, &package));
+        Ok(package)
+    }
+
+    /// The lockfile describing everything resolved so far. If resolving against an existing lockfile,
+    /// fails if that lockfile has entries which were never used.
+    pub fn finish(self) -> Result<Lockfile, Error> {
+        match self.locked {
+            Some(locked) if locked != self.resolved => Err(Error::LockMismatch(
+                "the lockfile contains dependencies which are no longer used".to_string(),
+            )),
+            _ => Ok(self.resolved),
+        }
+    }
+
+    /// Highest version of the package satisfying `dependency`, preferring cached packages.
+    fn latest(&self, dependency: &Dependency) -> Result<Package, Error> {
+        if dependency.url.is_some() {
+            return self.download_url(dependency);
+        }
+
+        let started = Instant::now();
+        if let Some(package) = self.cache.find(
+            &dependency.name,
+            &dependency.version,
+            dependency.prereleases,
+        )? {
+            self.record(&dependency.name, &package.version, Phase::Resolve, started);
+            return Ok(package);
+        }
+
+        if let Some((registry, remote)) = self.registries.find(
+            &dependency.name,
+            &dependency.version,
+            dependency.prereleases,
+        )? {
+            self.record(&dependency.name, &remote.version, Phase::Resolve, started);
+            return self.download(&dependency.name, &remote, registry);
+        }
+
+        Err(Error::Unresolved(dependency.to_string()))
+    }
+
+    /// Exactly the locked version of a package, downloading it if it isn't cached.
+    fn exact(&self, locked: &LockedPackage) -> Result<Package, Error> {
+        let started = Instant::now();
+        if let Some(package) = self.cache.get(&locked.name, &locked.version)? {
+            self.record(&locked.name, &locked.version, Phase::Resolve, started);
+            return Ok(package);
+        }
+
+        if let Some((registry, remote)) = self.registries.get(&locked.name, &locked.version)? {
+            self.record(&locked.name, &locked.version, Phase::Resolve, started);
+            return self.download(&locked.name, &remote, registry);
+        }
+
+        Err(Error::Unresolved(format!(
+            "{}@{}",
+            locked.name, locked.version
+        )))
+    }
+
+    /// Downloads `remote` from `registry` into the cache.
+    fn download(
+        &self,
+        name: &str,
+        remote: &RemoteVersion,
+        registry: &Registry,
+    ) -> Result<Package, Error> {
+        let started = Instant::now();
+        let package = registry.download(name, remote, self.cache)?;
+        self.record(name, &remote.version, Phase::Fetch, started);
+        Ok(package)
+    }
+
+    /// The package of a url dependency, downloading its artifact if it isn't cached, see [`sources::download`].
+    fn download_url(&self, dependency: &Dependency) -> Result<Package, Error> {
+        let started = Instant::now();
+        let package = sources::download(dependency, self.cache, self.registries)?;
+        self.record(&dependency.name, &package.version, Phase::Fetch, started);
+        Ok(package)
+    }
+
+    fn record(&self, name: &str, version: &Version, phase: Phase, started: Instant) {
+        if let Some(timings) = self.timings {
+            timings.record(&format!("{}@{}", name, version), phase, started);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use log::warn;
+use serde::Deserialize;
+
+use super::spec::BuildSpec;
+
+/// Host environment variables passed through to sandboxed builds. Everything else has to be set by the spec.
+const PASSTHROUGH: &[&str] = &["PATH"];
+
+/// How isolated build steps are from the host, configured using `sandbox` in the config and disabled using
+/// `--no-sandbox`. Hooks are never sandboxed, since they exist to talk to the world outside the build.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sandbox {
+    /// Steps run in whatever is left in the build directory, with the environment orca was started with.
+    Off,
+    /// Steps run in an empty build directory, with only `PATH` passed through from the host environment,
+    /// and `HOME` and `TMPDIR` pointing at a temporary directory private to the build.
+    #[default]
+    Env,
+    /// Like `env`, but steps also run in their own user, pid, ipc, uts and mount namespaces using `unshare`,
+    /// so they can't see or signal other processes on the host. Only supported on Linux.
+    Namespaces,
+}
+
+impl Sandbox {
+    /// Prepares the sandbox for a build in `directory`, which is emptied unless the sandbox is off.
+    pub fn enter(self, package: &str, directory: &Path) -> io::Result<Option<Session>> {
+        let mode = match self {
+            Sandbox::Off => return Ok(None),
+            Sandbox::Namespaces if !cfg!(target_os = "linux") => {
+                warn!("namespaces are only supported on linux, sandboxing the environment only");
+                Sandbox::Env
+            }
+            mode => mode,
+        };
+
+        if directory.exists() {
+            std::fs::remove_dir_all(directory)?;
+        }
+
+        let temporary = std::env::temp_dir().join(format!(
+            "orca-sandbox-{}-{}",
+            std::process::id(),
+            package.replace(|c: char| !c.is_ascii_alphanumeric() && c != '.', "-")
+        ));
+        if temporary.exists() {
+            std::fs::remove_dir_all(&temporary)?;
+        }
+        std::fs::create_dir_all(&temporary)?;
+
+        Ok(Some(Session { mode, temporary }))
+    }
+}
+
+/// Environment of build steps, configured using `env`, `env_remove` and `env_clear` in the config, which specs
+/// add to using the same fields, see [`BuildSpec::env_clear`]. Steps running in a container never inherit the
+/// host environment to begin with, and hooks always do.
+#[derive(Debug, Default, Clone)]
+pub struct Environment {
+    /// Variables set for every step, which the variables describing dependencies, and the spec's and the step's
+    /// own variables, take precedence over.
+    pub env: HashMap<String, String>,
+    /// Host variables the steps don't inherit.
+    pub remove: Vec<String>,
+    /// Whether the steps don't inherit any host variables, not even the ones the sandbox passes through.
+    pub clear: bool,
+}
+
+impl Environment {
+    /// The environment of the steps of `spec`, which removes the variables the spec removes as well.
+    pub fn of(&self, spec: &BuildSpec) -> Environment {
+        Environment {
+            env: self.env.clone(),
+            remove: self
+                .remove
+                .iter()
+                .chain(&spec.env_remove)
+                .cloned()
+                .collect(),
+            clear: self.clear || spec.env_clear,
+        }
+    }
+
+    /// Stops `command` from inheriting the removed host variables, or any of them when clearing. Commands of a
+    /// `sandboxed` build only lose the variables the sandbox passes through, keeping the ones it sets itself.
+    pub fn isolate(&self, command: &mut process::Command, sandboxed: bool) {
+        if self.clear {
+            if sandboxed {
+                for variable in PASSTHROUGH {
+                    command.env_remove(variable);
+                }
+            } else {
+                command.env_clear();
+            }
+        }
+
+        for variable in &self.remove {
+            command.env_remove(variable);
+        }
+    }
+}
+
+/// The sandbox of a single build, whose temporary directory is removed once the build is done.
+#[derive(Debug)]
+pub struct Session {
+    mode: Sandbox,
+    temporary: PathBuf,
+}
+
+impl Session {
+    /// A command running `program` with `arguments` inside the sandbox.
+    pub fn command<S: AsRef<OsStr>>(&self, program: &str, arguments: &[S]) -> process::Command {
+        let mut command = match self.mode {
+            Sandbox::Namespaces => {
+                let mut command = process::Command::new("unshare");
+                command
+                    .args(&[
+                        "--user",
+                        "--map-root-user",
+                        "--pid",
+                        "--fork",
+                        "--mount-proc",
+                        "--ipc",
+                        "--uts",
+                        "--",
+                    ])
+                    .arg(program);
+                command
+            }
+            _ => process::Command::new(program),
+        };
+
+        command.args(arguments).env_clear();
+        for variable in PASSTHROUGH {
+            if let Some(value) = std::env::var_os(variable) {
+                command.env(variable, value);
+            }
+        }
+
+        command
+            .env("HOME", &self.temporary)
+            .env("TMPDIR", &self.temporary)
+            .env("LANG", "C");
+        command
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if let Err(error) = std::fs::remove_dir_all(&self.temporary) {
+            warn!(
+                "failed to remove sandbox directory {}: {}",
+                self.temporary.display(),
+                error
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsStr, process};
+
+    use super::{Environment, Sandbox};
+
+    #[test]
+    fn test_sandbox() {
+        let directory =
+            std::env::temp_dir().join(format!("orca-sandbox-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("leftover"), "from a previous build").unwrap();
+
+        assert!(Sandbox::Off
+            .enter("app@1.0.0", &directory)
+            .unwrap()
+            .is_none());
+        assert!(directory.join("leftover").exists());
+
+        let session = Sandbox::Env
+            .enter("app@1.0.0", &directory)
+            .unwrap()
+            .unwrap();
+        assert!(!directory.exists());
+
+        let command = session.command("sh", &["-c", "true"]);
+        assert_eq!(command.get_program(), OsStr::new("sh"));
+        let home = command
+            .get_envs()
+            .find(|(key, _)| *key == OsStr::new("HOME"))
+            .and_then(|(_, value)| value)
+            .map(|value| value.to_owned())
+            .unwrap();
+        assert!(std::path::Path::new(&home).is_dir());
+
+        drop(session);
+        assert!(!std::path::Path::new(&home).exists());
+    }
+
+    #[test]
+    fn test_environment() {
+        let environment = Environment {
+            remove: vec!["SOURCE_DATE_EPOCH".to_string()],
+            ..Environment::default()
+        };
+        let mut command = process::Command::new("sh");
+        environment.isolate(&mut command, false);
+        assert!(command
+            .get_envs()
+            .any(|(key, value)| key == OsStr::new("SOURCE_DATE_EPOCH") && value.is_none()));
+
+        let environment = Environment {
+            clear: true,
+            ..environment
+        };
+        let session = Sandbox::Env
+            .enter(
+                "app@1.0.0",
+                &std::env::temp_dir().join("orca-environment-test"),
+            )
+            .unwrap()
+            .unwrap();
+        let mut command = session.command("sh", &["-c", "true"]);
+        environment.isolate(&mut command, true);
+        let variables: Vec<_> = command
+            .get_envs()
+            .filter(|(_, value)| value.is_some())
+            .map(|(key, _)| key.to_owned())
+            .collect();
+        assert!(variables.iter().any(|key| key == "HOME"));
+        assert!(!variables.iter().any(|key| key == "PATH"));
+    }
+}
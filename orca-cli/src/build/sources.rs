@@ -0,0 +1,611 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process,
+};
+
+use log::{info, warn};
+use semver::{BuildMetadata, Version, VersionReq};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use super::spec::{self, BuildSpec, Dependency};
+use crate::{
+    cache::{self, Artifact, Cache, Package},
+    identifier,
+    registry::{self, Registries},
+};
+
+/// Directory the repositories of git dependencies are checked out in, relative to the current directory.
+pub const CHECKOUTS: &str = ".orca/git";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    IO(#[from] std::io::Error),
+    #[error("{0}")]
+    Spec(#[from] spec::Error),
+    #[error("git {command} failed for {url}")]
+    Git { command: String, url: String },
+    #[error("dependency on {0} gives more than one of rev, tag and branch")]
+    AmbiguousReference(String),
+    #[error("{location} has no spec satisfying the dependency on {dependency}")]
+    NotFound {
+        dependency: String,
+        location: String,
+    },
+    #[error("{0} has more than one spec, so the dependency on it needs a name")]
+    AmbiguousPath(String),
+    #[error("{0} has a dependency without a name, which only path dependencies can leave out")]
+    Unnamed(String),
+    #[error("cache error: {0}")]
+    Cache(#[from] cache::Error),
+    #[error("registry error: {0}")]
+    Registry(#[from] registry::Error),
+    #[error("url dependency {0} needs a sha256 digest of the artifact")]
+    MissingDigest(String),
+    #[error("url dependency {0} needs an exact version, such as 1.2.0")]
+    InexactVersion(String),
+}
+
+/// What to check out of a git dependency's repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reference<'a> {
+    Rev(&'a str),
+    Tag(&'a str),
+    Branch(&'a str),
+    DefaultBranch,
+}
+
+impl<'a> Reference<'a> {
+    fn of(dependency: &'a Dependency) -> Result<Self, Error> {
+        match (&dependency.rev, &dependency.tag, &dependency.branch) {
+            (None, None, None) => Ok(Reference::DefaultBranch),
+            (Some(rev), None, None) => Ok(Reference::Rev(rev)),
+            (None, Some(tag), None) => Ok(Reference::Tag(tag)),
+            (None, None, Some(branch)) => Ok(Reference::Branch(branch)),
+            _ => Err(Error::AmbiguousReference(dependency.name.clone())),
+        }
+    }
+
+    /// The revision to check out, as understood by `git checkout`, once the remote has been fetched.
+    fn revision(&self) -> String {
+        match self {
+            Reference::Rev(rev) => rev.to_string(),
+            Reference::Tag(tag) => format!("refs/tags/{}", tag),
+            Reference::Branch(branch) => format!("origin/{}", branch),
+            Reference::DefaultBranch => "origin/HEAD".to_string(),
+        }
+    }
+}
+
+/// Adds the spec of every git and path dependency to `specs`, so it's built along with the rest. Repositories of git
+/// dependencies are checked out in `checkouts` first, while the paths of path dependencies are relative to the spec
+/// declaring them. Each of these dependencies is pinned to exactly the version of the spec it points at, so it
+/// never resolves to a package from anywhere else, and path dependencies which leave their name out get the name
+/// of the spec. The added specs may have git and path dependencies of their own. Dependencies declared for
+/// `target` in the `targets` of a spec are resolved too, since they're merged into the spec once it's selected,
+/// while the ones of other targets are left alone.
+pub fn resolve(specs: &mut Vec<BuildSpec>, checkouts: &Path, target: &str) -> Result<(), Error> {
+    let mut next = 0;
+
+    while next < specs.len() {
+        for table in [None, Some(target)].iter().copied() {
+            for index in 0..dependencies(&specs[next], table).len() {
+                let dependency = &dependencies(&specs[next], table)[index];
+                let (directory, location) = match (&dependency.git, &dependency.path) {
+                    (Some(url), _) => (
+                        checkout(url, Reference::of(dependency)?, checkouts)?,
+                        url.clone(),
+                    ),
+                    (None, Some(path)) => {
+                        let declared_in = specs[next].path.as_deref().and_then(Path::parent);
+                        let directory = declared_in.unwrap_or_else(|| Path::new(".")).join(path);
+                        let location = directory.display().to_string();
+                        (directory, location)
+                    }
+                    (None, None) if dependency.name.is_empty() => {
+                        return Err(Error::Unnamed(specs[next].to_string()))
+                    }
+                    (None, None) => continue,
+                };
+
+                let spec = find(
+                    spec::load_all(&[directory.to_string_lossy()], None)?,
+                    dependency,
+                    &location,
+                )?;
+
+                let dependency = &mut dependencies_mut(&mut specs[next], table)[index];
+                dependency.name = spec.name.clone();
+                dependency.version = VersionReq::parse(&format!("={}", spec.version))
+                    .expect("exact requirements are valid");
+
+                if !specs
+                    .iter()
+                    .any(|existing| existing.name == spec.name && existing.version == spec.version)
+                {
+                    specs.push(spec);
+                }
+            }
+        }
+
+        next += 1;
+    }
+
+    Ok(())
+}
+
+/// The dependencies of `spec` itself, or the ones it declares for `target`, if any.
+fn dependencies<'s>(spec: &'s BuildSpec, target: Option<&str>) -> &'s [Dependency] {
+    match target {
+        None => &spec.dependencies,
+        Some(target) => match spec.targets.get(target) {
+            Some(variant) => &variant.dependencies,
+            None => &[],
+        },
+    }
+}
+
+fn dependencies_mut<'s>(spec: &'s mut BuildSpec, target: Option<&str>) -> &'s mut [Dependency] {
+    match target {
+        None => &mut spec.dependencies,
+        Some(target) => match spec.targets.get_mut(target) {
+            Some(variant) => &mut variant.dependencies,
+            None => &mut [],
+        },
+    }
+}
+
+/// The spec `dependency` points at among the `candidates` loaded from `location`. Dependencies without a name need
+/// `location` to hold exactly one spec satisfying them.
+fn find(
+    candidates: Vec<BuildSpec>,
+    dependency: &Dependency,
+    location: &str,
+) -> Result<BuildSpec, Error> {
+    let mut matching = candidates.into_iter().filter(|spec| {
+        (dependency.name.is_empty() || spec.name == dependency.name)
+            && dependency.matches(&spec.version)
+    });
+
+    match (matching.next(), matching.next()) {
+        (Some(_), Some(_)) if dependency.name.is_empty() => {
+            Err(Error::AmbiguousPath(location.to_string()))
+        }
+        (Some(spec), _) => Ok(spec),
+        (None, _) => Err(Error::NotFound {
+            dependency: dependency.to_string(),
+            location: location.to_string(),
+        }),
+    }
+}
+
+/// The package previously downloaded for a url dependency, if it's cached and its artifact has the digest the
+/// dependency asks for.
+pub fn cached_download(dependency: &Dependency, cache: &Cache) -> Result<Option<Package>, Error> {
+    let (version, digest) = download_version(dependency)?;
+
+    Ok(cache.get(&dependency.name, &version)?.filter(|package| {
+        package.target.is_none()
+            && package.artifacts.len() == 1
+            && package.artifacts[0].digest.as_deref() == Some(&digest)
+    }))
+}
+
+/// Downloads the artifact of a url dependency, unless it's cached already, and stores it in the cache as the only
+/// artifact of a package which isn't target specific, named after the last segment of the url. The package gets
+/// the version the dependency's requirement names, which can therefore only name a single version.
+pub fn download(
+    dependency: &Dependency,
+    cache: &Cache,
+    registries: &Registries,
+) -> Result<Package, Error> {
+    if let Some(package) = cached_download(dependency, cache)? {
+        return Ok(package);
+    }
+
+    let (version, digest) = download_version(dependency)?;
+    let url = dependency.url.as_deref().unwrap_or_default();
+    let file_name = url
+        .split(|character| character == '?' || character == '#')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&dependency.name);
+
+    let staging = std::env::temp_dir().join(format!(
+        "orca-download-{}-{}-{}",
+        dependency.name.replace('/', "-"),
+        version,
+        process::id()
+    ));
+    std::fs::create_dir_all(&staging)?;
+
+    let path = staging.join(file_name);
+    let result = registries
+        .download_url(url, &path, &digest)
+        .map_err(Error::from)
+        .and_then(|_| {
+            Ok(cache.put(&Package {
+                name: identifier::parse_name(&dependency.name).map_err(cache::Error::from)?,
+                version,
+                target: None,
+                signature: None,
+                description: None,
+                dependencies: BTreeMap::new(),
+                yanked: false,
+                artifacts: vec![Artifact {
+                    name: file_name.to_string(),
+                    path: path.clone(),
+                    digest: None,
+                }],
+            })?)
+        });
+
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+/// The version to store a url dependency's artifact as, along with the digest it must have.
+fn download_version(dependency: &Dependency) -> Result<(Version, String), Error> {
+    let digest = dependency
+        .sha256
+        .as_ref()
+        .map(|digest| digest.to_lowercase())
+        .ok_or_else(|| Error::MissingDigest(dependency.name.clone()))?;
+
+    let version = match dependency.version.comparators.as_slice() {
+        [comparator] => comparator
+            .minor
+            .zip(comparator.patch)
+            .map(|(minor, patch)| Version {
+                major: comparator.major,
+                minor,
+                patch,
+                pre: comparator.pre.clone(),
+                build: BuildMetadata::EMPTY,
+            }),
+        _ => None,
+    };
+
+    let version = version.ok_or_else(|| Error::InexactVersion(dependency.to_string()))?;
+    Ok((version, digest))
+}
+
+/// Checks `reference` of the repository at `url` out, cloning the repository if there's no checkout of it yet.
+/// Every reference of a repository gets a checkout of its own, so dependencies on different branches of the same
+/// repository don't get in each other's way. Returns the directory of the checkout.
+fn checkout(url: &str, reference: Reference<'_>, checkouts: &Path) -> Result<PathBuf, Error> {
+    let revision = reference.revision();
+    let directory = checkouts.join(checkout_name(url, &revision));
+
+    if !directory.is_dir() {
+        info!("cloning {}", url);
+        std::fs::create_dir_all(checkouts)?;
+
+        // Cloned next to the checkout first, so an interrupted clone doesn't look like a checkout later on.
+        let staging = directory.with_extension(format!("tmp-{}", process::id()));
+        // The url comes from a spec, so it's kept from being mistaken for an option.
+        git(
+            &["clone", "--quiet", "--", url, &staging.to_string_lossy()],
+            None,
+            url,
+        )?;
+        std::fs::rename(&staging, &directory)?;
+    } else if !matches!(reference, Reference::Rev(_)) {
+        // Branches and tags move, but an existing checkout is better than none while offline.
+        if let Err(error) = git(
+            &["fetch", "--quiet", "--force", "--tags", "origin"],
+            Some(&directory),
+            url,
+        ) {
+            warn!("{}, using the existing checkout", error);
+        }
+    }
+
+    // Just like the url, the revision mustn't be mistaken for an option, nor for a path.
+    let checkout = [
+        "checkout",
+        "--quiet",
+        "--detach",
+        "--end-of-options",
+        revision.as_str(),
+        "--",
+    ];
+    let checked_out = git(&checkout, Some(&directory), url);
+
+    // Revisions which aren't in the checkout yet are only fetched when they're needed.
+    if checked_out.is_err() && matches!(reference, Reference::Rev(_)) {
+        git(&["fetch", "--quiet", "origin"], Some(&directory), url)?;
+        git(&checkout, Some(&directory), url)?;
+    } else {
+        checked_out?;
+    }
+
+    Ok(directory)
+}
+
+/// Name of the checkout of `revision` of the repository at `url`: the repository's own name, followed by a digest
+/// of both, such as `libfoo-3f2a9c0d1e4b5a69`.
+fn checkout_name(url: &str, revision: &str) -> String {
+    let repository = url
+        .trim_end_matches('/')
+        .rsplit(|character| character == '/' || character == ':')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(".git");
+    let repository: String = repository
+        .chars()
+        .filter(|character| character.is_ascii_alphanumeric() || matches!(character, '-' | '_'))
+        .collect();
+
+    let digest = hex::encode(Sha256::digest(format!("{}#{}", url, revision).as_bytes()));
+    format!("{}-{}", repository, &digest[..16])
+}
+
+fn git(arguments: &[&str], directory: Option<&Path>, url: &str) -> Result<(), Error> {
+    let mut command = process::Command::new("git");
+    if let Some(directory) = directory {
+        command.arg("-C").arg(directory);
+    }
+
+    let status = command.args(arguments).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::Git {
+            command: arguments[0].to_string(),
+            url: url.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, path::Path, process::Command};
+
+    use semver::{Version, VersionReq};
+
+    use super::{cached_download, checkout_name, download_version, resolve, Error};
+    use crate::{
+        build::spec::{load, parse, Dependency, Format},
+        cache::{self, Artifact, Cache, Package},
+    };
+
+    fn git(directory: &Path, arguments: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(directory)
+            .args(&["-c", "user.name=orca", "-c", "user.email=orca@example.com"])
+            .args(arguments)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn test_git_dependencies() {
+        let root = std::env::temp_dir().join(format!("orca-sources-test-{}", std::process::id()));
+        let repository = root.join("libfoo");
+        std::fs::create_dir_all(&repository).unwrap();
+        git(&repository, &["init", "--quiet"]);
+
+        std::fs::write(
+            repository.join("libfoo.toml"),
+            "name = \"libfoo\"\nversion = \"1.0.0\"\n",
+        )
+        .unwrap();
+        git(&repository, &["add", "libfoo.toml"]);
+        git(
+            &repository,
+            &["commit", "--quiet", "--message", "Release 1.0.0"],
+        );
+        git(&repository, &["tag", "v1.0.0"]);
+
+        std::fs::write(
+            repository.join("libfoo.toml"),
+            "name = \"libfoo\"\nversion = \"2.0.0\"\n",
+        )
+        .unwrap();
+        git(
+            &repository,
+            &["commit", "--quiet", "--all", "--message", "Release 2.0.0"],
+        );
+
+        let toml = format!(
+            r#"
+            name = "app"
+            version = "1.0.0"
+            dependencies = [
+                {{ name = "libfoo", git = "{0}", tag = "v1.0.0" }},
+                {{ name = "libfoo", version = "^2", git = "{0}", rev = "HEAD" }},
+            ]
+            "#,
+            repository.display()
+        );
+        let mut specs = parse(&toml, Format::Toml, &root.join("app.toml")).unwrap();
+        resolve(&mut specs, &root.join("checkouts"), "linux-x86_64").unwrap();
+
+        assert_eq!(specs.len(), 3);
+        assert_eq!(specs[1].version, Version::parse("1.0.0").unwrap());
+        assert_eq!(specs[2].version, Version::parse("2.0.0").unwrap());
+        assert_eq!(
+            specs[0].dependencies[0].version,
+            VersionReq::parse("=1.0.0").unwrap()
+        );
+
+        // Resolving again reuses the checkouts.
+        resolve(&mut specs, &root.join("checkouts"), "linux-x86_64").unwrap();
+        assert_eq!(specs.len(), 3);
+
+        let mut specs = parse(
+            &toml.replace("^2", "^3"),
+            Format::Toml,
+            &root.join("app.toml"),
+        )
+        .unwrap();
+        assert!(matches!(
+            resolve(&mut specs, &root.join("checkouts"), "linux-x86_64"),
+            Err(Error::NotFound { .. })
+        ));
+
+        // Dependencies of the target being resolved for are resolved too, while other targets' are left alone.
+        let toml = format!(
+            r#"
+            name = "app"
+            version = "1.0.0"
+
+            [targets.linux-x86_64]
+            dependencies = [{{ name = "libfoo", git = "{0}", tag = "v1.0.0" }}]
+
+            [targets.darwin-aarch64]
+            dependencies = [{{ name = "libbar", git = "{0}/missing" }}]
+            "#,
+            repository.display()
+        );
+        let mut specs = parse(&toml, Format::Toml, &root.join("app.toml")).unwrap();
+        resolve(&mut specs, &root.join("checkouts"), "linux-x86_64").unwrap();
+        assert_eq!(specs.len(), 2);
+        assert_eq!(specs[1].version, Version::parse("1.0.0").unwrap());
+
+        specs[0].select("linux-x86_64").unwrap();
+        assert_eq!(
+            specs[0].dependencies[0].version,
+            VersionReq::parse("=1.0.0").unwrap()
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_path_dependencies() {
+        let root = std::env::temp_dir().join(format!("orca-path-test-{}", std::process::id()));
+        for directory in &["app", "libfoo", "libs"] {
+            std::fs::create_dir_all(root.join(directory)).unwrap();
+        }
+        std::fs::write(
+            root.join("libfoo/libfoo.toml"),
+            "name = \"libfoo\"\nversion = \"0.3.0\"\ndependencies = [{ path = \"../libs/libbar.toml\" }]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("libs/libbar.toml"),
+            "name = \"libbar\"\nversion = \"1.0.0\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("libs/libbaz.toml"),
+            "name = \"libbaz\"\nversion = \"1.0.0\"\n",
+        )
+        .unwrap();
+
+        let app = root.join("app/app.toml");
+        std::fs::write(
+            &app,
+            "name = \"app\"\nversion = \"1.0.0\"\ndependencies = [{ path = \"../libfoo\" }]\n",
+        )
+        .unwrap();
+        let mut specs = load(&app, None).unwrap();
+        resolve(&mut specs, &root.join("checkouts"), "linux-x86_64").unwrap();
+
+        let names: Vec<_> = specs.iter().map(ToString::to_string).collect();
+        assert_eq!(names, vec!["app@1.0.0", "libfoo@0.3.0", "libbar@1.0.0"]);
+        assert_eq!(specs[0].dependencies[0].name, "libfoo");
+        assert_eq!(
+            specs[0].dependencies[0].version,
+            VersionReq::parse("=0.3.0").unwrap()
+        );
+
+        // A directory with more than one spec needs the dependency to say which one it means.
+        std::fs::write(
+            &app,
+            "name = \"app\"\nversion = \"1.0.0\"\ndependencies = [{ path = \"../libs\" }]\n",
+        )
+        .unwrap();
+        let mut specs = load(&app, None).unwrap();
+        assert!(matches!(
+            resolve(&mut specs, &root.join("checkouts"), "linux-x86_64"),
+            Err(Error::AmbiguousPath(_))
+        ));
+
+        std::fs::write(
+            &app,
+            "name = \"app\"\nversion = \"1.0.0\"\ndependencies = [{ name = \"libbaz\", path = \"../libs\" }]\n",
+        )
+        .unwrap();
+        let mut specs = load(&app, None).unwrap();
+        resolve(&mut specs, &root.join("checkouts"), "linux-x86_64").unwrap();
+        assert_eq!(specs[1].name, "libbaz");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_url_dependencies() {
+        let root = std::env::temp_dir().join(format!("orca-url-test-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("blob.tar.gz"), b"blob").unwrap();
+        let digest = cache::digest(&root.join("blob.tar.gz")).unwrap();
+
+        let dependency = |version: &str, sha256: Option<&str>| -> Dependency {
+            let mut toml = format!(
+                "name = \"vendor/blob\"\nversion = \"{}\"\nurl = \"https://example.com/blob.tar.gz\"\n",
+                version
+            );
+            if let Some(sha256) = sha256 {
+                toml.push_str(&format!("sha256 = \"{}\"\n", sha256));
+            }
+            toml::from_str(&toml).unwrap()
+        };
+
+        let (version, _) = download_version(&dependency("1.2.0", Some(&digest))).unwrap();
+        assert_eq!(version, Version::parse("1.2.0").unwrap());
+        let (version, _) = download_version(&dependency("=2.0.0-rc.1", Some(&digest))).unwrap();
+        assert_eq!(version, Version::parse("2.0.0-rc.1").unwrap());
+        assert!(matches!(
+            download_version(&dependency("^1", Some(&digest))),
+            Err(Error::InexactVersion(_))
+        ));
+        assert!(matches!(
+            download_version(&dependency("1.2.0", None)),
+            Err(Error::MissingDigest(_))
+        ));
+
+        let cache = Cache::new(root.join("cache")).unwrap();
+        let dependency = dependency("1.2.0", Some(&digest.to_uppercase()));
+        assert!(cached_download(&dependency, &cache).unwrap().is_none());
+
+        cache
+            .put(&Package {
+                name: vec!["vendor".to_string(), "blob".to_string()],
+                version: Version::parse("1.2.0").unwrap(),
+                target: None,
+                signature: None,
+                description: None,
+                dependencies: BTreeMap::new(),
+                yanked: false,
+                artifacts: vec![Artifact::new(root.join("blob.tar.gz"))],
+            })
+            .unwrap();
+        let cached = cached_download(&dependency, &cache).unwrap().unwrap();
+        assert_eq!(cached.artifacts[0].name, "blob.tar.gz");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_checkout_name() {
+        let name = checkout_name("https://example.com/org/libfoo.git", "origin/HEAD");
+        assert!(name.starts_with("libfoo-"));
+        assert_eq!(name.len(), "libfoo-".len() + 16);
+        assert_ne!(
+            name,
+            checkout_name("https://example.com/org/libfoo.git", "refs/tags/v1.0.0")
+        );
+        assert!(checkout_name("git@example.com:libbar", "origin/HEAD").starts_with("libbar-"));
+    }
+}
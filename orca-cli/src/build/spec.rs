@@ -1,17 +1,911 @@
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use dependency_graph::Node;
 use semver::{Version, VersionReq};
 
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
+use thiserror::Error;
+
+use crate::{config::PROJECT_CONFIG, identifier};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to read spec file {0}: {1}")]
+    IO(PathBuf, #[source] std::io::Error),
+    #[error("failed to parse spec file {0}: {1}")]
+    Json(PathBuf, #[source] serde_json::Error),
+    #[error("failed to parse spec file {0}: {1}")]
+    Yaml(PathBuf, #[source] serde_yaml::Error),
+    #[error("failed to parse spec file {0}: {1}")]
+    Toml(PathBuf, #[source] toml::de::Error),
+    #[error("unable to determine format of spec file {0}, use --format to specify it")]
+    UnknownFormat(PathBuf),
+    #[error("invalid spec pattern: {0}")]
+    Pattern(#[from] glob::PatternError),
+    #[error("failed to find spec files: {0}")]
+    Glob(#[from] glob::GlobError),
+    #[error("no spec files found matching {0}")]
+    NotFound(String),
+    #[error("invalid spec file {0}: {1}")]
+    Name(PathBuf, #[source] identifier::ParsingError),
+    #[error("base spec {0} is part of an extends cycle")]
+    CircularExtends(PathBuf),
+    #[error("{spec} can't be built for target {target}, it only supports {supported}")]
+    UnsupportedTarget {
+        spec: String,
+        target: String,
+        supported: String,
+    },
+    #[error("no spec files given, use --spec or list workspace members in orca.toml")]
+    NoSpecs,
+    #[error("no spec named {0}")]
+    UnknownPackage(String),
+}
+
+/// File formats BuildSpecs can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    /// Guesses the format of a spec file from its extension.
+    pub fn from_extension<P: AsRef<Path>>(path: P) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            _ => None,
+        }
+    }
+
+    /// The usual extension of spec files in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Yaml => "yaml",
+            Format::Toml => "toml",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "json" => Ok(Format::Json),
+            "yaml" => Ok(Format::Yaml),
+            "toml" => Ok(Format::Toml),
+            other => Err(format!("unknown spec format: {}", other)),
+        }
+    }
+}
 
 #[derive(Debug, Deserialize)]
 pub struct Dependency {
+    /// Can only be left out by path dependencies, which take the name of the spec they point at.
+    #[serde(default)]
     pub name: String,
+    /// Defaults to any version, which is mostly useful for git dependencies, whose repository decides the version.
+    /// Url dependencies are stored as the version the requirement names, so `1.2.0` is stored as `1.2.0`.
+    #[serde(default)]
     pub version: VersionReq,
+    /// Let pre-releases such as `1.0.0-rc.1` satisfy the requirement, as if they were the release they precede.
+    /// Can be enabled for every dependency using `--include-prereleases`. See [`identifier::satisfies`].
+    #[serde(default)]
+    pub prereleases: bool,
+    /// Url of a git repository holding the dependency's spec, to build it from instead of resolving a package from
+    /// the cache and registries. The repository is checked out at `rev`, `tag` or `branch`, or at its default
+    /// branch if none of them are given, see [`sources::resolve`](super::sources::resolve).
+    #[serde(default)]
+    pub git: Option<String>,
+    #[serde(default)]
+    pub rev: Option<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// Url of a single artifact to use as the dependency instead, which is downloaded into the cache as a package
+    /// of its own, see [`sources::download`](super::sources::download). Requires `sha256` as well.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Hex-encoded SHA-256 digest the artifact at `url` must have.
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Spec file or directory holding the dependency's spec, such as `../libfoo`, relative to the spec declaring the
+    /// dependency. The spec is built in place, rather than resolving a package from the cache and registries.
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+}
+
+impl Dependency {
+    pub fn matches(&self, version: &Version) -> bool {
+        identifier::satisfies(version, &self.version, self.prereleases)
+    }
+}
+
+/// A command to run as part of a build, either as a single string which is passed to the shell,
+/// or as an array of which the first element is executed directly, using the rest as arguments.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Command {
+    Shell(String),
+    Exec(Vec<String>),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BuildStep {
+    pub run: Command,
+    /// Directory to run the command in, relative to the build directory.
+    #[serde(default)]
+    pub working_directory: Option<PathBuf>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct BuildSpec {
     pub name: String,
     pub version: Version,
+    /// Short description of the package, shown by `orca search`.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Base spec to inherit from, relative to the file this spec is defined in. See [`Base`].
+    #[serde(default)]
+    pub extends: Option<PathBuf>,
+    /// Packages this one is built against. Before the steps run, the artifacts of every dependency are linked
+    /// into `deps/<name>/` within the build directory, and the steps see the following environment variables:
+    ///
+    /// * `ORCA_DEPS_DIR`: absolute path of the `deps` directory.
+    /// * `ORCA_DEP_<NAME>_DIR` and `ORCA_DEP_<NAME>_VERSION`: where the artifacts of a single dependency are,
+    ///   and its version. `<NAME>` is the package name in upper case, with anything but letters and digits
+    ///   replaced by `_`, so `org/lib-foo` becomes `ORG_LIB_FOO`.
     #[serde(default)]
     pub dependencies: Vec<Dependency>,
+    /// Environment variables set for every step. Variables set by the step itself take precedence.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Host environment variables the steps don't inherit, such as `CFLAGS` or `SOURCE_DATE_EPOCH`, in addition to
+    /// the ones removed using `env_remove` in the config. Variables set using `env` are set regardless.
+    #[serde(default)]
+    pub env_remove: Vec<String>,
+    /// Don't let the steps inherit any host environment variables, not even `PATH` when sandboxed, so they only
+    /// see the variables set using `env` and the ones orca sets itself. Clearing in the config clears every spec.
+    #[serde(default)]
+    pub env_clear: bool,
+    /// Steps required to build the package, executed in order.
+    #[serde(default)]
+    pub steps: Vec<BuildStep>,
+    /// Paths or glob patterns relative to the build directory, matching the files to collect
+    /// into the cache once the build has finished. If empty, the whole build directory is collected.
+    #[serde(default)]
+    pub artifacts: Vec<String>,
+    /// Container image to run the steps in, such as `rust:1.75`, when building with a container executor.
+    /// See [`Executor`](super::executor::Executor).
+    #[serde(default)]
+    pub builder_image: Option<String>,
+    /// Files or directories the package is built from, relative to the spec file. `orca build --watch` rebuilds
+    /// the package, and everything depending on it, whenever any of them change.
+    #[serde(default)]
+    pub sources: Vec<PathBuf>,
+    /// Additional commands to run before and after the build.
+    #[serde(default)]
+    pub hooks: Hooks,
+    /// Variants of the spec for each supported target, such as `linux-x86_64` or `darwin-aarch64`. A spec which
+    /// declares targets can only be built for one of them, and its packages are cached separately for each.
+    #[serde(default)]
+    pub targets: HashMap<String, Target>,
+    /// The target the spec has been selected for, see [`BuildSpec::select`].
+    #[serde(skip)]
+    pub target: Option<String>,
+    /// The file the spec was loaded from, if any.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+/// Commands to run before and after a build, for automation such as notifying a webhook or signing artifacts.
+/// Hooks can be declared by individual specs, as well as globally in the `[hooks]` section of the config, in which
+/// case they run for every spec. Global `pre_build` hooks run before the spec's own, while global `post_build`
+/// hooks run after them.
+///
+/// Hooks run in the build directory, and see the following environment variables:
+///
+/// * `ORCA_HOOK`: either `pre_build` or `post_build`.
+/// * `ORCA_PACKAGE_NAME` and `ORCA_PACKAGE_VERSION`: the package being built.
+/// * `ORCA_TARGET`: the target being built for.
+/// * `ORCA_BUILD_DIRECTORY`: absolute path of the build directory.
+/// * `ORCA_ARTIFACTS`: paths of the collected artifacts, one per line, for `post_build` hooks only. Post build hooks
+///   run before the artifacts are cached, so they can still modify them.
+///
+/// A hook which fails, fails the build.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Hooks {
+    #[serde(default)]
+    pub pre_build: Vec<Command>,
+    #[serde(default)]
+    pub post_build: Vec<Command>,
+}
+
+/// Target specific additions to a [`BuildSpec`]. When the target is selected, its `dependencies` are added to
+/// the spec's own, replacing any of the same name, its `steps` run after the spec's own, its `env` variables take
+/// precedence over the spec's, and its `artifacts` and `builder_image` replace the spec's, unless there are none.
+#[derive(Debug, Default, Deserialize)]
+pub struct Target {
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub steps: Vec<BuildStep>,
+    #[serde(default)]
+    pub artifacts: Vec<String>,
+    #[serde(default)]
+    pub builder_image: Option<String>,
+}
+
+impl BuildSpec {
+    /// Selects the target to build the spec for, merging that target's variant into the spec.
+    /// Fails if the spec declares targets, but not this one.
+    pub fn select(&mut self, target: &str) -> Result<(), Error> {
+        if !self.targets.is_empty() {
+            let variant = self.targets.remove(target).ok_or_else(|| {
+                let mut supported: Vec<_> = self.targets.keys().cloned().collect();
+                supported.sort();
+
+                Error::UnsupportedTarget {
+                    spec: self.to_string(),
+                    target: target.to_string(),
+                    supported: supported.join(", "),
+                }
+            })?;
+
+            self.dependencies.retain(|dependency| {
+                !variant
+                    .dependencies
+                    .iter()
+                    .any(|d| d.name == dependency.name)
+            });
+            self.dependencies.extend(variant.dependencies);
+            self.steps.extend(variant.steps);
+            self.env.extend(variant.env);
+            if !variant.artifacts.is_empty() {
+                self.artifacts = variant.artifacts;
+            }
+            if variant.builder_image.is_some() {
+                self.builder_image = variant.builder_image;
+            }
+
+            // Only the selected variant is kept, which marks the spec as target specific.
+            self.targets.clear();
+            self.targets.insert(target.to_string(), Target::default());
+        }
+
+        self.target = Some(target.to_string());
+        Ok(())
+    }
+
+    /// The target the packages built from this spec are specific to, if the spec declares targets at all.
+    pub fn package_target(&self) -> Option<&str> {
+        if self.targets.is_empty() {
+            None
+        } else {
+            self.target.as_deref()
+        }
+    }
+}
+
+/// Shared defaults which any number of specs can inherit from using `extends`. A base spec has all the fields of a
+/// [`BuildSpec`] except for its name and version, all of which are optional, and may itself extend another base.
+///
+/// Inherited fields are merged into the spec as follows:
+///
+/// * `dependencies` are added to the spec's own, unless the spec declares a dependency of the same name.
+/// * `steps` run before the spec's own steps.
+/// * `sources` are added to the spec's own, relative to the base rather than the spec.
+/// * `env` variables are set unless the spec sets them as well.
+/// * `env_remove` variables are removed along with the spec's own, and `env_clear` clears the spec's environment.
+/// * `artifacts` and `builder_image` are only used if the spec doesn't declare any.
+///
+/// Base specs are only ever loaded through `extends`, so directory scans skip files starting with `_`,
+/// which is where bases such as `_base.toml` are expected to live alongside the specs extending them.
+#[derive(Debug, Default, Deserialize)]
+pub struct Base {
+    #[serde(default)]
+    pub extends: Option<PathBuf>,
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub env_remove: Vec<String>,
+    #[serde(default)]
+    pub env_clear: bool,
+    #[serde(default)]
+    pub steps: Vec<BuildStep>,
+    #[serde(default)]
+    pub artifacts: Vec<String>,
+    #[serde(default)]
+    pub builder_image: Option<String>,
+    #[serde(default)]
+    pub sources: Vec<PathBuf>,
+}
+
+impl Base {
+    /// Merges the base into `spec`, following the rules described on [`Base`].
+    fn merge_into(self, spec: &mut BuildSpec) {
+        let mut dependencies: Vec<_> = self
+            .dependencies
+            .into_iter()
+            .filter(|inherited| {
+                !spec
+                    .dependencies
+                    .iter()
+                    .any(|dependency| dependency.name == inherited.name)
+            })
+            .collect();
+        dependencies.append(&mut spec.dependencies);
+        spec.dependencies = dependencies;
+
+        let mut steps = self.steps;
+        steps.append(&mut spec.steps);
+        spec.steps = steps;
+
+        spec.sources.extend(self.sources);
+
+        for (key, value) in self.env {
+            spec.env.entry(key).or_insert(value);
+        }
+        spec.env_remove.extend(self.env_remove);
+        spec.env_clear |= self.env_clear;
+
+        if spec.artifacts.is_empty() {
+            spec.artifacts = self.artifacts;
+        }
+        if spec.builder_image.is_none() {
+            spec.builder_image = self.builder_image;
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Shell(command) => write!(f, "{}", command),
+            Command::Exec(arguments) => write!(f, "{}", arguments.join(" ")),
+        }
+    }
+}
+
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.name, self.version)
+    }
+}
+
+impl fmt::Display for BuildSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.name, self.version)
+    }
+}
+
+/// How the specs in a single document are laid out. The layout is worked out from the shape of the document
+/// before the specs themselves are deserialized, so errors in a spec are reported with the field and line they
+/// occur at, rather than as the document not matching any layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layout {
+    One,
+    Many,
+    /// Toml documents can't be arrays, so multiple specs are listed under a `specs` key instead.
+    Table,
+}
+
+#[derive(Deserialize)]
+struct Table {
+    specs: Vec<BuildSpec>,
+}
+
+impl Layout {
+    fn of_json(document: &serde_json::Value) -> Self {
+        match document {
+            serde_json::Value::Array(_) => Layout::Many,
+            serde_json::Value::Object(table) if table.contains_key("specs") => Layout::Table,
+            _ => Layout::One,
+        }
+    }
+
+    fn of_yaml(document: &serde_yaml::Value) -> Self {
+        match document {
+            serde_yaml::Value::Sequence(_) => Layout::Many,
+            serde_yaml::Value::Mapping(table) if table.contains_key(&"specs".into()) => {
+                Layout::Table
+            }
+            _ => Layout::One,
+        }
+    }
+
+    fn of_toml(document: &toml::Value) -> Self {
+        match document {
+            toml::Value::Table(table) if table.contains_key("specs") => Layout::Table,
+            _ => Layout::One,
+        }
+    }
+
+    fn deserialize<'de, D: Deserializer<'de>>(
+        self,
+        document: D,
+    ) -> Result<Vec<BuildSpec>, D::Error> {
+        match self {
+            Layout::One => BuildSpec::deserialize(document).map(|spec| vec![spec]),
+            Layout::Many => Vec::deserialize(document),
+            Layout::Table => Table::deserialize(document).map(|table| table.specs),
+        }
+    }
+}
+
+/// Reads and parses all the BuildSpecs in a single file. Unless a `format` is given, it is determined by the file extension.
+/// Yaml files may contain multiple documents, each holding one or more specs.
+pub fn load<P: AsRef<Path>>(path: P, format: Option<Format>) -> Result<Vec<BuildSpec>, Error> {
+    let path = path.as_ref();
+
+    let format = format
+        .or_else(|| Format::from_extension(path))
+        .ok_or_else(|| Error::UnknownFormat(path.to_path_buf()))?;
+
+    let contents =
+        std::fs::read_to_string(path).map_err(|error| Error::IO(path.to_path_buf(), error))?;
+
+    let mut specs = parse(&contents, format, path)?;
+    for spec in &mut specs {
+        spec.path = Some(path.to_path_buf());
+        spec.sources = spec
+            .sources
+            .iter()
+            .map(|source| relative_to(path, source))
+            .collect();
+        inherit(spec, path, format)?;
+        validate(spec).map_err(|error| Error::Name(path.to_path_buf(), error))?;
+    }
+
+    Ok(specs)
+}
+
+/// Merges the chain of bases `spec` extends into it, starting with the closest one.
+fn inherit(spec: &mut BuildSpec, path: &Path, format: Format) -> Result<(), Error> {
+    let mut visited = Vec::new();
+    let mut next = spec
+        .extends
+        .as_ref()
+        .map(|extends| relative_to(path, extends));
+
+    while let Some(base_path) = next {
+        let canonical = std::fs::canonicalize(&base_path)
+            .map_err(|error| Error::IO(base_path.clone(), error))?;
+        if visited.contains(&canonical) {
+            return Err(Error::CircularExtends(base_path));
+        }
+
+        let mut base = load_base(&base_path, format)?;
+        base.sources = base
+            .sources
+            .iter()
+            .map(|source| relative_to(&base_path, source))
+            .collect();
+        next = base
+            .extends
+            .as_ref()
+            .map(|extends| relative_to(&base_path, extends));
+
+        base.merge_into(spec);
+        visited.push(canonical);
+    }
+
+    Ok(())
+}
+
+/// Reads and parses a base spec. Unless its extension says otherwise, it is assumed to be in the same format as the spec extending it.
+fn load_base(path: &Path, format: Format) -> Result<Base, Error> {
+    let format = Format::from_extension(path).unwrap_or(format);
+    let contents =
+        std::fs::read_to_string(path).map_err(|error| Error::IO(path.to_path_buf(), error))?;
+
+    match format {
+        Format::Json => {
+            serde_json::from_str(&contents).map_err(|error| Error::Json(path.to_path_buf(), error))
+        }
+        Format::Yaml => {
+            serde_yaml::from_str(&contents).map_err(|error| Error::Yaml(path.to_path_buf(), error))
+        }
+        Format::Toml => {
+            toml::from_str(&contents).map_err(|error| Error::Toml(path.to_path_buf(), error))
+        }
+    }
+}
+
+/// Resolves `relative`, such as the path of a base spec, relative to the directory containing the file at `path`.
+fn relative_to(path: &Path, relative: &Path) -> PathBuf {
+    path.parent()
+        .map_or_else(|| relative.to_path_buf(), |parent| parent.join(relative))
+}
+
+/// Ensures the names of a spec and all of its dependencies, including the ones of each of its targets, are valid
+/// package names. Path dependencies may leave their name out, since it's filled in from the spec they point at, see
+/// [`sources::resolve`](super::sources::resolve).
+fn validate(spec: &BuildSpec) -> Result<(), identifier::ParsingError> {
+    identifier::parse_name(&spec.name)?;
+    let targets = spec
+        .targets
+        .values()
+        .flat_map(|variant| &variant.dependencies);
+    for dependency in spec.dependencies.iter().chain(targets) {
+        if dependency.path.is_some() && dependency.name.is_empty() {
+            continue;
+        }
+        identifier::parse_name(&dependency.name)?;
+    }
+
+    Ok(())
+}
+
+/// Parses the contents of the spec file at `path`.
+pub(crate) fn parse(contents: &str, format: Format, path: &Path) -> Result<Vec<BuildSpec>, Error> {
+    // Every document is parsed twice, once to find its layout, and once more to deserialize the specs in it.
+    match format {
+        Format::Json => {
+            let error = |error| Error::Json(path.to_path_buf(), error);
+            let layout = Layout::of_json(&serde_json::from_str(contents).map_err(error)?);
+            layout
+                .deserialize(&mut serde_json::Deserializer::from_str(contents))
+                .map_err(error)
+        }
+        Format::Yaml => {
+            let error = |error| Error::Yaml(path.to_path_buf(), error);
+            let layouts = serde_yaml::Deserializer::from_str(contents)
+                .map(|document| {
+                    serde_yaml::Value::deserialize(document).map(|value| Layout::of_yaml(&value))
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(error)?;
+
+            let mut specs = Vec::new();
+            for (document, layout) in serde_yaml::Deserializer::from_str(contents).zip(layouts) {
+                specs.extend(layout.deserialize(document).map_err(error)?);
+            }
+            Ok(specs)
+        }
+        Format::Toml => {
+            let error = |error| Error::Toml(path.to_path_buf(), error);
+            let layout = Layout::of_toml(&toml::from_str(contents).map_err(error)?);
+            layout
+                .deserialize(&mut toml::Deserializer::new(contents))
+                .map_err(error)
+        }
+    }
+}
+
+/// Expands a `--spec` argument into the spec files it refers to. Directories are scanned recursively for
+/// files with a known spec extension, skipping hidden directories such as `.orca`, base specs starting with `_`
+/// and the project's `orca.toml`, while anything else
+/// is treated as a glob pattern, which may just be the path of a single file.
+pub fn discover(argument: &str) -> Result<Vec<PathBuf>, Error> {
+    let path = Path::new(argument);
+
+    if path.is_dir() {
+        let mut files = Vec::new();
+        scan(path, &mut files).map_err(|error| Error::IO(path.to_path_buf(), error))?;
+        files.sort();
+        return Ok(files);
+    }
+
+    let files = glob::glob(argument)?.collect::<Result<Vec<_>, _>>()?;
+
+    if files.is_empty() {
+        Err(Error::NotFound(argument.to_string()))
+    } else {
+        Ok(files)
+    }
+}
+
+fn scan(directory: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        if path.is_dir() {
+            if !name.starts_with('.') {
+                scan(&path, files)?;
+            }
+        } else if Format::from_extension(&path).is_some()
+            && !name.starts_with('_')
+            && path.file_name() != Some(OsStr::new(PROJECT_CONFIG))
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Lets pre-releases satisfy every dependency of `specs`, see [`Dependency::prereleases`].
+pub fn include_prereleases(specs: &mut [BuildSpec]) {
+    for dependency in specs.iter_mut().flat_map(|spec| &mut spec.dependencies) {
+        dependency.prereleases = true;
+    }
+}
+
+/// Selects `target` for every one of `specs`, see [`BuildSpec::select`].
+pub fn select_all(specs: &mut [BuildSpec], target: &str) -> Result<(), Error> {
+    for spec in specs {
+        spec.select(target)?;
+    }
+
+    Ok(())
+}
+
+/// Only keeps the specs named in `packages`, along with every spec they depend on, directly or not.
+/// Keeps every spec if `packages` is empty.
+pub fn select_packages(
+    specs: Vec<BuildSpec>,
+    packages: &[String],
+) -> Result<Vec<BuildSpec>, Error> {
+    if packages.is_empty() {
+        return Ok(specs);
+    }
+
+    let mut selected = vec![false; specs.len()];
+    let mut pending = Vec::new();
+    for package in packages {
+        let found = specs
+            .iter()
+            .position(|spec| &spec.name == package)
+            .ok_or_else(|| Error::UnknownPackage(package.clone()))?;
+        pending.push(found);
+    }
+
+    while let Some(index) = pending.pop() {
+        if std::mem::replace(&mut selected[index], true) {
+            continue;
+        }
+
+        for dependency in &specs[index].dependencies {
+            pending.extend(
+                specs
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, spec)| spec.matches(dependency))
+                    .map(|(index, _)| index),
+            );
+        }
+    }
+
+    Ok(specs
+        .into_iter()
+        .zip(selected)
+        .filter(|(_, selected)| *selected)
+        .map(|(spec, _)| spec)
+        .collect())
+}
+
+/// Loads all the specs referred to by the given `--spec` arguments, failing on the first file which can't be read.
+pub fn load_all<S: AsRef<str>>(
+    arguments: &[S],
+    format: Option<Format>,
+) -> Result<Vec<BuildSpec>, Error> {
+    if arguments.is_empty() {
+        return Err(Error::NoSpecs);
+    }
+
+    let mut specs = Vec::new();
+
+    for argument in arguments {
+        for path in discover(argument.as_ref())? {
+            specs.extend(load(path, format)?);
+        }
+    }
+
+    Ok(specs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{load, parse, select_packages, Error, Format};
+
+    #[test]
+    fn test_multiple_specs_per_file() {
+        let json = r#"[
+            { "name": "base", "version": "1.0.0" },
+            { "name": "derived", "version": "1.0.0", "dependencies": [{ "name": "base", "version": "^1" }] }
+        ]"#;
+        assert_eq!(
+            parse(json, Format::Json, Path::new("test")).unwrap().len(),
+            2
+        );
+
+        let yaml = "name: base\nversion: 1.0.0\n---\n- name: derived\n  version: 1.0.0\n- name: other\n  version: 0.1.0\n";
+        assert_eq!(
+            parse(yaml, Format::Yaml, Path::new("test")).unwrap().len(),
+            3
+        );
+
+        let toml = "[[specs]]\nname = \"base\"\nversion = \"1.0.0\"\n\n[[specs]]\nname = \"other\"\nversion = \"0.1.0\"\n";
+        assert_eq!(
+            parse(toml, Format::Toml, Path::new("test")).unwrap().len(),
+            2
+        );
+
+        let single = "name = \"base\"\nversion = \"1.0.0\"\n";
+        assert_eq!(
+            parse(single, Format::Toml, Path::new("test")).unwrap()[0].name,
+            "base"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        // Errors in a spec keep their field and position, whichever layout the spec is in.
+        let error = |contents: &str, format: Format| {
+            let error = parse(contents, format, Path::new("test")).unwrap_err();
+            std::error::Error::source(&error).unwrap().to_string()
+        };
+
+        let json = "[\n  { \"name\": \"base\", \"version\": \"1.0\" }\n]";
+        let message = error(json, Format::Json);
+        assert!(message.contains("line 2"), "{}", message);
+
+        let yaml = "name: base\nversion: 1.0.0\n---\nspecs:\n  - name: other\n    version: 1.0.0\n    steps: nope\n";
+        let message = error(yaml, Format::Yaml);
+        assert!(message.contains("steps"), "{}", message);
+
+        let toml = "name = \"base\"\nversion = \"1.0.0\"\nsources = 1\n";
+        let message = error(toml, Format::Toml);
+        assert!(message.contains("line 3"), "{}", message);
+
+        assert!(!message.contains("did not match any variant"));
+    }
+
+    #[test]
+    fn test_extends() {
+        let root = std::env::temp_dir().join(format!("orca-extends-test-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("common")).unwrap();
+
+        std::fs::write(
+            root.join("common/_toolchain.yaml"),
+            "env: { CC: gcc, CFLAGS: -O2 }\nsteps: [{ run: ./configure }]\nbuilder_image: gcc:13\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("_base.toml"),
+            r#"
+            extends = "common/_toolchain.yaml"
+            artifacts = ["dist/*"]
+            dependencies = [{ name = "zlib", version = "^1" }, { name = "openssl", version = "^1" }]
+
+            [[steps]]
+            run = "make"
+            "#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("libfoo.toml"),
+            r#"
+            name = "libfoo"
+            version = "1.0.0"
+            extends = "_base.toml"
+            env = { CC = "clang" }
+            dependencies = [{ name = "openssl", version = "^3" }]
+
+            [[steps]]
+            run = "make install"
+            "#,
+        )
+        .unwrap();
+
+        let specs = load(root.join("libfoo.toml"), None).unwrap();
+        let spec = &specs[0];
+
+        let dependencies: Vec<_> = spec.dependencies.iter().map(|d| d.to_string()).collect();
+        assert_eq!(dependencies, vec!["zlib ^1", "openssl ^3"]);
+
+        let steps: Vec<_> = spec.steps.iter().map(|s| s.run.to_string()).collect();
+        assert_eq!(steps, vec!["./configure", "make", "make install"]);
+
+        assert_eq!(spec.env["CC"], "clang");
+        assert_eq!(spec.env["CFLAGS"], "-O2");
+        assert_eq!(spec.artifacts, vec!["dist/*"]);
+        assert_eq!(spec.builder_image.as_deref(), Some("gcc:13"));
+
+        std::fs::write(
+            root.join("common/_toolchain.yaml"),
+            "extends: ../_base.toml\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            load(root.join("libfoo.toml"), None),
+            Err(super::Error::CircularExtends(_))
+        ));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_targets() {
+        let toml = r#"
+            name = "libfoo"
+            version = "1.0.0"
+            dependencies = [{ name = "zlib", version = "^1" }]
+            steps = [{ run = "./configure" }]
+
+            [targets.linux-x86_64]
+            dependencies = [{ name = "zlib", version = "^2" }, { name = "glibc", version = "^2" }]
+            steps = [{ run = "make" }]
+            artifacts = ["libfoo.so"]
+
+            [targets.darwin-aarch64]
+            artifacts = ["libfoo.dylib"]
+        "#;
+
+        let mut spec = parse(toml, Format::Toml, Path::new("test"))
+            .unwrap()
+            .remove(0);
+        spec.select("linux-x86_64").unwrap();
+
+        let dependencies: Vec<_> = spec.dependencies.iter().map(|d| d.to_string()).collect();
+        assert_eq!(dependencies, vec!["zlib ^2", "glibc ^2"]);
+        assert_eq!(spec.steps.len(), 2);
+        assert_eq!(spec.artifacts, vec!["libfoo.so"]);
+        assert_eq!(spec.package_target(), Some("linux-x86_64"));
+
+        let mut spec = parse(toml, Format::Toml, Path::new("test"))
+            .unwrap()
+            .remove(0);
+        assert!(matches!(
+            spec.select("windows-x86_64"),
+            Err(super::Error::UnsupportedTarget { supported, .. }) if supported == "darwin-aarch64, linux-x86_64"
+        ));
+
+        let mut generic = parse(
+            "name = \"base\"\nversion = \"1.0.0\"\n",
+            Format::Toml,
+            Path::new("test"),
+        )
+        .unwrap()
+        .remove(0);
+        generic.select("windows-x86_64").unwrap();
+        assert_eq!(generic.package_target(), None);
+        assert_eq!(generic.target.as_deref(), Some("windows-x86_64"));
+    }
+
+    #[test]
+    fn test_select_packages() {
+        let json = r#"[
+            { "name": "zlib", "version": "1.2.0" },
+            { "name": "libfoo", "version": "1.0.0", "dependencies": [{ "name": "zlib", "version": "^1" }] },
+            { "name": "app", "version": "1.0.0", "dependencies": [{ "name": "libfoo", "version": "^1" }] },
+            { "name": "tool", "version": "0.1.0" }
+        ]"#;
+        let specs = || parse(json, Format::Json, Path::new("test")).unwrap();
+
+        let selected = select_packages(specs(), &["app".to_string()]).unwrap();
+        let names: Vec<_> = selected.iter().map(|spec| spec.name.as_str()).collect();
+        assert_eq!(names, vec!["zlib", "libfoo", "app"]);
+
+        assert_eq!(select_packages(specs(), &[]).unwrap().len(), 4);
+        assert!(matches!(
+            select_packages(specs(), &["missing".to_string()]),
+            Err(Error::UnknownPackage(name)) if name == "missing"
+        ));
+    }
 }
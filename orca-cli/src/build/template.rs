@@ -0,0 +1,253 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use thiserror::Error;
+
+use crate::target;
+
+use super::spec::{BuildSpec, Command, Hooks};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(
+        "undefined variable ${{{variable}}} in {spec}, use --define {variable}=VALUE to define it"
+    )]
+    Undefined { spec: String, variable: String },
+    #[error("unterminated variable reference in {spec}: {input}")]
+    Unterminated { spec: String, input: String },
+}
+
+/// Variables available for substitution in BuildSpecs, using `${VAR}`. Variables are looked up in the following order,
+/// so each can override the ones after it:
+///
+/// 1. Values passed using `--define KEY=VALUE`.
+/// 2. The built-ins `${name}`, `${version}` and `${target}` of the spec being expanded, where `${target}` is the
+///    target selected using `--target`.
+/// 3. Environment variables.
+///
+/// A literal `$` can be written as `$$`, so `$${HOME}` is passed along as `${HOME}`. A `$` which is not followed by
+/// a `{` is left alone, which allows shell commands to keep using `$HOME` and the like without having to escape them.
+#[derive(Debug, Default)]
+pub struct Variables {
+    defines: HashMap<String, String>,
+}
+
+impl Variables {
+    pub fn new<I: IntoIterator<Item = (String, String)>>(defines: I) -> Self {
+        Variables {
+            defines: defines.into_iter().collect(),
+        }
+    }
+
+    /// Expands all variables in the commands, hooks, working directories, environments and artifacts of `spec`.
+    /// The name, version and dependencies of a spec are left as-is, since the dependency graph is built from them.
+    pub fn apply(&self, spec: &mut BuildSpec) -> Result<(), Error> {
+        let context = Context {
+            variables: self,
+            spec: &*spec,
+        };
+
+        let mut steps = Vec::with_capacity(spec.steps.len());
+        for step in &spec.steps {
+            let run = context.command(&step.run)?;
+
+            let working_directory = match &step.working_directory {
+                Some(directory) => Some(match directory.to_str() {
+                    Some(directory) => PathBuf::from(context.expand(directory)?),
+                    None => directory.clone(),
+                }),
+                None => None,
+            };
+
+            let env = step
+                .env
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), context.expand(value)?)))
+                .collect::<Result<_, Error>>()?;
+
+            steps.push((run, working_directory, env));
+        }
+
+        let env = spec
+            .env
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), context.expand(value)?)))
+            .collect::<Result<_, Error>>()?;
+
+        let artifacts = spec
+            .artifacts
+            .iter()
+            .map(|artifact| context.expand(artifact))
+            .collect::<Result<_, _>>()?;
+
+        let hooks = Hooks {
+            pre_build: context.commands(&spec.hooks.pre_build)?,
+            post_build: context.commands(&spec.hooks.post_build)?,
+        };
+
+        for (step, (run, working_directory, env)) in spec.steps.iter_mut().zip(steps) {
+            step.run = run;
+            step.working_directory = working_directory;
+            step.env = env;
+        }
+        spec.env = env;
+        spec.artifacts = artifacts;
+        spec.hooks = hooks;
+
+        Ok(())
+    }
+
+    fn lookup(&self, spec: &BuildSpec, variable: &str) -> Option<String> {
+        if let Some(value) = self.defines.get(variable) {
+            return Some(value.clone());
+        }
+
+        match variable {
+            "name" => Some(spec.name.clone()),
+            "version" => Some(spec.version.to_string()),
+            "target" => Some(spec.target.clone().unwrap_or_else(target::host)),
+            _ => std::env::var(variable).ok(),
+        }
+    }
+}
+
+/// The spec being expanded, along with the variables to expand it with.
+struct Context<'a> {
+    variables: &'a Variables,
+    spec: &'a BuildSpec,
+}
+
+impl<'a> Context<'a> {
+    fn command(&self, command: &Command) -> Result<Command, Error> {
+        Ok(match command {
+            Command::Shell(script) => Command::Shell(self.expand(script)?),
+            Command::Exec(arguments) => Command::Exec(
+                arguments
+                    .iter()
+                    .map(|argument| self.expand(argument))
+                    .collect::<Result<_, _>>()?,
+            ),
+        })
+    }
+
+    fn commands(&self, commands: &[Command]) -> Result<Vec<Command>, Error> {
+        commands
+            .iter()
+            .map(|command| self.command(command))
+            .collect()
+    }
+
+    fn expand(&self, input: &str) -> Result<String, Error> {
+        let mut output = String::with_capacity(input.len());
+        let mut rest = input;
+
+        while let Some(position) = rest.find('$') {
+            output.push_str(&rest[..position]);
+            rest = &rest[position + 1..];
+
+            if let Some(remainder) = rest.strip_prefix('$') {
+                output.push('$');
+                rest = remainder;
+            } else if let Some(remainder) = rest.strip_prefix('{') {
+                let end = remainder.find('}').ok_or_else(|| Error::Unterminated {
+                    spec: self.spec.to_string(),
+                    input: input.to_string(),
+                })?;
+
+                let variable = &remainder[..end];
+                let value =
+                    self.variables
+                        .lookup(self.spec, variable)
+                        .ok_or_else(|| Error::Undefined {
+                            spec: self.spec.to_string(),
+                            variable: variable.to_string(),
+                        })?;
+
+                output.push_str(&value);
+                rest = &remainder[end + 1..];
+            } else {
+                output.push('$');
+            }
+        }
+
+        output.push_str(rest);
+        Ok(output)
+    }
+}
+
+/// Parses a `--define KEY=VALUE` argument.
+pub fn parse_define(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("invalid definition, expected KEY=VALUE: {}", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_define, Error, Variables};
+    use crate::build::spec::{BuildSpec, Command};
+
+    fn spec(toml: &str) -> BuildSpec {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let mut spec = spec(
+            r#"
+            name = "libfoo"
+            version = "1.2.3"
+            artifacts = ["dist/${name}-${version}.tar.gz"]
+
+            [[steps]]
+            run = "make PROFILE=${profile} PREFIX=$HOME $${KEEP}"
+            env = { TARGET = "${target}" }
+            "#,
+        );
+
+        let variables = Variables::new(vec![("profile".to_string(), "release".to_string())]);
+        variables.apply(&mut spec).unwrap();
+
+        assert_eq!(spec.artifacts, vec!["dist/libfoo-1.2.3.tar.gz"]);
+        match &spec.steps[0].run {
+            Command::Shell(command) => {
+                assert_eq!(command, "make PROFILE=release PREFIX=$HOME ${KEEP}")
+            }
+            Command::Exec(_) => panic!("expected a shell command"),
+        }
+        assert!(spec.steps[0].env["TARGET"].contains(std::env::consts::OS));
+    }
+
+    #[test]
+    fn test_defines_override_builtins() {
+        let mut spec = spec("name = \"libfoo\"\nversion = \"1.0.0\"\nartifacts = [\"${name}\"]\n");
+        Variables::new(vec![("name".to_string(), "other".to_string())])
+            .apply(&mut spec)
+            .unwrap();
+        assert_eq!(spec.artifacts, vec!["other"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut spec = spec(
+            "name = \"libfoo\"\nversion = \"1.0.0\"\nartifacts = [\"${ORCA_SURELY_UNDEFINED}\"]\n",
+        );
+        assert!(matches!(
+            Variables::default().apply(&mut spec),
+            Err(Error::Undefined { variable, .. }) if variable == "ORCA_SURELY_UNDEFINED"
+        ));
+
+        spec.artifacts = vec!["${name".to_string()];
+        assert!(matches!(
+            Variables::default().apply(&mut spec),
+            Err(Error::Unterminated { .. })
+        ));
+
+        assert_eq!(
+            parse_define("KEY=a=b").unwrap(),
+            ("KEY".to_string(), "a=b".to_string())
+        );
+        assert!(parse_define("=value").is_err());
+        assert!(parse_define("KEY").is_err());
+    }
+}
@@ -0,0 +1,303 @@
+use std::{
+    collections::BTreeMap,
+    fmt, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+/// Number of packages listed in the summary printed at the end of a build.
+const SUMMARY_LENGTH: usize = 10;
+
+/// Format of the report written by `orca build --timings`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimingsFormat {
+    Json,
+    /// A timeline of every package, with a row for each package and a bar for each of its phases.
+    Html,
+}
+
+impl FromStr for TimingsFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "json" => Ok(TimingsFormat::Json),
+            "html" => Ok(TimingsFormat::Html),
+            other => Err(format!("unknown timings format: {}", other)),
+        }
+    }
+}
+
+impl TimingsFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            TimingsFormat::Json => "json",
+            TimingsFormat::Html => "html",
+        }
+    }
+}
+
+/// Phases each package goes through on its way into the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    /// Finding the package in the cache or registries.
+    Resolve,
+    /// Downloading the package from a registry.
+    Fetch,
+    /// Running the hooks and steps of the package's spec.
+    Build,
+    /// Putting the built package into the cache.
+    Store,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Phase::Resolve => "resolve",
+            Phase::Fetch => "fetch",
+            Phase::Build => "build",
+            Phase::Store => "store",
+        })
+    }
+}
+
+/// A single phase of a single package, with times in seconds since the build started.
+#[derive(Debug, Clone, Serialize)]
+pub struct Record {
+    pub package: String,
+    pub phase: Phase,
+    pub start: f64,
+    pub duration: f64,
+}
+
+/// Records how long every phase of every package takes during a build. Phases are recorded from the threads
+/// running them, so they can overlap when packages are built at the same time.
+pub struct Timings {
+    started: Instant,
+    records: Mutex<Vec<Record>>,
+}
+
+impl Timings {
+    pub fn new() -> Self {
+        Timings {
+            started: Instant::now(),
+            records: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Records `phase` of `package` as running from `started` until now.
+    pub fn record(&self, package: &str, phase: Phase, started: Instant) {
+        let record = Record {
+            package: package.to_string(),
+            phase,
+            start: started
+                .saturating_duration_since(self.started)
+                .as_secs_f64(),
+            duration: started.elapsed().as_secs_f64(),
+        };
+
+        self.records.lock().expect("timings poisoned").push(record);
+    }
+
+    /// Everything recorded so far, in the order the phases started.
+    pub fn records(&self) -> Vec<Record> {
+        let mut records = self.records.lock().expect("timings poisoned").clone();
+        records.sort_by(|a, b| a.start.total_cmp(&b.start));
+        records
+    }
+
+    /// Total time spent in each phase, by package, with the slowest packages first.
+    fn totals(&self) -> Vec<(String, BTreeMap<Phase, f64>)> {
+        let mut totals: BTreeMap<String, BTreeMap<Phase, f64>> = BTreeMap::new();
+        for record in self.records() {
+            *totals
+                .entry(record.package)
+                .or_default()
+                .entry(record.phase)
+                .or_default() += record.duration;
+        }
+
+        let mut totals: Vec<_> = totals.into_iter().collect();
+        totals.sort_by(|(_, a), (_, b)| total(b).total_cmp(&total(a)));
+        totals
+    }
+
+    /// A table of the slowest packages, along with the time spent in each of their phases.
+    pub fn summary(&self) -> String {
+        let totals = self.totals();
+        let width = totals
+            .iter()
+            .take(SUMMARY_LENGTH)
+            .map(|(package, _)| package.len())
+            .chain(Some("package".len()))
+            .max()
+            .unwrap_or_default();
+
+        let phases = [Phase::Resolve, Phase::Fetch, Phase::Build, Phase::Store];
+        let mut summary = format!("{:width$}", "package", width = width);
+        for phase in &phases {
+            summary += &format!(" {:>8}", phase.to_string());
+        }
+        summary += &format!(" {:>8}\n", "total");
+
+        for (package, durations) in totals.iter().take(SUMMARY_LENGTH) {
+            summary += &format!("{:width$}", package, width = width);
+            for phase in &phases {
+                match durations.get(phase) {
+                    Some(duration) => summary += &format!(" {:>7.1}s", duration),
+                    None => summary += &format!(" {:>8}", "-"),
+                }
+            }
+            summary += &format!(" {:>7.1}s\n", total(durations));
+        }
+
+        if totals.len() > SUMMARY_LENGTH {
+            summary += &format!("... and {} more\n", totals.len() - SUMMARY_LENGTH);
+        }
+
+        summary += &format!(
+            "{} packages in {:.1}s",
+            totals.len(),
+            self.elapsed().as_secs_f64()
+        );
+        summary
+    }
+
+    /// Writes the full report in `format` into `directory`, returning the path it was written to.
+    pub fn write(&self, format: TimingsFormat, directory: &Path) -> io::Result<PathBuf> {
+        let path = directory.join(format!("timings.{}", format.extension()));
+        std::fs::create_dir_all(directory)?;
+
+        let contents = match format {
+            TimingsFormat::Json => {
+                serde_json::to_string_pretty(&self.records()).map_err(io::Error::from)?
+            }
+            TimingsFormat::Html => self.html(),
+        };
+        std::fs::write(&path, contents)?;
+
+        Ok(path)
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// A timeline with a row for each package, in the order they started, and a bar for each phase.
+    fn html(&self) -> String {
+        let records = self.records();
+        let elapsed = self.elapsed().as_secs_f64().max(f64::EPSILON);
+
+        let mut rows: Vec<(String, Vec<Record>)> = Vec::new();
+        for record in records {
+            match rows
+                .iter_mut()
+                .find(|(package, _)| *package == record.package)
+            {
+                Some((_, phases)) => phases.push(record),
+                None => rows.push((record.package.clone(), vec![record])),
+            }
+        }
+
+        let mut timeline = String::new();
+        for (package, phases) in &rows {
+            timeline += &format!(
+                "<div class=\"row\"><span class=\"name\">{}</span><span class=\"lane\">",
+                escape(package)
+            );
+            for record in phases {
+                timeline += &format!(
+                    "<span class=\"{phase}\" style=\"left: {left:.3}%; width: {width:.3}%\" title=\"{package} {phase}: {duration:.2}s\"></span>",
+                    phase = record.phase,
+                    left = record.start / elapsed * 100.0,
+                    width = (record.duration / elapsed * 100.0).max(0.1),
+                    package = escape(package),
+                    duration = record.duration,
+                );
+            }
+            timeline += "</span></div>\n";
+        }
+
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>orca build timings</title>
+<style>
+body {{ font-family: sans-serif; font-size: 13px; }}
+.row {{ display: flex; height: 18px; margin: 2px 0; }}
+.name {{ width: 300px; flex-shrink: 0; overflow: hidden; white-space: nowrap; text-overflow: ellipsis; }}
+.lane {{ position: relative; flex-grow: 1; background: #f4f4f4; }}
+.lane span {{ position: absolute; top: 0; bottom: 0; }}
+.resolve {{ background: #9ecae1; }}
+.fetch {{ background: #3182bd; }}
+.build {{ background: #fd8d3c; }}
+.store {{ background: #74c476; }}
+</style>
+</head>
+<body>
+<h1>orca build timings</h1>
+<p>{packages} packages in {elapsed:.1}s. Phases:
+<span class="resolve">&nbsp;resolve&nbsp;</span>
+<span class="fetch">&nbsp;fetch&nbsp;</span>
+<span class="build">&nbsp;build&nbsp;</span>
+<span class="store">&nbsp;store&nbsp;</span></p>
+{timeline}</body>
+</html>
+"#,
+            packages = rows.len(),
+            elapsed = elapsed,
+            timeline = timeline,
+        )
+    }
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Timings::new()
+    }
+}
+
+fn total(durations: &BTreeMap<Phase, f64>) -> f64 {
+    durations.values().sum()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::{Phase, Timings};
+
+    #[test]
+    fn test_summary() {
+        let timings = Timings::new();
+        let started = Instant::now();
+        timings.record("libfoo@1.0.0", Phase::Fetch, started);
+        timings.record("app@1.0.0", Phase::Build, started);
+        timings.record("app@1.0.0", Phase::Store, started);
+
+        let summary = timings.summary();
+        let lines: Vec<_> = summary.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("package"));
+        assert!(lines[1..3]
+            .iter()
+            .any(|line| line.starts_with("libfoo@1.0.0")));
+        assert!(lines[3].starts_with("2 packages in"));
+        assert!(timings.html().contains("title=\"app@1.0.0 store"));
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use super::spec::BuildSpec;
+
+/// How often watched files are checked for changes.
+const INTERVAL: Duration = Duration::from_millis(500);
+
+/// Watches files and directories for changes by polling their modification times, which works the same way on
+/// every platform and file system. Hidden files and directories within watched directories, such as `.orca`
+/// holding the build directory, are ignored.
+#[derive(Debug, Default)]
+pub struct Watcher {
+    paths: Vec<PathBuf>,
+    /// Modification time of every watched file, as of the last check.
+    snapshot: HashMap<PathBuf, SystemTime>,
+}
+
+impl Watcher {
+    /// Replaces the watched paths. Only changes made after this call are reported by [`Watcher::wait`].
+    pub fn watch(&mut self, paths: Vec<PathBuf>) {
+        self.snapshot = snapshot(&paths);
+        self.paths = paths;
+    }
+
+    /// Blocks until watched files are created, modified or removed, and returns their paths. Waits for the changes
+    /// to settle first, so saving several files at once only wakes the caller up once.
+    pub fn wait(&mut self) -> Vec<PathBuf> {
+        let mut changed = HashSet::new();
+
+        loop {
+            thread::sleep(INTERVAL);
+
+            let current = snapshot(&self.paths);
+            let changes = diff(&self.snapshot, &current);
+            self.snapshot = current;
+
+            if changes.is_empty() && !changed.is_empty() {
+                let mut changed: Vec<_> = changed.into_iter().collect();
+                changed.sort();
+                return changed;
+            }
+
+            changed.extend(changes);
+        }
+    }
+}
+
+/// Every path worth watching while building `specs`: the spec files and sources of every spec, along with the
+/// `--spec` arguments they were loaded from, so that new spec files in watched directories are picked up too.
+pub fn paths(arguments: &[String], specs: &[BuildSpec]) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = arguments
+        .iter()
+        .map(PathBuf::from)
+        // Glob patterns aren't paths, but the spec files they matched are watched anyway.
+        .filter(|path| path.exists())
+        .chain(specs.iter().filter_map(|spec| spec.path.clone()))
+        .chain(specs.iter().flat_map(|spec| spec.sources.iter().cloned()))
+        .collect();
+
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// The specs affected by the `changed` paths, by `name@version`. A spec is affected if it was loaded from a changed
+/// spec file or one of its sources changed, or if it depends on an affected spec, directly or not.
+pub fn affected(specs: &[BuildSpec], changed: &[PathBuf]) -> HashSet<String> {
+    let mut affected: HashSet<String> = specs
+        .iter()
+        .filter(|spec| {
+            changed.iter().any(|path| {
+                spec.path.as_deref() == Some(path.as_path())
+                    || spec.sources.iter().any(|source| path.starts_with(source))
+            })
+        })
+        .map(ToString::to_string)
+        .collect();
+
+    loop {
+        let dependents: Vec<_> = specs
+            .iter()
+            .filter(|spec| !affected.contains(&spec.to_string()))
+            .filter(|spec| {
+                spec.dependencies.iter().any(|dependency| {
+                    specs.iter().any(|other| {
+                        other.name == dependency.name
+                            && dependency.matches(&other.version)
+                            && affected.contains(&other.to_string())
+                    })
+                })
+            })
+            .map(ToString::to_string)
+            .collect();
+
+        if dependents.is_empty() {
+            return affected;
+        }
+        affected.extend(dependents);
+    }
+}
+
+fn snapshot(paths: &[PathBuf]) -> HashMap<PathBuf, SystemTime> {
+    let mut files = HashMap::new();
+    for path in paths {
+        visit(path, &mut files);
+    }
+
+    files
+}
+
+/// Records the modification time of `path`, or of every file within it if it's a directory. Paths which can't
+/// be read are skipped, which the next snapshot reports as a removal if they were there before.
+fn visit(path: &Path, files: &mut HashMap<PathBuf, SystemTime>) {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return,
+    };
+
+    if !metadata.is_dir() {
+        if let Ok(modified) = metadata.modified() {
+            files.insert(path.to_path_buf(), modified);
+        }
+        return;
+    }
+
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        // Symlinked directories aren't followed, since they may well link back to one of their parents.
+        let linked_directory =
+            entry.file_type().map_or(false, |kind| kind.is_symlink()) && entry.path().is_dir();
+
+        if !hidden && !linked_directory {
+            visit(&entry.path(), files);
+        }
+    }
+}
+
+/// Paths which were created, modified or removed between the two snapshots.
+fn diff(
+    before: &HashMap<PathBuf, SystemTime>,
+    after: &HashMap<PathBuf, SystemTime>,
+) -> Vec<PathBuf> {
+    let created_or_modified = after
+        .iter()
+        .filter(|(path, modified)| before.get(*path) != Some(modified))
+        .map(|(path, _)| path);
+    let removed = before.keys().filter(|path| !after.contains_key(*path));
+
+    created_or_modified.chain(removed).cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::affected;
+    use crate::build::spec::{parse, Format};
+
+    #[test]
+    fn test_affected() {
+        let toml = r#"
+            [[specs]]
+            name = "libfoo"
+            version = "1.0.0"
+            sources = ["libfoo/src"]
+
+            [[specs]]
+            name = "libbar"
+            version = "1.0.0"
+            dependencies = [{ name = "libfoo", version = "^1" }]
+
+            [[specs]]
+            name = "app"
+            version = "1.0.0"
+            dependencies = [{ name = "libbar", version = "^1" }]
+
+            [[specs]]
+            name = "tool"
+            version = "1.0.0"
+            dependencies = [{ name = "libfoo", version = "^2" }]
+        "#;
+        let mut specs = parse(toml, Format::Toml, Path::new("orca.toml")).unwrap();
+        specs[3].path = Some(PathBuf::from("tool.toml"));
+
+        let mut changed: Vec<_> = affected(&specs, &[PathBuf::from("libfoo/src/lib.rs")])
+            .into_iter()
+            .collect();
+        changed.sort();
+        assert_eq!(changed, vec!["app@1.0.0", "libbar@1.0.0", "libfoo@1.0.0"]);
+
+        let changed = affected(&specs, &[PathBuf::from("tool.toml")]);
+        assert_eq!(changed.len(), 1);
+        assert!(changed.contains("tool@1.0.0"));
+
+        assert!(affected(&specs, &[PathBuf::from("libfoo/README.md")]).is_empty());
+    }
+}
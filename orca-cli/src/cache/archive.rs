@@ -0,0 +1,240 @@
+use std::{
+    collections::HashSet,
+    io::{Read, Write},
+    path::Path,
+};
+
+use semver::Version;
+
+use crate::identifier;
+
+use super::{
+    index_key, index_target, object_key, segments,
+    storage::{Directory, Storage},
+    Cache, Error, Package,
+};
+
+impl Cache {
+    /// Writes `packages` to an archive, to move them to a cache which can't reach this one, such as one in an
+    /// air-gapped network. The archive is a zstd compressed tarball holding the indices and objects of the packages
+    /// under the same keys as a remote cache, such as `org/foo/1.0.0/index.json` and `objects/ab/abcd...`, see
+    /// [`Storage`]. Artifacts are verified before they're written, and objects shared between packages are only
+    /// written once.
+    pub fn export<W: Write>(&self, packages: &[Package], writer: W) -> Result<(), Error> {
+        let mut archive = tar::Builder::new(zstd::Encoder::new(writer, 0)?);
+        let mut objects = HashSet::new();
+
+        for package in packages {
+            self.verify(package)?;
+
+            let key = index_key(
+                &package.name.join("/"),
+                &package.version,
+                package.target.as_deref(),
+            );
+            archive.append_path_with_name(self.index_of(package), key)?;
+
+            for artifact in &package.artifacts {
+                if let Some(digest) = &artifact.digest {
+                    if objects.insert(digest.clone()) {
+                        archive.append_path_with_name(&artifact.path, object_key(digest))?;
+                    }
+                }
+            }
+        }
+
+        archive.into_inner()?.finish()?;
+        Ok(())
+    }
+
+    /// Stores every package in an archive written by [`Cache::export`], replacing the cached ones with the same
+    /// name, version and target. Objects are checked against their digests the same way as when pulling from a
+    /// remote cache, and each index is only moved into place once all of its objects are.
+    pub fn import<R: Read>(&self, reader: R) -> Result<Vec<Package>, Error> {
+        let unpacked = self.staging_path("import");
+        let result = self.import_from(reader, &unpacked);
+        let _ = std::fs::remove_dir_all(&unpacked);
+        result
+    }
+
+    fn import_from<R: Read>(&self, reader: R, unpacked: &Path) -> Result<Vec<Package>, Error> {
+        tar::Archive::new(zstd::Decoder::new(reader)?).unpack(unpacked)?;
+        let archive = Directory::new(unpacked);
+
+        let mut imported = Vec::new();
+        for key in archive.list("")? {
+            if key.starts_with("objects/") {
+                continue;
+            }
+
+            let (name, version, target) =
+                parse_index_key(&key).ok_or_else(|| Error::InvalidEntry(key.clone()))?;
+            if !self.pull_index(&archive, name, &version, target.as_deref())? {
+                continue;
+            }
+
+            let path = self.package_path(name, &version);
+            imported.extend(
+                self.variants(segments(name), version, &path)?
+                    .into_iter()
+                    .filter(|package| package.target == target),
+            );
+        }
+
+        Ok(imported)
+    }
+
+    /// `packages` along with the cached packages they depend on, directly or not, so an archive of them is enough
+    /// to build against. Each dependency is satisfied by the highest cached version which isn't yanked, in every
+    /// target it was built for.
+    pub fn with_dependencies(&self, mut packages: Vec<Package>) -> Result<Vec<Package>, Error> {
+        let mut included: HashSet<String> = packages.iter().map(ToString::to_string).collect();
+
+        let mut next = 0;
+        while next < packages.len() {
+            let dependencies = packages[next].dependencies.clone();
+            next += 1;
+
+            for (name, requirement) in &dependencies {
+                let versions = self.list_versions(name)?;
+                let highest = versions
+                    .iter()
+                    .filter(|package| {
+                        !package.yanked
+                            && identifier::satisfies(&package.version, requirement, false)
+                    })
+                    .map(|package| package.version.clone())
+                    .max();
+
+                for package in versions {
+                    if Some(&package.version) == highest.as_ref()
+                        && included.insert(package.to_string())
+                    {
+                        packages.push(package);
+                    }
+                }
+            }
+        }
+
+        Ok(packages)
+    }
+}
+
+/// Splits the key of an index, such as `org/foo/1.0.0/index.json`, into the package's name, version and target.
+fn parse_index_key(key: &str) -> Option<(&str, Version, Option<String>)> {
+    let (path, file_name) = key.rsplit_once('/')?;
+    let (name, version) = path.rsplit_once('/')?;
+    identifier::parse_name(name).ok()?;
+
+    Some((
+        name,
+        Version::parse(version).ok()?,
+        index_target(file_name)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use semver::{Version, VersionReq};
+
+    use super::parse_index_key;
+    use crate::cache::{Artifact, Cache, Package};
+
+    #[test]
+    fn test_export_and_import() {
+        let root = std::env::temp_dir().join(format!("orca-archive-test-{}", std::process::id()));
+        let build = root.join("build");
+        std::fs::create_dir_all(&build).unwrap();
+        std::fs::write(build.join("libfoo.so"), b"foo").unwrap();
+        std::fs::write(build.join("app"), b"app").unwrap();
+
+        let connected = Cache::new(root.join("connected")).unwrap();
+        let package = |name: &str, artifact: &str, dependencies: &[(&str, &str)]| Package {
+            name: vec![name.to_string()],
+            version: Version::parse("1.0.0").unwrap(),
+            target: None,
+            signature: None,
+            description: None,
+            dependencies: dependencies
+                .iter()
+                .map(|(name, requirement)| {
+                    (name.to_string(), VersionReq::parse(requirement).unwrap())
+                })
+                .collect::<BTreeMap<_, _>>(),
+            yanked: false,
+            artifacts: vec![Artifact::new(build.join(artifact))],
+        };
+        connected.put(&package("libfoo", "libfoo.so", &[])).unwrap();
+        let app = connected
+            .put(&package("app", "app", &[("libfoo", "^1")]))
+            .unwrap();
+        connected.put(&package("unrelated", "app", &[])).unwrap();
+
+        let packages = connected.with_dependencies(vec![app]).unwrap();
+        assert_eq!(packages.len(), 2);
+
+        let mut archive = Vec::new();
+        connected.export(&packages, &mut archive).unwrap();
+
+        let air_gapped = Cache::new(root.join("air-gapped")).unwrap();
+        assert_eq!(air_gapped.import(&archive[..]).unwrap().len(), 2);
+
+        let libfoo = air_gapped
+            .get("libfoo", &Version::parse("1.0.0").unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(std::fs::read(&libfoo.artifacts[0].path).unwrap(), b"foo");
+        assert!(air_gapped.list_versions("unrelated").unwrap().is_empty());
+        assert!(std::fs::read_dir(root.join("air-gapped/.tmp"))
+            .unwrap()
+            .next()
+            .is_none());
+
+        assert!(air_gapped.import(&b"not an archive"[..]).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_import_rejects_tampered_indices() {
+        let root =
+            std::env::temp_dir().join(format!("orca-archive-tampered-{}", std::process::id()));
+        let cache = Cache::new(root.join("cache")).unwrap();
+
+        for index in &[
+            r#"{"artifacts":{"/etc/passwd":"a"}}"#,
+            r#"{"artifacts":{"../../escaped":"a"}}"#,
+            r#"{"artifacts":{"libfoo.so":"a"}}"#,
+        ] {
+            let mut archive = tar::Builder::new(zstd::Encoder::new(Vec::new(), 0).unwrap());
+            let mut header = tar::Header::new_gnu();
+            header.set_size(index.len() as u64);
+            header.set_mode(0o644);
+            archive
+                .append_data(&mut header, "foo/1.0.0/index.json", index.as_bytes())
+                .unwrap();
+            let archive = archive.into_inner().unwrap().finish().unwrap();
+
+            assert!(cache.import(&archive[..]).is_err());
+            assert!(cache.list_versions("foo").unwrap().is_empty());
+        }
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_parse_index_key() {
+        let (name, version, target) = parse_index_key("org/foo/1.0.0/index.json").unwrap();
+        assert_eq!(name, "org/foo");
+        assert_eq!(version, Version::parse("1.0.0").unwrap());
+        assert_eq!(target, None);
+
+        let (_, _, target) = parse_index_key("foo/1.0.0/index.linux-x86_64.json").unwrap();
+        assert_eq!(target.as_deref(), Some("linux-x86_64"));
+
+        assert!(parse_index_key("foo/1.0.0/README").is_none());
+        assert!(parse_index_key("../1.0.0/index.json").is_none());
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use clap::Clap;
+use semver::Version;
+
+use crate::{
+    config::Config,
+    lockfile::{Lockfile, LOCKFILE},
+    output::OutputFormat,
+};
+
+use super::{Cache, Error};
+
+#[derive(Clap)]
+pub struct CacheCmd {
+    #[clap(subcommand)]
+    pub subcmd: CacheSubCommand,
+}
+
+#[derive(Clap)]
+pub enum CacheSubCommand {
+    #[clap(about = "List all cached packages.")]
+    List,
+    #[clap(about = "Show the cached versions of a package, and their artifacts.")]
+    Info(InfoCmd),
+    #[clap(about = "Remove a package, or a single version of it, from the cache.")]
+    Remove(RemoveCmd),
+    #[clap(about = "Remove everything from the cache.")]
+    Clean,
+    #[clap(
+        about = "Remove the least recently used packages beyond the configured limits, and artifacts which are no longer used."
+    )]
+    Gc(GcCmd),
+    #[clap(
+        about = "Write packages and their dependencies to an archive, to import into a cache which can't reach this one."
+    )]
+    Export(ExportCmd),
+    #[clap(about = "Store the packages in an archive written by orca cache export.")]
+    Import(ImportCmd),
+}
+
+#[derive(Clap)]
+pub struct InfoCmd {
+    pub name: String,
+}
+
+#[derive(Clap)]
+pub struct RemoveCmd {
+    #[clap(about = "Package to remove, as name or name@version.")]
+    pub package: String,
+}
+
+#[derive(Clap)]
+pub struct GcCmd {
+    #[clap(long, parse(try_from_str = parse_size), about = "Maximum size of the cache, such as 500M or 10G.")]
+    pub max_size: Option<u64>,
+    #[clap(long, parse(try_from_str = parse_age), about = "Maximum time since cached packages were last used, such as 12h or 30d.")]
+    pub max_age: Option<Duration>,
+    #[clap(
+        long,
+        about = "Maximum number of versions to keep of each package, removing the lowest ones first."
+    )]
+    pub max_versions_per_package: Option<usize>,
+}
+
+#[derive(Clap)]
+pub struct ExportCmd {
+    #[clap(
+        long,
+        short,
+        parse(from_os_str),
+        about = "Archive to write, such as cache.tar.zst."
+    )]
+    pub output: PathBuf,
+    #[clap(
+        about = "Packages to export along with their dependencies, as name or name@version. Defaults to every cached package."
+    )]
+    pub packages: Vec<String>,
+}
+
+#[derive(Clap)]
+pub struct ImportCmd {
+    #[clap(
+        parse(from_os_str),
+        about = "Archive to import, such as cache.tar.zst."
+    )]
+    pub archive: PathBuf,
+}
+
+impl CacheCmd {
+    pub(crate) fn execute(&self, config: &Config) -> Result<(), Error> {
+        let cache = Cache::configured(config)?;
+
+        match &self.subcmd {
+            CacheSubCommand::List => {
+                let packages = cache.list()?;
+
+                match config.output {
+                    OutputFormat::Text => {
+                        for package in packages {
+                            println!("{}", package);
+                        }
+                    }
+                    OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&packages)?),
+                }
+            }
+            CacheSubCommand::Info(info) => {
+                let packages = cache.list_versions(&info.name)?;
+
+                match config.output {
+                    OutputFormat::Text => {
+                        for package in packages {
+                            println!("{} ({} bytes)", package, package.size());
+                            for artifact in &package.artifacts {
+                                println!("  {}", artifact.name);
+                            }
+                        }
+                    }
+                    OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&packages)?),
+                }
+            }
+            CacheSubCommand::Remove(remove) => {
+                let (name, version) = parse_package(&remove.package)?;
+                for package in cache.remove(name, version.as_ref())? {
+                    println!("removed {}", package);
+                }
+            }
+            CacheSubCommand::Clean => cache.clean()?,
+            CacheSubCommand::Gc(gc) => {
+                // Flags override the configured limits one by one, rather than all at once.
+                let mut policy = config.gc.clone();
+                policy.max_size = gc.max_size.or(policy.max_size);
+                policy.max_age = gc.max_age.or(policy.max_age);
+                policy.max_versions_per_package = gc
+                    .max_versions_per_package
+                    .or(policy.max_versions_per_package);
+
+                // Packages the workspace is locked to are kept, so the next build doesn't have to fetch them again.
+                if Path::new(LOCKFILE).is_file() {
+                    let lockfile = Lockfile::load(LOCKFILE)?;
+                    policy.keep.extend(lockfile.pinned());
+                }
+
+                let report = cache.gc(&policy)?;
+
+                for package in &report.removed {
+                    println!("removed {}", package);
+                }
+                println!("freed {} bytes", report.freed);
+            }
+            CacheSubCommand::Export(export) => {
+                let packages = if export.packages.is_empty() {
+                    cache.list()?
+                } else {
+                    let mut selected = Vec::new();
+                    for package in &export.packages {
+                        let (name, version) = parse_package(package)?;
+                        let versions: Vec<_> = cache
+                            .list_versions(name)?
+                            .into_iter()
+                            .filter(|cached| {
+                                version
+                                    .as_ref()
+                                    .map_or(true, |version| &cached.version == version)
+                            })
+                            .collect();
+
+                        if versions.is_empty() {
+                            return Err(Error::NotCached(package.clone()));
+                        }
+                        selected.extend(versions);
+                    }
+
+                    cache.with_dependencies(selected)?
+                };
+
+                cache.export(&packages, File::create(&export.output)?)?;
+                println!(
+                    "exported {} packages to {}",
+                    packages.len(),
+                    export.output.display()
+                );
+            }
+            CacheSubCommand::Import(import) => {
+                for package in cache.import(File::open(&import.archive)?)? {
+                    println!("imported {}", package);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits `name@version` into its name and version. The version is optional.
+pub(crate) fn parse_package(package: &str) -> Result<(&str, Option<Version>), Error> {
+    match package.split_once('@') {
+        Some((name, version)) => Ok((
+            name,
+            Some(Version::parse(version).map_err(crate::identifier::ParsingError::from)?),
+        )),
+        None => Ok((package, None)),
+    }
+}
+
+/// Parses a size in bytes, optionally suffixed with K, M or G.
+pub(crate) fn parse_size(value: &str) -> Result<u64, String> {
+    let (number, multiplier) = match value.chars().last() {
+        Some('K') | Some('k') => (&value[..value.len() - 1], 1 << 10),
+        Some('M') | Some('m') => (&value[..value.len() - 1], 1 << 20),
+        Some('G') | Some('g') => (&value[..value.len() - 1], 1 << 30),
+        _ => (value, 1),
+    };
+
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(|| format!("invalid size: {}", value))
+}
+
+/// Parses a duration in seconds, optionally suffixed with s, m, h or d.
+pub(crate) fn parse_age(value: &str) -> Result<Duration, String> {
+    let (number, multiplier) = match value.chars().last() {
+        Some('s') => (&value[..value.len() - 1], 1),
+        Some('m') => (&value[..value.len() - 1], 60),
+        Some('h') => (&value[..value.len() - 1], 60 * 60),
+        Some('d') => (&value[..value.len() - 1], 24 * 60 * 60),
+        _ => (value, 1),
+    };
+
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("invalid age: {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{parse_age, parse_package, parse_size};
+
+    #[test]
+    fn test_argument_parsing() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("10M").unwrap(), 10 * 1024 * 1024);
+        assert!(parse_size("ten").is_err());
+        assert!(parse_size("99999999999999G").is_err());
+
+        assert_eq!(parse_age("12h").unwrap(), Duration::from_secs(12 * 3600));
+        assert_eq!(parse_age("30d").unwrap(), Duration::from_secs(30 * 86400));
+        assert!(parse_age("999999999999999999d").is_err());
+
+        let (name, version) = parse_package("org/foo@1.2.3").unwrap();
+        assert_eq!(name, "org/foo");
+        assert_eq!(version.unwrap().to_string(), "1.2.3");
+        assert!(parse_package("org/foo").unwrap().1.is_none());
+    }
+}
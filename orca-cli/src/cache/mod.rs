@@ -0,0 +1,1614 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    convert::TryFrom,
+    fmt,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use log::{debug, warn};
+use semver::{Version, VersionReq};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::Config,
+    identifier::{self, Identifier, NameIdentifier, VersionIdentifier},
+    registry::is_artifact_name,
+    signing::{self, Signature, Signer, Verifier},
+    target,
+};
+use thiserror::Error;
+
+mod archive;
+pub(crate) mod cmd;
+mod s3;
+pub mod storage;
+
+pub use cmd::CacheCmd;
+
+use storage::Storage;
+
+/// Name of the file within each package directory, which maps the package's artifacts to their digests.
+/// Target specific packages use `index.<target>.json` instead, so each target has an index of its own.
+const INDEX: &str = "index.json";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    IO(#[from] std::io::Error),
+    #[error("identifier parsing error")]
+    Identifier(#[from] identifier::ParsingError),
+    #[error("unnamed package: {0}")]
+    UnnamedPackage(PathBuf),
+    #[error("invalid artifact path: {0}")]
+    InvalidArtifact(PathBuf),
+    #[error("invalid package index {0}: {1}")]
+    Index(PathBuf, #[source] serde_json::Error),
+    #[error("artifact {name} is missing from the cache")]
+    MissingArtifact { name: String },
+    #[error("artifact {name} is corrupted, expected digest {expected} but found {actual}")]
+    Corrupted {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("failed to serialize output: {0}")]
+    Output(#[from] serde_json::Error),
+    #[error("{0}")]
+    Signature(#[from] signing::Error),
+    #[error("remote cache error: {0}")]
+    Storage(#[from] storage::Error),
+    #[error("{0}")]
+    Lockfile(#[from] crate::lockfile::Error),
+    #[error("{0} is not cached")]
+    NotCached(String),
+    #[error("invalid archive entry: {0}")]
+    InvalidEntry(String),
+    #[error("invalid package index entry {name} with digest {digest}")]
+    InvalidIndexEntry { name: String, digest: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Artifact {
+    /// Path of the artifact relative to the package, such as `lib/libfoo.so`.
+    pub name: String,
+    /// Where the contents of the artifact are stored.
+    pub path: PathBuf,
+    /// SHA-256 digest of the artifact's contents, if it is stored in the cache.
+    pub digest: Option<String>,
+}
+
+impl Artifact {
+    /// An artifact which isn't stored in the cache, named after its file name.
+    pub fn new(path: PathBuf) -> Self {
+        Artifact {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path,
+            digest: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Package {
+    #[serde(serialize_with = "serialize_name")]
+    pub name: Vec<NameIdentifier>,
+    pub version: VersionIdentifier,
+    /// The target the package was built for, or `None` if it isn't target specific.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<Signature>,
+    /// Short description of the package, taken from the spec it was built from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Version requirements on other packages, by package name, which the package was built against.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, VersionReq>,
+    /// Yanked packages aren't picked when resolving dependencies anew, but can still be used by lockfiles which
+    /// pin them. See [`Cache::yank`].
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub yanked: bool,
+    pub artifacts: Vec<Artifact>,
+}
+
+impl Package {
+    /// Total size of the package's artifacts in bytes. Artifacts which can't be found count as empty.
+    pub fn size(&self) -> u64 {
+        self.artifacts
+            .iter()
+            .filter_map(|artifact| std::fs::metadata(&artifact.path).ok())
+            .map(|metadata| metadata.len())
+            .sum()
+    }
+}
+
+impl fmt::Display for Package {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.name.join("/"), self.version)?;
+        if let Some(target) = &self.target {
+            write!(f, " ({})", target)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Serializes a package name as a single string, such as `org/package`.
+fn serialize_name<S: Serializer>(
+    name: &[NameIdentifier],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&name.join("/"))
+}
+
+/// Limits enforced by [`Cache::gc`], as configured in the `[gc]` section of a config file. Limits which are `None`
+/// are not enforced. Sizes and ages are written the same way as on the command line, such as `10G` or `30d`.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct GcPolicy {
+    /// Maximum total size of all cached artifacts in bytes. The least recently used packages are removed first.
+    #[serde(deserialize_with = "deserialize_size")]
+    pub max_size: Option<u64>,
+    /// Packages which haven't been used for longer than this are removed.
+    #[serde(deserialize_with = "deserialize_age")]
+    pub max_age: Option<Duration>,
+    /// Maximum number of versions kept of each package, for each target. The lowest versions are removed first.
+    pub max_versions_per_package: Option<usize>,
+    /// Packages which are never removed, by name and version, such as the ones the lockfile pins.
+    #[serde(skip)]
+    pub keep: HashSet<(String, Version)>,
+}
+
+impl GcPolicy {
+    /// True if any limit is enforced, otherwise running [`Cache::gc`] only removes unused artifacts.
+    pub fn is_limited(&self) -> bool {
+        self.max_size.is_some() || self.max_age.is_some() || self.max_versions_per_package.is_some()
+    }
+}
+
+fn deserialize_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|size| cmd::parse_size(&size).map_err(de::Error::custom))
+        .transpose()
+}
+
+fn deserialize_age<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|age| cmd::parse_age(&age).map_err(de::Error::custom))
+        .transpose()
+}
+
+/// Outcome of a [`Cache::gc`] run.
+#[derive(Debug, Default)]
+pub struct GcReport {
+    /// Packages which were removed from the cache.
+    pub removed: Vec<Package>,
+    /// Number of bytes freed by removing artifacts no package refers to anymore.
+    pub freed: u64,
+}
+
+/// Outcome of checking a single entry of the cache, see [`Cache::audit`].
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    /// The package the entry belongs to, as far as it could be determined.
+    pub package: String,
+    /// Location of the entry within the cache.
+    pub path: PathBuf,
+    /// What's wrong with the entry, or `None` if it is intact.
+    pub problem: Option<String>,
+}
+
+impl Finding {
+    fn problem<P: Into<String>>(package: String, path: PathBuf, problem: P) -> Self {
+        Finding {
+            package,
+            path,
+            problem: Some(problem.into()),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.problem.is_none()
+    }
+}
+
+/// Contents of a package's index file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Index {
+    /// Digests of the package's artifacts, by artifact name.
+    artifacts: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signature: Option<Signature>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    dependencies: BTreeMap<String, VersionReq>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    yanked: bool,
+}
+
+impl Index {
+    /// Checks that every artifact's name stays within the package and its digest is a SHA-256 digest, which
+    /// indices coming from a remote cache or an archive can't be trusted to do.
+    fn check(&self) -> Result<(), Error> {
+        match self
+            .artifacts
+            .iter()
+            .find(|(name, digest)| !is_artifact_name(name) || !is_digest(digest))
+        {
+            Some((name, digest)) => Err(Error::InvalidIndexEntry {
+                name: name.clone(),
+                digest: digest.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Local package cache. Each package is a directory under `name/segments/version/` containing an index
+/// of its artifacts, while the artifacts themselves are stored under `.objects/`, named after their SHA-256
+/// digest. Identical artifacts are therefore only stored once, regardless of how many packages contain them.
+///
+/// A version directory holds one index per target the package was built for, along with one for the package
+/// itself if it isn't target specific. Lookups prefer the cache's target, which is the host unless
+/// [`Cache::with_target`] says otherwise, and fall back to the package which isn't target specific.
+///
+/// Packages are signed as they are put into the cache if the cache has a [`Signer`], and their signatures
+/// are checked by [`Cache::verify`] along with their artifacts.
+///
+/// A cache can be backed by a remote [`Storage`], which holds the same indices and objects under the same keys,
+/// with the objects under `objects/`. Packages are pushed to it as they are put into the cache, and packages
+/// which are missing locally are pulled from it on lookup, so the local cache acts as a read-through cache of
+/// the remote one. Removing packages and collecting garbage only ever affects the local cache.
+pub struct Cache {
+    path: PathBuf,
+    target: String,
+    signer: Option<Signer>,
+    verifier: Verifier,
+    remote: Option<Box<dyn Storage>>,
+}
+
+impl Cache {
+    /// Directory the cache is stored in.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(Cache {
+            path: std::env::current_dir()?.join(path),
+            target: target::host(),
+            signer: None,
+            verifier: Verifier::default(),
+            remote: None,
+        })
+    }
+
+    /// Opens the configured cache, signing and verifying packages as configured. The configured
+    /// signing key is trusted as well, so locally built packages can be used.
+    /// If a remote cache is configured, it backs the local one.
+    pub fn configured(config: &Config) -> Result<Self, Error> {
+        let mut verifier = Verifier::new(&config.trusted_keys[..], config.require_signatures)?;
+        let signer = config.signing_key.as_ref().map(Signer::load).transpose()?;
+        if let Some(signer) = &signer {
+            verifier.trust(signer);
+        }
+
+        Ok(Cache {
+            signer,
+            verifier,
+            remote: config
+                .remote_cache
+                .as_ref()
+                .map(storage::open)
+                .transpose()?,
+            ..Cache::new(&config.cache_directory)?
+        })
+    }
+
+    /// Pushes packages to `remote` as they are put into the cache, and pulls packages from it which are missing locally.
+    pub fn with_remote(mut self, remote: Box<dyn Storage>) -> Self {
+        self.remote = Some(remote);
+        self
+    }
+
+    /// Looks up packages built for `target`, instead of the host.
+    pub fn with_target<S: Into<String>>(mut self, target: S) -> Self {
+        self.target = target.into();
+        self
+    }
+
+    pub fn list(&self) -> Result<Vec<Package>, Error> {
+        let mut packages = Vec::new();
+        debug!("listing all packages in {}", self.path.display());
+        self.walk(&mut packages, Vec::new(), &self.path)?;
+
+        Ok(packages)
+    }
+
+    /// Looks up the package with exactly the given name and version, verifying the integrity of its artifacts.
+    /// The package built for the cache's target is preferred over one which isn't target specific. Packages
+    /// which aren't cached locally are pulled from the remote cache, if there is one.
+    pub fn get(&self, name: &str, version: &Version) -> Result<Option<Package>, Error> {
+        let path = self.package_path(name, version);
+        let targets = [Some(self.target.as_str()), None];
+
+        if !targets
+            .iter()
+            .any(|target| index_path(&path, *target).is_file())
+        {
+            self.pull(name, version);
+        }
+
+        let target = targets
+            .iter()
+            .copied()
+            .find(|target| index_path(&path, *target).is_file());
+
+        let target = match target {
+            Some(target) => target,
+            None => return Ok(None),
+        };
+
+        let index = self.read_index(&index_path(&path, target))?;
+        let package = Package {
+            name: segments(name),
+            version: version.clone(),
+            target: target.map(String::from),
+            artifacts: self.artifacts(&index),
+            signature: index.signature,
+            description: index.description,
+            dependencies: index.dependencies,
+            yanked: index.yanked,
+        };
+
+        self.verify(&package)?;
+        self.touch(&package);
+        Ok(Some(package))
+    }
+
+    /// All cached versions of the named package, lowest version first, for every target they were built for.
+    pub fn list_versions(&self, name: &str) -> Result<Vec<Package>, Error> {
+        let path = self.name_path(name);
+
+        if !path.is_dir() {
+            return Ok(vec![]);
+        }
+
+        let mut packages = Vec::new();
+        for entry in std::fs::read_dir(&path)? {
+            let entry = entry?;
+
+            // Anything but versions in here are nested package names, which are packages of their own.
+            if let Identifier::Version(version) =
+                Identifier::try_from(entry.file_name().to_string_lossy().to_string())?
+            {
+                packages.extend(self.variants(segments(name), version, &entry.path())?);
+            }
+        }
+
+        packages.sort_by(|a, b| a.version.cmp(&b.version).then(a.target.cmp(&b.target)));
+        Ok(packages)
+    }
+
+    /// Finds the highest cached version of the named package which satisfies `required_version`,
+    /// verifying the integrity of its artifacts. Versions in the remote cache are considered as well.
+    /// Pre-releases are only considered if `prereleases` are included, see [`identifier::satisfies`],
+    /// and yanked versions are never considered.
+    pub fn find(
+        &self,
+        name: &str,
+        required_version: &VersionReq,
+        prereleases: bool,
+    ) -> Result<Option<Package>, Error> {
+        let mut variants: Vec<_> = self
+            .list_versions(name)?
+            .into_iter()
+            .map(|package| (package.version, package.target))
+            .collect();
+        variants.extend(self.remote_variants(name));
+
+        let mut versions: Vec<_> = variants
+            .into_iter()
+            .filter(|(version, target)| {
+                self.supports_target(target.as_deref())
+                    && identifier::satisfies(version, required_version, prereleases)
+            })
+            .map(|(version, _)| version)
+            .collect();
+        versions.sort();
+        versions.dedup();
+
+        // Whether a version is yanked is only known once its index is, which may have to be pulled first.
+        for version in versions.iter().rev() {
+            match self.get(name, version)? {
+                Some(package) if !package.yanked => return Ok(Some(package)),
+                _ => continue,
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Marks every cached variant of the given version of a package as yanked, or no longer yanked, returning the
+    /// packages which were marked. Yanked packages are skipped by [`Cache::find`], but can still be looked up
+    /// using [`Cache::get`], so builds pinning them in their lockfile keep working.
+    pub fn yank(&self, name: &str, version: &Version, yanked: bool) -> Result<Vec<Package>, Error> {
+        let path = self.package_path(name, version);
+        if !path.is_dir() {
+            return Ok(vec![]);
+        }
+
+        let mut packages = self.variants(segments(name), version.clone(), &path)?;
+        for package in &mut packages {
+            let index_path = index_path(&path, package.target.as_deref());
+            let mut index = self.read_index(&index_path)?;
+            index.yanked = yanked;
+
+            let staging = self.staging_path(&format!(
+                "{}-{}-{}",
+                package.name.join("-"),
+                version,
+                package.target.as_deref().unwrap_or("any")
+            ));
+            let contents = serde_json::to_vec_pretty(&index)
+                .map_err(|error| Error::Index(index_path.clone(), error))?;
+            std::fs::write(&staging, contents)?;
+            std::fs::rename(&staging, &index_path)?;
+            package.yanked = yanked;
+
+            if let Some(remote) = &self.remote {
+                let key = index_key(name, version, package.target.as_deref());
+                if let Err(error) = remote.put(&key, &index_path) {
+                    warn!("failed to push {} to the remote cache: {}", package, error);
+                }
+            }
+        }
+
+        Ok(packages)
+    }
+
+    /// True if `package` can be used when looking up packages for the cache's target.
+    pub fn supports(&self, package: &Package) -> bool {
+        self.supports_target(package.target.as_deref())
+    }
+
+    fn supports_target(&self, target: Option<&str>) -> bool {
+        target.map_or(true, |target| target == self.target)
+    }
+
+    /// Stores the artifacts of `build` in the cache, replacing any previously cached package with the same
+    /// name, version and target. The package's index is staged in a temporary file first, which is then moved
+    /// into place, so a failed or interrupted build never leaves a partial package behind. Directories are stored
+    /// file by file.
+    pub fn put(&self, build: &Package) -> Result<Package, Error> {
+        let name = build.name.join("/");
+        identifier::parse_name(&name)?;
+        let destination = self.package_path(&name, &build.version);
+        let index_path = index_path(&destination, build.target.as_deref());
+
+        let staging = self.staging_path(&format!(
+            "{}-{}-{}",
+            build.name.join("-"),
+            build.version,
+            build.target.as_deref().unwrap_or("any")
+        ));
+
+        let mut index = Index::default();
+        for artifact in &build.artifacts {
+            if artifact.name.is_empty() {
+                return Err(Error::InvalidArtifact(artifact.path.clone()));
+            }
+
+            self.store(&artifact.path, &artifact.name, &mut index)?;
+        }
+
+        let mut package = Package {
+            name: build.name.clone(),
+            version: build.version.clone(),
+            target: build.target.clone(),
+            artifacts: self.artifacts(&index),
+            signature: None,
+            description: build.description.clone(),
+            dependencies: build.dependencies.clone(),
+            yanked: build.yanked,
+        };
+
+        // Packages which are already signed, such as downloaded ones, keep their signature.
+        package.signature = match (&build.signature, &self.signer) {
+            (Some(signature), _) => Some(signature.clone()),
+            (None, Some(signer)) => Some(signer.sign(&package)),
+            (None, None) => None,
+        };
+        index.signature = package.signature.clone();
+        index.description = package.description.clone();
+        index.dependencies = package.dependencies.clone();
+        index.yanked = package.yanked;
+
+        let contents = serde_json::to_vec_pretty(&index)
+            .map_err(|error| Error::Index(index_path.clone(), error))?;
+        std::fs::write(&staging, contents)?;
+
+        std::fs::create_dir_all(&destination)?;
+        std::fs::rename(&staging, &index_path)?;
+
+        // The package is cached locally either way, so a remote cache which can't be reached isn't worth failing over.
+        if let Some(remote) = &self.remote {
+            if let Err(error) = self.push(remote.as_ref(), &package, &index_path) {
+                warn!("failed to push {} to the remote cache: {}", package, error);
+            }
+        }
+
+        Ok(package)
+    }
+
+    /// Uploads the objects of a package which the remote cache doesn't have yet, followed by its index.
+    fn push(
+        &self,
+        remote: &dyn Storage,
+        package: &Package,
+        index_path: &Path,
+    ) -> Result<(), storage::Error> {
+        for artifact in &package.artifacts {
+            if let Some(digest) = &artifact.digest {
+                let key = object_key(digest);
+                if !remote.exists(&key)? {
+                    remote.put(&key, &artifact.path)?;
+                }
+            }
+        }
+
+        let key = index_key(
+            &package.name.join("/"),
+            &package.version,
+            package.target.as_deref(),
+        );
+        remote.put(&key, index_path)
+    }
+
+    /// Downloads the given version of a package from the remote cache, preferring the one built for the cache's
+    /// target. Failures are logged and treated as if the package wasn't there, so it can still be built instead.
+    fn pull(&self, name: &str, version: &Version) {
+        let remote = match &self.remote {
+            Some(remote) => remote.as_ref(),
+            None => return,
+        };
+
+        for target in [Some(self.target.as_str()), None].iter().copied() {
+            match self.pull_index(remote, name, version, target) {
+                Ok(true) => break,
+                Ok(false) => continue,
+                Err(error) => {
+                    warn!(
+                        "failed to pull {}@{} from the remote cache: {}",
+                        name, version, error
+                    );
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Downloads a single index from the remote cache along with the objects it refers to, which are checked
+    /// against their digests before being kept. The index is only moved into place once all of its objects are.
+    fn pull_index(
+        &self,
+        remote: &dyn Storage,
+        name: &str,
+        version: &Version,
+        target: Option<&str>,
+    ) -> Result<bool, Error> {
+        let staging = self.staging_path(&format!(
+            "{}-{}-{}",
+            name.replace('/', "-"),
+            version,
+            target.unwrap_or("any")
+        ));
+
+        let result = self.pull_into(remote, name, version, target, &staging);
+        let _ = std::fs::remove_file(&staging);
+        result
+    }
+
+    fn pull_into(
+        &self,
+        remote: &dyn Storage,
+        name: &str,
+        version: &Version,
+        target: Option<&str>,
+        staging: &Path,
+    ) -> Result<bool, Error> {
+        if !remote.get(&index_key(name, version, target), staging)? {
+            return Ok(false);
+        }
+
+        debug!("pulling {}@{} from the remote cache", name, version);
+        let index = self.read_index(staging)?;
+        index.check()?;
+
+        for (artifact, expected) in &index.artifacts {
+            let object = self.object_path(expected);
+            if object.is_file() {
+                continue;
+            }
+
+            let download = self.staging_path(expected);
+            if !remote.get(&object_key(expected), &download)? {
+                return Err(Error::MissingArtifact {
+                    name: artifact.clone(),
+                });
+            }
+
+            let actual = digest(&download)?;
+            if actual != *expected {
+                let _ = std::fs::remove_file(&download);
+                return Err(Error::Corrupted {
+                    name: artifact.clone(),
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+
+            if let Some(parent) = object.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::rename(&download, &object)?;
+        }
+
+        let destination = self.package_path(name, version);
+        std::fs::create_dir_all(&destination)?;
+        std::fs::rename(staging, index_path(&destination, target))?;
+
+        Ok(true)
+    }
+
+    /// Versions and targets of the named package in the remote cache, if there is one.
+    fn remote_variants(&self, name: &str) -> Vec<(Version, Option<String>)> {
+        let remote = match &self.remote {
+            Some(remote) => remote,
+            None => return vec![],
+        };
+
+        let prefix = format!("{}/", name);
+        let keys = match remote.list(&prefix) {
+            Ok(keys) => keys,
+            Err(error) => {
+                warn!("failed to list {} in the remote cache: {}", name, error);
+                return vec![];
+            }
+        };
+
+        // Keys of nested packages have more segments, and are skipped along with anything else which isn't an index.
+        keys.iter()
+            .filter_map(|key| {
+                let (version, file_name) = key.strip_prefix(&prefix)?.split_once('/')?;
+                let version = Version::parse(version).ok()?;
+                Some((version, index_target(file_name)?))
+            })
+            .collect()
+    }
+
+    /// Checks that every artifact of `package` is present, and still matches the digest it was stored with,
+    /// and that the package's signature is acceptable.
+    pub fn verify(&self, package: &Package) -> Result<(), Error> {
+        self.verify_artifacts(package)?;
+        self.verifier.verify(package)?;
+        Ok(())
+    }
+
+    /// Checks that `build` would pass [`Cache::verify`] once stored, before anything is stored, so a package
+    /// which doesn't never replaces a cached one. Its artifacts must match the digests they claim to have, and
+    /// unsigned packages are acceptable if the cache signs them when storing them.
+    pub fn check(&self, build: &Package) -> Result<(), Error> {
+        self.verify_artifacts(build)?;
+        if build.signature.is_none() && self.signer.is_some() {
+            return Ok(());
+        }
+
+        self.verifier.verify(build)?;
+        Ok(())
+    }
+
+    fn verify_artifacts(&self, package: &Package) -> Result<(), Error> {
+        for artifact in &package.artifacts {
+            let expected = match &artifact.digest {
+                Some(digest) => digest,
+                None => continue,
+            };
+
+            if !artifact.path.is_file() {
+                return Err(Error::MissingArtifact {
+                    name: artifact.name.clone(),
+                });
+            }
+
+            let actual = digest(&artifact.path)?;
+            if actual != *expected {
+                return Err(Error::Corrupted {
+                    name: artifact.name.clone(),
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks every package in the cache, or only the versions of the named package, and reports on each of them.
+    /// Unlike [`Cache::list`], entries which can't be read don't fail the audit, but are reported as findings
+    /// along with packages whose artifacts or signatures don't check out, see [`Cache::verify`].
+    pub fn audit(
+        &self,
+        name: Option<&str>,
+        version: Option<&Version>,
+    ) -> Result<Vec<Finding>, Error> {
+        let mut findings = Vec::new();
+        let (segments, path) = match name {
+            Some(name) => (segments(name), self.name_path(name)),
+            None => (Vec::new(), self.path.clone()),
+        };
+
+        if path.is_dir() {
+            self.audit_walk(&mut findings, segments, &path, version)?;
+        }
+
+        Ok(findings)
+    }
+
+    /// Removes the given version of the named package, or every version of it if `version` is `None`,
+    /// for all targets. Returns the removed packages. Their artifacts are only deleted by [`Cache::gc`],
+    /// since other packages may share them.
+    pub fn remove(&self, name: &str, version: Option<&Version>) -> Result<Vec<Package>, Error> {
+        let removed: Vec<_> = self
+            .list_versions(name)?
+            .into_iter()
+            .filter(|package| version.map_or(true, |version| &package.version == version))
+            .collect();
+
+        for package in &removed {
+            self.remove_package(package)?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Removes a single package, along with its version directory once no target is left in it.
+    pub fn remove_package(&self, package: &Package) -> Result<(), Error> {
+        let path = self.package_path(&package.name.join("/"), &package.version);
+        std::fs::remove_file(index_path(&path, package.target.as_deref()))?;
+
+        if std::fs::read_dir(&path)?.next().is_none() {
+            std::fs::remove_dir(&path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes everything from the cache.
+    pub fn clean(&self) -> Result<(), Error> {
+        if self.path.exists() {
+            std::fs::remove_dir_all(&self.path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes packages exceeding the limits of `policy`, and then deletes every stored artifact which
+    /// is no longer part of any package, including ones left behind by [`Cache::remove`].
+    pub fn gc(&self, policy: &GcPolicy) -> Result<GcReport, Error> {
+        let mut report = GcReport::default();
+        let now = SystemTime::now();
+
+        // Least recently used packages first, since those are the first to go.
+        let mut packages = Vec::new();
+        for package in self.list()? {
+            let used = self.used_at(&package)?;
+            packages.push((used, package));
+        }
+        packages.sort_by_key(|(used, _)| *used);
+
+        let excess = policy
+            .max_versions_per_package
+            .map_or_else(HashSet::new, |max_versions| {
+                excess_versions(packages.iter().map(|(_, package)| package), max_versions)
+            });
+
+        // Objects are shared between packages, so removing a package only frees the objects no package still
+        // being kept refers to.
+        let mut references: HashMap<PathBuf, usize> = HashMap::new();
+        for (_, package) in &packages {
+            for object in self.referenced_objects(std::iter::once(package)) {
+                *references.entry(object).or_default() += 1;
+            }
+        }
+        let object_size =
+            |object: &Path| std::fs::metadata(object).map_or(0, |metadata| metadata.len());
+        let mut size: u64 = references.keys().map(|object| object_size(object)).sum();
+
+        let mut kept = Vec::new();
+        for (used, package) in packages {
+            let kept_by_policy = policy
+                .keep
+                .contains(&(package.name.join("/"), package.version.clone()));
+            let expired = policy.max_age.map_or(false, |max_age| {
+                now.duration_since(used).unwrap_or_default() > max_age
+            });
+            let oversized = policy.max_size.map_or(false, |max_size| size > max_size);
+
+            if !kept_by_policy && (expired || oversized || excess.contains(&package.to_string())) {
+                for object in self.referenced_objects(std::iter::once(&package)) {
+                    let count = references
+                        .get_mut(&object)
+                        .expect("every object of a package is counted");
+                    *count -= 1;
+                    if *count == 0 {
+                        size = size.saturating_sub(object_size(&object));
+                    }
+                }
+                self.remove_package(&package)?;
+                report.removed.push(package);
+            } else {
+                kept.push(package);
+            }
+        }
+
+        let referenced = self.referenced_objects(kept.iter());
+        let objects = self.path.join(".objects");
+        if objects.is_dir() {
+            for prefix in std::fs::read_dir(&objects)? {
+                for object in std::fs::read_dir(prefix?.path())? {
+                    let object = object?.path();
+                    if !referenced.contains(&object) {
+                        report.freed += std::fs::metadata(&object)?.len();
+                        std::fs::remove_file(&object)?;
+                    }
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// When the given package was last used, which is the modification time of its index. Indexes are written
+    /// when packages are stored, and touched whenever they're looked up, see [`Cache::touch`].
+    fn used_at(&self, package: &Package) -> Result<SystemTime, Error> {
+        Ok(std::fs::metadata(self.index_of(package))?.modified()?)
+    }
+
+    /// Records that the package was just used, so [`Cache::gc`] removes it after packages which haven't been.
+    /// Failing to do so, say because the cache is read-only, only makes the package look older than it is.
+    fn touch(&self, package: &Package) {
+        let touched = File::options()
+            .write(true)
+            .open(self.index_of(package))
+            .and_then(|index| index.set_modified(SystemTime::now()));
+
+        if let Err(error) = touched {
+            debug!("failed to record use of {}: {}", package, error);
+        }
+    }
+
+    fn index_of(&self, package: &Package) -> PathBuf {
+        index_path(
+            &self.package_path(&package.name.join("/"), &package.version),
+            package.target.as_deref(),
+        )
+    }
+
+    /// Every package stored in the version directory at `path`, one for each index it contains.
+    fn variants(
+        &self,
+        name: Vec<NameIdentifier>,
+        version: Version,
+        path: &Path,
+    ) -> Result<Vec<Package>, Error> {
+        let mut packages = Vec::new();
+
+        for entry in std::fs::read_dir(path)? {
+            let target = match index_target(&entry?.file_name().to_string_lossy()) {
+                Some(target) => target,
+                None => continue,
+            };
+
+            let index = self.read_index(&index_path(path, target.as_deref()))?;
+            packages.push(Package {
+                name: name.clone(),
+                version: version.clone(),
+                target,
+                artifacts: self.artifacts(&index),
+                signature: index.signature,
+                description: index.description,
+                dependencies: index.dependencies,
+                yanked: index.yanked,
+            });
+        }
+
+        Ok(packages)
+    }
+
+    /// Paths of all the objects the given packages refer to.
+    fn referenced_objects<'p, I>(&self, packages: I) -> HashSet<PathBuf>
+    where
+        I: Iterator<Item = &'p Package>,
+    {
+        packages
+            .flat_map(|package| package.artifacts.iter())
+            .map(|artifact| artifact.path.clone())
+            .collect()
+    }
+
+    /// Stores the file or directory at `path` as the artifact `name`, recording it in `index`.
+    fn store(&self, path: &Path, name: &str, index: &mut Index) -> Result<(), Error> {
+        if path.is_dir() {
+            for entry in std::fs::read_dir(path)? {
+                let entry = entry?;
+                let nested = format!("{}/{}", name, entry.file_name().to_string_lossy());
+                self.store(&entry.path(), &nested, index)?;
+            }
+
+            return Ok(());
+        }
+
+        let digest = digest(path)?;
+        let object = self.object_path(&digest);
+
+        // Objects are immutable, so if one already exists it holds exactly these contents.
+        if !object.exists() {
+            let staging = self.staging_path(&digest);
+            std::fs::copy(path, &staging)?;
+
+            if let Some(parent) = object.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::rename(&staging, &object)?;
+        }
+
+        index.artifacts.insert(name.to_string(), digest);
+        Ok(())
+    }
+
+    /// Reads the package index at `path`, without verifying the artifacts themselves.
+    fn read_index(&self, path: &Path) -> Result<Index, Error> {
+        let contents = std::fs::read(path)?;
+        serde_json::from_slice(&contents).map_err(|error| Error::Index(path.to_path_buf(), error))
+    }
+
+    /// The artifacts listed in a package index, pointing at the objects holding their contents.
+    fn artifacts(&self, index: &Index) -> Vec<Artifact> {
+        index
+            .artifacts
+            .iter()
+            .map(|(name, digest)| Artifact {
+                name: name.clone(),
+                path: self.object_path(digest),
+                digest: Some(digest.clone()),
+            })
+            .collect()
+    }
+
+    fn name_path(&self, name: &str) -> PathBuf {
+        segments(name)
+            .into_iter()
+            .fold(self.path.clone(), |path, segment| path.join(segment))
+    }
+
+    fn package_path(&self, name: &str, version: &Version) -> PathBuf {
+        self.name_path(name).join(version.to_string())
+    }
+
+    fn object_path(&self, digest: &str) -> PathBuf {
+        self.path.join(".objects").join(&digest[..2]).join(digest)
+    }
+
+    /// Unique path within the cache's staging area, which is on the same filesystem as the cache itself
+    /// so things can be moved into place atomically. Its parent directory is created if necessary.
+    fn staging_path(&self, name: &str) -> PathBuf {
+        let staging = self.path.join(".tmp");
+        let _ = std::fs::create_dir_all(&staging);
+        staging.join(format!("{}-{}", name, std::process::id()))
+    }
+
+    /// Same as [`Cache::walk`], but records findings instead of failing on entries it can't make sense of.
+    /// Nested packages are only audited when auditing the whole cache.
+    fn audit_walk(
+        &self,
+        findings: &mut Vec<Finding>,
+        package_name: Vec<NameIdentifier>,
+        path: &Path,
+        only: Option<&Version>,
+    ) -> Result<(), Error> {
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if file_name.starts_with('.') {
+                continue;
+            }
+
+            let path = entry.path();
+            match Identifier::try_from(file_name) {
+                Err(error) => findings.push(Finding::problem(
+                    package_name.join("/"),
+                    path,
+                    error.to_string(),
+                )),
+                Ok(Identifier::Name(name)) => {
+                    if only.is_some() {
+                        continue;
+                    }
+
+                    let mut extended_name = package_name.clone();
+                    extended_name.push(name);
+
+                    match identifier::parse_name(&extended_name.join("/")) {
+                        Err(error) => findings.push(Finding::problem(
+                            extended_name.join("/"),
+                            path,
+                            error.to_string(),
+                        )),
+                        Ok(_) => self.audit_walk(findings, extended_name, &path, only)?,
+                    }
+                }
+                Ok(Identifier::Version(version)) => {
+                    if only.map_or(false, |only| only != &version) {
+                        continue;
+                    }
+
+                    let package = format!("{}@{}", package_name.join("/"), version);
+                    if package_name.is_empty() {
+                        findings.push(Finding::problem(
+                            package,
+                            path,
+                            "version directory without a package name",
+                        ));
+                    } else if !path.is_dir() {
+                        findings.push(Finding::problem(package, path, "not a directory"));
+                    } else {
+                        self.audit_version(findings, &package_name, version, &path)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Audits every index within a version directory, along with the artifacts they refer to.
+    fn audit_version(
+        &self,
+        findings: &mut Vec<Finding>,
+        name: &[NameIdentifier],
+        version: Version,
+        path: &Path,
+    ) -> Result<(), Error> {
+        let mut indices = 0;
+
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            let mut description = format!("{}@{}", name.join("/"), version);
+
+            let target = match index_target(&entry.file_name().to_string_lossy()) {
+                Some(target) => target,
+                None => {
+                    findings.push(Finding::problem(
+                        description,
+                        entry.path(),
+                        "unexpected file in package directory",
+                    ));
+                    continue;
+                }
+            };
+            indices += 1;
+
+            if let Some(target) = &target {
+                description = format!("{} ({})", description, target);
+            }
+
+            let problem = match self.read_index(&entry.path()) {
+                Err(error) => Some(error.to_string()),
+                Ok(index) => {
+                    let package = Package {
+                        name: name.to_vec(),
+                        version: version.clone(),
+                        target,
+                        artifacts: self.artifacts(&index),
+                        signature: index.signature,
+                        description: index.description,
+                        dependencies: index.dependencies,
+                        yanked: index.yanked,
+                    };
+
+                    self.verify(&package).err().map(|error| error.to_string())
+                }
+            };
+
+            findings.push(Finding {
+                package: description,
+                path: entry.path(),
+                problem,
+            });
+        }
+
+        if indices == 0 {
+            findings.push(Finding::problem(
+                format!("{}@{}", name.join("/"), version),
+                path.to_path_buf(),
+                "package directory without an index",
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Recursively walks a path structure, looking for packages.
+    fn walk<P: AsRef<Path>>(
+        &self,
+        packages: &mut Vec<Package>,
+        package_name: Vec<NameIdentifier>,
+        path: P,
+    ) -> Result<(), Error> {
+        let entries: Result<Vec<_>, _> = std::fs::read_dir(&path)?.into_iter().collect();
+
+        for entry in entries? {
+            // Skip the object store and staging area, along with anything else which is hidden.
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+
+            let identifier = Identifier::try_from(entry.file_name().to_string_lossy().to_string())?;
+
+            match identifier {
+                Identifier::Name(name) => {
+                    let mut extended_name = package_name.clone();
+                    extended_name.push(name);
+                    self.walk(packages, extended_name, entry.path())?;
+                }
+                Identifier::Version(version) => {
+                    if package_name.is_empty() {
+                        return Err(Error::UnnamedPackage(path.as_ref().to_path_buf()));
+                    }
+
+                    packages.extend(self.variants(package_name.clone(), version, &entry.path())?);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Key of the index of the given package within a remote cache.
+fn index_key(name: &str, version: &Version, target: Option<&str>) -> String {
+    format!("{}/{}/{}", name, version, index_file(target))
+}
+
+/// Key of the object holding the contents with the given digest within a remote cache.
+fn object_key(digest: &str) -> String {
+    format!("objects/{}/{}", &digest[..2], digest)
+}
+
+/// Path of the index of the package for `target` within the version directory at `path`.
+fn index_path(path: &Path, target: Option<&str>) -> PathBuf {
+    path.join(index_file(target))
+}
+
+/// File name of the index of the package for `target`.
+fn index_file(target: Option<&str>) -> String {
+    match target {
+        Some(target) => format!("index.{}.json", target),
+        None => INDEX.to_string(),
+    }
+}
+
+/// The target of the package index named `file_name`, which is `Some(None)` for the index of a package which
+/// isn't target specific, or `None` if the file isn't an index at all.
+fn index_target(file_name: &str) -> Option<Option<String>> {
+    if file_name == INDEX {
+        return Some(None);
+    }
+
+    file_name
+        .strip_prefix("index.")
+        .and_then(|rest| rest.strip_suffix(".json"))
+        .map(|target| Some(target.to_string()))
+}
+
+/// The packages beyond the `max_versions` highest versions of the same package and target, as `name@version`
+/// along with the target if there is one, the way [`Package`] displays them.
+fn excess_versions<'a>(
+    packages: impl Iterator<Item = &'a Package>,
+    max_versions: usize,
+) -> HashSet<String> {
+    let mut versions: HashMap<(String, Option<String>), Vec<&Package>> = HashMap::new();
+    for package in packages {
+        versions
+            .entry((package.name.join("/"), package.target.clone()))
+            .or_default()
+            .push(package);
+    }
+
+    versions
+        .into_values()
+        .flat_map(|mut packages| {
+            packages.sort_by(|a, b| b.version.cmp(&a.version));
+            packages.into_iter().skip(max_versions)
+        })
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Splits a package name such as `org/package` into its segments.
+fn segments(name: &str) -> Vec<NameIdentifier> {
+    name.split(identifier::SEPARATOR)
+        .map(String::from)
+        .collect()
+}
+
+/// Whether `digest` is a hex-encoded SHA-256 digest, such as the ones [`digest`] returns.
+fn is_digest(digest: &str) -> bool {
+    digest.len() == 64 && digest.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+/// Hex-encoded SHA-256 digest of the file at `path`.
+pub(crate) fn digest(path: &Path) -> Result<String, Error> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Lists the entries of a directory outside of the cache, such as a build directory, as artifacts.
+pub(crate) fn list_artifacts<P: AsRef<Path>>(path: P) -> Result<Vec<Artifact>, Error> {
+    let entries: Result<Vec<_>, _> = std::fs::read_dir(path)?.into_iter().collect();
+
+    Ok(entries?
+        .into_iter()
+        .map(|entry| Artifact::new(entry.path()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        fs::File,
+        time::{Duration, SystemTime},
+    };
+
+    use semver::{Version, VersionReq};
+
+    use super::{object_key, storage::Directory, Artifact, Cache, GcPolicy, Package};
+
+    #[test]
+    fn test_cache_listing() {
+        let cache = Cache::new(".orca/cache").unwrap();
+
+        println!("{:#?}", cache.list().unwrap());
+    }
+
+    #[test]
+    fn test_put_and_find() {
+        let root = std::env::temp_dir().join(format!("orca-cache-test-{}", std::process::id()));
+        let build = root.join("build");
+        std::fs::create_dir_all(&build).unwrap();
+        std::fs::write(build.join("libfoo.so"), b"foo").unwrap();
+
+        let cache = Cache::new(root.join("cache")).unwrap();
+        for version in &["1.0.0", "1.2.0", "2.0.0"] {
+            cache
+                .put(&Package {
+                    name: vec!["org".to_string(), "foo".to_string()],
+                    version: Version::parse(version).unwrap(),
+                    target: None,
+                    signature: None,
+                    description: None,
+                    dependencies: BTreeMap::new(),
+                    yanked: false,
+                    artifacts: vec![Artifact::new(build.join("libfoo.so"))],
+                })
+                .unwrap();
+        }
+
+        assert_eq!(cache.list_versions("org/foo").unwrap().len(), 3);
+        assert!(cache.list_versions("org/bar").unwrap().is_empty());
+
+        let found = cache
+            .find("org/foo", &VersionReq::parse("^1.0").unwrap(), false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.version, Version::parse("1.2.0").unwrap());
+        assert_eq!(found.artifacts.len(), 1);
+        assert_eq!(std::fs::read(&found.artifacts[0].path).unwrap(), b"foo");
+
+        // Yanked versions are skipped when finding, but not when looking them up exactly.
+        let version = Version::parse("1.2.0").unwrap();
+        assert_eq!(cache.yank("org/foo", &version, true).unwrap().len(), 1);
+        let found = cache
+            .find("org/foo", &VersionReq::parse("^1.0").unwrap(), false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.version, Version::parse("1.0.0").unwrap());
+        assert!(cache.get("org/foo", &version).unwrap().unwrap().yanked);
+        cache.yank("org/foo", &version, false).unwrap();
+
+        let exact = cache
+            .get("org/foo", &Version::parse("2.0.0").unwrap())
+            .unwrap();
+        assert!(exact.is_some());
+
+        // Every version holds the same artifact, so it's only stored once.
+        let objects: Vec<_> = cache
+            .list_versions("org/foo")
+            .unwrap()
+            .into_iter()
+            .map(|package| package.artifacts[0].path.clone())
+            .collect();
+        assert!(objects.iter().all(|object| object == &objects[0]));
+
+        // Tampering with the stored artifact is detected.
+        std::fs::write(&objects[0], b"bar").unwrap();
+        assert!(cache
+            .get("org/foo", &Version::parse("2.0.0").unwrap())
+            .is_err());
+
+        // All three versions share the tampered artifact, and an index which can't be read is reported as well.
+        std::fs::write(root.join("cache/org/foo/2.0.0/index.json"), b"{").unwrap();
+        std::fs::create_dir_all(root.join("cache/org/foo/3.0.0")).unwrap();
+        let findings = cache.audit(None, None).unwrap();
+        assert_eq!(findings.len(), 4);
+        assert!(findings.iter().all(|finding| !finding.is_ok()));
+        assert_eq!(
+            cache
+                .audit(Some("org/foo"), Some(&Version::parse("1.0.0").unwrap()))
+                .unwrap()
+                .len(),
+            1
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_gc() {
+        let root = std::env::temp_dir().join(format!("orca-cache-gc-{}", std::process::id()));
+        let build = root.join("build");
+        std::fs::create_dir_all(&build).unwrap();
+
+        let cache = Cache::new(root.join("cache")).unwrap();
+        let versions: Vec<_> = ["1.0.0", "1.1.0", "2.0.0"]
+            .iter()
+            .map(|version| Version::parse(version).unwrap())
+            .collect();
+        for version in &versions {
+            let artifact = build.join(format!("libfoo-{}.so", version));
+            std::fs::write(&artifact, version.to_string()).unwrap();
+            cache
+                .put(&Package {
+                    name: vec!["org".to_string(), "foo".to_string()],
+                    version: version.clone(),
+                    target: None,
+                    signature: None,
+                    description: None,
+                    dependencies: BTreeMap::new(),
+                    yanked: false,
+                    artifacts: vec![Artifact::new(artifact)],
+                })
+                .unwrap();
+
+            let two_days_ago = SystemTime::now() - Duration::from_secs(2 * 24 * 60 * 60);
+            File::options()
+                .write(true)
+                .open(root.join(format!("cache/org/foo/{}/index.json", version)))
+                .unwrap()
+                .set_modified(two_days_ago)
+                .unwrap();
+        }
+
+        // Looking a package up counts as using it, and kept packages are never removed.
+        cache.get("org/foo", &versions[2]).unwrap().unwrap();
+        let mut policy = GcPolicy {
+            max_age: Some(Duration::from_secs(24 * 60 * 60)),
+            ..GcPolicy::default()
+        };
+        policy
+            .keep
+            .insert(("org/foo".to_string(), versions[0].clone()));
+
+        let report = cache.gc(&policy).unwrap();
+        assert_eq!(report.removed.len(), 1);
+        assert_eq!(report.removed[0].version, versions[1]);
+        assert_eq!(report.freed, 5);
+
+        let report = cache
+            .gc(&GcPolicy {
+                max_versions_per_package: Some(1),
+                ..GcPolicy::default()
+            })
+            .unwrap();
+        assert_eq!(report.removed.len(), 1);
+        assert_eq!(report.removed[0].version, versions[0]);
+
+        let remaining = cache.list_versions("org/foo").unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].version, versions[2]);
+
+        // Removing a package whose objects are shared with another one frees nothing, so both have to go.
+        let shared = build.join("libbar.so");
+        std::fs::write(&shared, vec![0u8; 1000]).unwrap();
+        for (version, days_ago) in &[(&versions[0], 3u64), (&versions[1], 2u64)] {
+            cache
+                .put(&Package {
+                    name: vec!["org".to_string(), "bar".to_string()],
+                    version: (*version).clone(),
+                    target: None,
+                    signature: None,
+                    description: None,
+                    dependencies: BTreeMap::new(),
+                    yanked: false,
+                    artifacts: vec![Artifact::new(shared.clone())],
+                })
+                .unwrap();
+
+            File::options()
+                .write(true)
+                .open(root.join(format!("cache/org/bar/{}/index.json", version)))
+                .unwrap()
+                .set_modified(SystemTime::now() - Duration::from_secs(days_ago * 24 * 60 * 60))
+                .unwrap();
+        }
+
+        let report = cache
+            .gc(&GcPolicy {
+                max_size: Some(999),
+                ..GcPolicy::default()
+            })
+            .unwrap();
+        assert_eq!(report.removed.len(), 2);
+        assert_eq!(report.freed, 1000);
+        assert_eq!(cache.list_versions("org/foo").unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_targets() {
+        let root = std::env::temp_dir().join(format!("orca-cache-targets-{}", std::process::id()));
+        let build = root.join("build");
+        std::fs::create_dir_all(&build).unwrap();
+        std::fs::write(build.join("libfoo.so"), b"linux").unwrap();
+        std::fs::write(build.join("libfoo.dylib"), b"darwin").unwrap();
+
+        let linux = Cache::new(root.join("cache"))
+            .unwrap()
+            .with_target("linux-x86_64");
+        let darwin = Cache::new(root.join("cache"))
+            .unwrap()
+            .with_target("darwin-aarch64");
+        let version = Version::parse("1.0.0").unwrap();
+
+        for (target, artifact) in &[
+            ("linux-x86_64", "libfoo.so"),
+            ("darwin-aarch64", "libfoo.dylib"),
+        ] {
+            linux
+                .put(&Package {
+                    name: vec!["foo".to_string()],
+                    version: version.clone(),
+                    target: Some(target.to_string()),
+                    signature: None,
+                    description: None,
+                    dependencies: BTreeMap::new(),
+                    yanked: false,
+                    artifacts: vec![Artifact::new(build.join(artifact))],
+                })
+                .unwrap();
+        }
+
+        assert_eq!(linux.list_versions("foo").unwrap().len(), 2);
+        let found = darwin.get("foo", &version).unwrap().unwrap();
+        assert_eq!(found.target.as_deref(), Some("darwin-aarch64"));
+        assert_eq!(found.artifacts[0].name, "libfoo.dylib");
+
+        let windows = Cache::new(root.join("cache"))
+            .unwrap()
+            .with_target("windows-x86_64");
+        assert!(windows.get("foo", &version).unwrap().is_none());
+        assert!(windows
+            .find("foo", &VersionReq::parse("^1").unwrap(), false)
+            .unwrap()
+            .is_none());
+
+        // Removing the version removes it for every target.
+        assert_eq!(linux.remove("foo", Some(&version)).unwrap().len(), 2);
+        assert!(linux.list_versions("foo").unwrap().is_empty());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_remote() {
+        let root = std::env::temp_dir().join(format!("orca-cache-remote-{}", std::process::id()));
+        let build = root.join("build");
+        std::fs::create_dir_all(&build).unwrap();
+        std::fs::write(build.join("libfoo.so"), b"foo").unwrap();
+
+        let producer = Cache::new(root.join("producer"))
+            .unwrap()
+            .with_remote(Box::new(Directory::new(root.join("remote"))));
+        for version in &["1.0.0", "1.1.0"] {
+            producer
+                .put(&Package {
+                    name: vec!["org".to_string(), "foo".to_string()],
+                    version: Version::parse(version).unwrap(),
+                    target: None,
+                    signature: None,
+                    description: None,
+                    dependencies: BTreeMap::new(),
+                    yanked: false,
+                    artifacts: vec![Artifact::new(build.join("libfoo.so"))],
+                })
+                .unwrap();
+        }
+
+        // A second machine with an empty local cache finds the package remotely, and keeps a local copy.
+        let consumer = Cache::new(root.join("consumer"))
+            .unwrap()
+            .with_remote(Box::new(Directory::new(root.join("remote"))));
+        let found = consumer
+            .find("org/foo", &VersionReq::parse("^1").unwrap(), false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.version, Version::parse("1.1.0").unwrap());
+        assert_eq!(std::fs::read(&found.artifacts[0].path).unwrap(), b"foo");
+        assert!(found.artifacts[0].path.starts_with(root.join("consumer")));
+        assert_eq!(consumer.list_versions("org/foo").unwrap().len(), 1);
+
+        // Corrupted remote objects are never kept.
+        std::fs::remove_dir_all(root.join("consumer")).unwrap();
+        let object = object_key(found.artifacts[0].digest.as_ref().unwrap());
+        std::fs::write(root.join("remote").join(object), b"bar").unwrap();
+        assert!(consumer
+            .get("org/foo", &Version::parse("1.0.0").unwrap())
+            .unwrap()
+            .is_none());
+
+        // Indices naming artifacts outside the package, or with malformed digests, are rejected before anything
+        // is downloaded.
+        let digest = found.artifacts[0].digest.clone().unwrap();
+        for (name, digest) in &[("../../escaped", digest.as_str()), ("libfoo.so", "a")] {
+            let index = root.join("remote/org/foo/2.0.0/index.json");
+            std::fs::create_dir_all(index.parent().unwrap()).unwrap();
+            std::fs::write(
+                &index,
+                format!(r#"{{"artifacts":{{"{}":"{}"}}}}"#, name, digest),
+            )
+            .unwrap();
+
+            assert!(consumer
+                .get("org/foo", &Version::parse("2.0.0").unwrap())
+                .unwrap()
+                .is_none());
+            assert!(!root.join("consumer/org/foo/2.0.0").exists());
+        }
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
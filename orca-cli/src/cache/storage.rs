@@ -0,0 +1,131 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::s3::{S3Config, S3};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    IO(#[from] io::Error),
+    #[error("remote cache request failed: {0}")]
+    Http(#[from] Box<ureq::Error>),
+    #[error("missing credentials for the remote cache: {0}")]
+    Credentials(String),
+    #[error("invalid response from the remote cache: {0}")]
+    InvalidResponse(String),
+}
+
+/// A place to share cached packages, such as a bucket which every CI runner can reach. Entries are addressed
+/// by keys mirroring the layout of the local cache, such as `org/foo/1.0.0/index.json` and `objects/ab/abcd...`.
+pub trait Storage: Send + Sync {
+    /// Downloads the entry at `key` into a file at `path`. Returns `false` if there is no such entry.
+    fn get(&self, key: &str, path: &Path) -> Result<bool, Error>;
+
+    /// Uploads the file at `path` as the entry at `key`, replacing it if it already exists.
+    fn put(&self, key: &str, path: &Path) -> Result<(), Error>;
+
+    fn exists(&self, key: &str) -> Result<bool, Error>;
+
+    /// Keys of all entries starting with `prefix`.
+    fn list(&self, prefix: &str) -> Result<Vec<String>, Error>;
+}
+
+/// Configuration of the remote cache, as it appears in the `[remote_cache]` section of the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RemoteCache {
+    /// A directory, typically on a network share.
+    Directory { path: PathBuf },
+    /// A bucket in S3, or any other service compatible with S3.
+    S3(S3Config),
+}
+
+/// Connects to the configured remote cache.
+pub fn open(config: &RemoteCache) -> Result<Box<dyn Storage>, Error> {
+    Ok(match config {
+        RemoteCache::Directory { path } => Box::new(Directory::new(path)),
+        RemoteCache::S3(config) => Box::new(S3::new(config)?),
+    })
+}
+
+/// Storage in a plain directory, where each key is a path relative to the directory.
+pub struct Directory {
+    path: PathBuf,
+}
+
+impl Directory {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Directory {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    fn walk(&self, directory: &Path, keys: &mut Vec<String>) -> io::Result<()> {
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                self.walk(&path, keys)?;
+            } else if let Ok(relative) = path.strip_prefix(&self.path) {
+                let segments: Vec<_> = relative
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect();
+                keys.push(segments.join("/"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Storage for Directory {
+    fn get(&self, key: &str, path: &Path) -> Result<bool, Error> {
+        let source = self.path.join(key);
+        if !source.is_file() {
+            return Ok(false);
+        }
+
+        std::fs::copy(source, path)?;
+        Ok(true)
+    }
+
+    fn put(&self, key: &str, path: &Path) -> Result<(), Error> {
+        let destination = self.path.join(key);
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Copy next to the destination first, so readers never see a partial entry.
+        let staging = destination.with_extension(format!("tmp-{}", std::process::id()));
+        std::fs::copy(path, &staging)?;
+        std::fs::rename(&staging, &destination)?;
+        Ok(())
+    }
+
+    fn exists(&self, key: &str) -> Result<bool, Error> {
+        Ok(self.path.join(key).is_file())
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>, Error> {
+        // Only the directory the prefix points into needs to be walked.
+        let directory = match prefix.rfind('/') {
+            Some(end) => self.path.join(&prefix[..end]),
+            None => self.path.clone(),
+        };
+
+        let mut keys = Vec::new();
+        if directory.is_dir() {
+            self.walk(&directory, &mut keys)?;
+        }
+
+        keys.retain(|key| key.starts_with(prefix));
+        keys.sort();
+        Ok(keys)
+    }
+}
@@ -0,0 +1,351 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    build::{
+        executor::Executor,
+        overrides::Override,
+        sandbox::{Environment, Sandbox},
+        spec::Hooks,
+    },
+    cache::{storage::RemoteCache, GcPolicy},
+    credentials::{self, Credentials},
+    output::{OutputFormat, Verbosity},
+    registry::{self, Route},
+};
+
+/// Name of the project configuration file, which is looked for in the current directory and its parents.
+pub const PROJECT_CONFIG: &str = "orca.toml";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to read config file {0}: {1}")]
+    IO(PathBuf, #[source] std::io::Error),
+    #[error("failed to parse config file {0}: {1}")]
+    Parse(PathBuf, #[source] toml::de::Error),
+    #[error("{0}")]
+    Credentials(#[from] credentials::Error),
+}
+
+/// Settings as they appear in a single config file. Anything left out falls back to the layer below it.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ConfigFile {
+    pub cache_directory: Option<PathBuf>,
+    pub registry: Option<String>,
+    /// Registries to look for packages in before the registry, in order.
+    pub mirrors: Option<Vec<String>>,
+    /// Registries to look for specific packages in instead, see [`Route`].
+    pub routes: Option<Vec<Route>>,
+    pub jobs: Option<usize>,
+    /// Name of the environment variable holding the token for the registry.
+    pub token_env: Option<String>,
+    pub output: Option<OutputFormat>,
+    pub verbosity: Option<Verbosity>,
+    /// Hooks which run for every build, see [`Hooks`].
+    pub hooks: Option<Hooks>,
+    /// Keyfile holding the ed25519 key packages are signed with as they are put into the cache.
+    pub signing_key: Option<PathBuf>,
+    /// Hex-encoded ed25519 public keys whose package signatures are trusted.
+    pub trusted_keys: Option<Vec<String>>,
+    /// Refuse packages which aren't signed by a trusted key.
+    pub require_signatures: Option<bool>,
+    /// Remote cache shared between machines, see [`RemoteCache`].
+    pub remote_cache: Option<RemoteCache>,
+    /// Number of times a failed download from the registry is retried.
+    pub retries: Option<u32>,
+    /// How to authenticate with each registry, by url, see [`Credentials`].
+    pub credentials: Option<BTreeMap<String, Credentials>>,
+    /// What to resolve dependencies on each package to instead, by package name, see [`Override`].
+    pub overrides: Option<BTreeMap<String, Override>>,
+    pub workspace: Option<Workspace>,
+    /// How isolated build steps are from the host, see [`Sandbox`].
+    pub sandbox: Option<Sandbox>,
+    /// Where build steps run, see [`Executor`].
+    pub executor: Option<Executor>,
+    /// Directory `orca install` installs packages into.
+    pub prefix: Option<PathBuf>,
+    /// Limits of the cache, enforced by `orca cache gc` and after every build, see [`GcPolicy`].
+    pub gc: Option<GcPolicy>,
+    /// Environment variables set for the steps of every build, see [`Environment`].
+    pub env: Option<BTreeMap<String, String>>,
+    /// Host environment variables the steps of every build don't inherit.
+    pub env_remove: Option<Vec<String>>,
+    /// Don't let the steps of any build inherit host environment variables.
+    pub env_clear: Option<bool>,
+}
+
+/// The `[workspace]` section of an `orca.toml`, listing the specs which make up the project. Commands which take
+/// `--spec` use the workspace members instead when none are given.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Workspace {
+    /// Spec files, directories or glob patterns, relative to the `orca.toml`.
+    pub members: Vec<String>,
+}
+
+impl ConfigFile {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let contents =
+            std::fs::read_to_string(path).map_err(|error| Error::IO(path.to_path_buf(), error))?;
+
+        let mut config: ConfigFile =
+            toml::from_str(&contents).map_err(|error| Error::Parse(path.to_path_buf(), error))?;
+
+        // Paths within a config file are relative to the file itself, not to wherever orca happens to run.
+        if let Some(parent) = path.parent() {
+            config.cache_directory = config
+                .cache_directory
+                .map(|cache_directory| parent.join(cache_directory));
+            config.signing_key = config
+                .signing_key
+                .map(|signing_key| parent.join(signing_key));
+
+            if let Some(RemoteCache::Directory { path }) = &mut config.remote_cache {
+                *path = parent.join(&path);
+            }
+
+            config.credentials = config.credentials.map(|credentials| {
+                credentials
+                    .into_iter()
+                    .map(|(url, credentials)| {
+                        (
+                            credentials::normalize(&url),
+                            credentials.relative_to(parent),
+                        )
+                    })
+                    .collect()
+            });
+
+            if let Some(workspace) = &mut config.workspace {
+                for member in &mut workspace.members {
+                    *member = parent.join(&member).to_string_lossy().to_string();
+                }
+            }
+
+            config.overrides = config.overrides.map(|overrides| {
+                overrides
+                    .into_iter()
+                    .map(|(name, value)| match value {
+                        Override::Path(path) => (name, Override::Path(parent.join(path))),
+                        version => (name, version),
+                    })
+                    .collect()
+            });
+        }
+
+        Ok(config)
+    }
+
+    /// Settings from `other` take precedence over the ones in `self`.
+    fn layer(self, other: ConfigFile) -> ConfigFile {
+        ConfigFile {
+            cache_directory: other.cache_directory.or(self.cache_directory),
+            registry: other.registry.or(self.registry),
+            mirrors: other.mirrors.or(self.mirrors),
+            routes: other.routes.or(self.routes),
+            jobs: other.jobs.or(self.jobs),
+            token_env: other.token_env.or(self.token_env),
+            output: other.output.or(self.output),
+            verbosity: other.verbosity.or(self.verbosity),
+            hooks: other.hooks.or(self.hooks),
+            signing_key: other.signing_key.or(self.signing_key),
+            trusted_keys: other.trusted_keys.or(self.trusted_keys),
+            require_signatures: other.require_signatures.or(self.require_signatures),
+            remote_cache: other.remote_cache.or(self.remote_cache),
+            retries: other.retries.or(self.retries),
+            // Credentials, overrides and env are layered per registry, package and variable, rather than all at once.
+            credentials: merge(self.credentials, other.credentials),
+            overrides: merge(self.overrides, other.overrides),
+            env: merge(self.env, other.env),
+            workspace: other.workspace.or(self.workspace),
+            sandbox: other.sandbox.or(self.sandbox),
+            executor: other.executor.or(self.executor),
+            prefix: other.prefix.or(self.prefix),
+            gc: other.gc.or(self.gc),
+            env_remove: other.env_remove.or(self.env_remove),
+            env_clear: other.env_clear.or(self.env_clear),
+        }
+    }
+}
+
+/// Combines two maps of settings, where entries in `other` take precedence over the ones in `map`.
+fn merge<V>(
+    map: Option<BTreeMap<String, V>>,
+    other: Option<BTreeMap<String, V>>,
+) -> Option<BTreeMap<String, V>> {
+    match (map, other) {
+        (Some(mut map), Some(other)) => {
+            map.extend(other);
+            Some(map)
+        }
+        (map, other) => other.or(map),
+    }
+}
+
+/// Effective settings, combining the built-in defaults, the user config at `~/.config/orca/config.toml`,
+/// the project's `orca.toml`, and finally any command line flags, each overriding the ones before it.
+#[derive(Debug)]
+pub struct Config {
+    pub cache_directory: PathBuf,
+    pub registry: Option<String>,
+    pub mirrors: Vec<String>,
+    pub routes: Vec<Route>,
+    /// Number of packages to build at the same time, if configured.
+    pub jobs: Option<usize>,
+    /// Token for registries without credentials of their own, read from the configured environment
+    /// variable, or `ORCA_TOKEN`.
+    pub token: Option<String>,
+    pub output: OutputFormat,
+    pub verbosity: Verbosity,
+    pub hooks: Hooks,
+    pub signing_key: Option<PathBuf>,
+    pub trusted_keys: Vec<String>,
+    pub require_signatures: bool,
+    pub remote_cache: Option<RemoteCache>,
+    pub retries: u32,
+    /// Credentials by registry url, including the ones stored using `orca login`.
+    pub credentials: BTreeMap<String, Credentials>,
+    pub overrides: BTreeMap<String, Override>,
+    pub workspace: Option<Workspace>,
+    pub sandbox: Sandbox,
+    pub executor: Executor,
+    pub prefix: Option<PathBuf>,
+    pub gc: GcPolicy,
+    pub environment: Environment,
+}
+
+impl Config {
+    /// Loads all the config files which exist, and applies `flags` on top.
+    pub fn load(flags: ConfigFile) -> Result<Self, Error> {
+        // Credentials stored using `orca login` can be overridden by any config file.
+        let mut config = ConfigFile {
+            credentials: Some(credentials::load_stored()?),
+            ..ConfigFile::default()
+        };
+
+        for path in user_config().into_iter().chain(project_config()) {
+            config = config.layer(ConfigFile::load(path)?);
+        }
+
+        Ok(Config::from(config.layer(flags)))
+    }
+
+    /// The given `--spec` arguments, or the members of the workspace if there are none.
+    pub fn specs(&self, arguments: &[String]) -> Vec<String> {
+        match &self.workspace {
+            Some(workspace) if arguments.is_empty() => workspace.members.clone(),
+            _ => arguments.to_vec(),
+        }
+    }
+
+    /// The configured overrides, along with the ones given on the command line, which take precedence.
+    pub fn overrides_with(&self, flags: &[(String, Override)]) -> BTreeMap<String, Override> {
+        let mut overrides = self.overrides.clone();
+        overrides.extend(flags.iter().cloned());
+        overrides
+    }
+
+    /// Credentials for the registry at `url`. Registries without credentials of their own use the ones in the
+    /// environment, see [`Credentials::from_env`].
+    pub fn credentials(&self, url: &str) -> Credentials {
+        self.credentials
+            .get(&credentials::normalize(url))
+            .cloned()
+            .unwrap_or_else(|| Credentials::from_env(self.token.clone()))
+    }
+}
+
+impl From<ConfigFile> for Config {
+    fn from(config: ConfigFile) -> Self {
+        let token_env = config.token_env.as_deref().unwrap_or("ORCA_TOKEN");
+
+        Config {
+            cache_directory: config
+                .cache_directory
+                .unwrap_or_else(|| PathBuf::from(".orca/cache")),
+            registry: config.registry,
+            mirrors: config.mirrors.unwrap_or_default(),
+            routes: config.routes.unwrap_or_default(),
+            jobs: config.jobs,
+            token: std::env::var(token_env).ok(),
+            output: config.output.unwrap_or_default(),
+            verbosity: config.verbosity.unwrap_or_default(),
+            hooks: config.hooks.unwrap_or_default(),
+            signing_key: config.signing_key,
+            trusted_keys: config.trusted_keys.unwrap_or_default(),
+            require_signatures: config.require_signatures.unwrap_or(false),
+            remote_cache: config.remote_cache,
+            retries: config.retries.unwrap_or(registry::DEFAULT_RETRIES),
+            credentials: config.credentials.unwrap_or_default(),
+            overrides: config.overrides.unwrap_or_default(),
+            workspace: config.workspace,
+            sandbox: config.sandbox.unwrap_or_default(),
+            executor: config.executor.unwrap_or_default(),
+            prefix: config.prefix,
+            gc: config.gc.unwrap_or_default(),
+            environment: Environment {
+                env: config.env.unwrap_or_default().into_iter().collect(),
+                remove: config.env_remove.unwrap_or_default(),
+                clear: config.env_clear.unwrap_or(false),
+            },
+        }
+    }
+}
+
+/// Location of the user's config file, if it exists.
+fn user_config() -> Option<PathBuf> {
+    Some(user_config_directory()?.join("config.toml")).filter(|path| path.is_file())
+}
+
+/// Directory holding the user's config, such as `~/.config/orca`, whether or not it exists.
+pub fn user_config_directory() -> Option<PathBuf> {
+    let directory = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(directory.join("orca"))
+}
+
+/// Location of the closest `orca.toml`, searching from the current directory upwards.
+pub fn project_config() -> Option<PathBuf> {
+    let current = std::env::current_dir().ok()?;
+
+    current
+        .ancestors()
+        .map(|directory| directory.join(PROJECT_CONFIG))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{Config, ConfigFile};
+
+    #[test]
+    fn test_layering() {
+        let user: ConfigFile = toml::from_str("registry = \"https://user\"\njobs = 2").unwrap();
+        let project: ConfigFile = toml::from_str("registry = \"https://project\"").unwrap();
+        let flags = ConfigFile {
+            cache_directory: Some(PathBuf::from("/tmp/cache")),
+            ..ConfigFile::default()
+        };
+
+        let config = Config::from(
+            ConfigFile::default()
+                .layer(user)
+                .layer(project)
+                .layer(flags),
+        );
+
+        assert_eq!(config.registry.as_deref(), Some("https://project"));
+        assert_eq!(config.jobs, Some(2));
+        assert_eq!(config.cache_directory, PathBuf::from("/tmp/cache"));
+    }
+}
@@ -0,0 +1,317 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use clap::Clap;
+use rustls::internal::pemfile;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::config;
+
+/// Name of the file `orca login` stores credentials in, next to the user config.
+pub const CREDENTIALS: &str = "credentials.toml";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    IO(#[from] io::Error),
+    #[error("failed to read credentials from {0}: {1}")]
+    Parse(PathBuf, #[source] toml::de::Error),
+    #[error("failed to store credentials: {0}")]
+    Serialize(#[from] toml::ser::Error),
+    #[error("invalid client certificate {0}: {1}")]
+    Certificate(PathBuf, String),
+    #[error("a client certificate needs both a certificate and a key")]
+    IncompleteCertificate,
+    #[error("no user config directory, set $HOME or $XDG_CONFIG_HOME")]
+    NoConfigDirectory,
+}
+
+/// How to authenticate with a single registry, configured in the `[credentials."<url>"]` sections of the config
+/// or stored using `orca login`. Secrets can be read from environment variables instead of being written down.
+///
+/// A token is sent as a bearer token, and takes precedence over a username and password, which are sent using
+/// basic auth. A client certificate can be used along with either of them.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Credentials {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Name of the environment variable holding the token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_env: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Name of the environment variable holding the password.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password_env: Option<String>,
+    /// PEM file holding the client certificate, along with any intermediate certificates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<PathBuf>,
+    /// PEM file holding the private key of the client certificate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<PathBuf>,
+}
+
+impl Credentials {
+    /// Credentials for registries which aren't configured explicitly, read from `ORCA_TOKEN`, or the
+    /// configured `token_env`, along with `ORCA_USERNAME` and `ORCA_PASSWORD`.
+    pub fn from_env(token: Option<String>) -> Self {
+        Credentials {
+            token,
+            username: std::env::var("ORCA_USERNAME").ok(),
+            password: std::env::var("ORCA_PASSWORD").ok(),
+            ..Credentials::default()
+        }
+    }
+
+    /// Value of the `Authorization` header to send along with requests, if any.
+    pub fn authorization(&self) -> Option<String> {
+        let token = self.token.clone().or_else(|| env(&self.token_env));
+        if let Some(token) = token {
+            return Some(format!("Bearer {}", token));
+        }
+
+        let username = self.username.as_ref()?;
+        let password = self
+            .password
+            .clone()
+            .or_else(|| env(&self.password_env))
+            .unwrap_or_default();
+
+        Some(format!(
+            "Basic {}",
+            base64::encode(format!("{}:{}", username, password))
+        ))
+    }
+
+    /// TLS configuration presenting the client certificate, if one is configured.
+    pub fn tls_config(&self) -> Result<Option<Arc<rustls::ClientConfig>>, Error> {
+        let (certificate, key) = match (&self.certificate, &self.key) {
+            (Some(certificate), Some(key)) => (certificate, key),
+            (None, None) => return Ok(None),
+            _ => return Err(Error::IncompleteCertificate),
+        };
+
+        let invalid =
+            |path: &Path, reason: &str| Error::Certificate(path.to_path_buf(), reason.to_string());
+
+        let chain = pemfile::certs(&mut BufReader::new(File::open(certificate)?))
+            .map_err(|_| invalid(certificate, "not a PEM file"))?;
+        if chain.is_empty() {
+            return Err(invalid(certificate, "no certificates found"));
+        }
+
+        let mut keys = pemfile::pkcs8_private_keys(&mut BufReader::new(File::open(key)?))
+            .map_err(|_| invalid(key, "not a PEM file"))?;
+        if keys.is_empty() {
+            keys = pemfile::rsa_private_keys(&mut BufReader::new(File::open(key)?))
+                .map_err(|_| invalid(key, "not a PEM file"))?;
+        }
+        let key_der = keys
+            .into_iter()
+            .next()
+            .ok_or_else(|| invalid(key, "no private key found"))?;
+
+        let mut config = rustls::ClientConfig::new();
+        config
+            .root_store
+            .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+        config
+            .set_single_client_cert(chain, key_der)
+            .map_err(|error| invalid(certificate, &error.to_string()))?;
+
+        Ok(Some(Arc::new(config)))
+    }
+
+    /// Paths within a config file are relative to the file itself.
+    pub(crate) fn relative_to(mut self, directory: &Path) -> Self {
+        self.certificate = self.certificate.map(|path| directory.join(path));
+        self.key = self.key.map(|path| directory.join(path));
+        self
+    }
+}
+
+fn env(variable: &Option<String>) -> Option<String> {
+    std::env::var(variable.as_ref()?).ok()
+}
+
+/// Url of a registry, the way credentials are looked up by it.
+pub fn normalize(url: &str) -> String {
+    url.trim_end_matches('/').to_string()
+}
+
+/// Contents of the credentials file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct CredentialsFile {
+    registries: BTreeMap<String, Credentials>,
+}
+
+/// Location of the credentials file, whether or not it exists.
+fn credentials_path() -> Option<PathBuf> {
+    Some(config::user_config_directory()?.join(CREDENTIALS))
+}
+
+/// Credentials stored using `orca login`, by registry url.
+pub fn load_stored() -> Result<BTreeMap<String, Credentials>, Error> {
+    let path = match credentials_path() {
+        Some(path) if path.is_file() => path,
+        _ => return Ok(BTreeMap::new()),
+    };
+
+    let contents = std::fs::read_to_string(&path)?;
+    let file: CredentialsFile =
+        toml::from_str(&contents).map_err(|error| Error::Parse(path.clone(), error))?;
+
+    Ok(file.registries)
+}
+
+/// Stores credentials for the registry at `url`, replacing any stored before. The credentials file is only
+/// readable by the current user.
+fn store(url: &str, credentials: Credentials) -> Result<PathBuf, Error> {
+    let path = credentials_path().ok_or(Error::NoConfigDirectory)?;
+
+    let mut file = CredentialsFile {
+        registries: load_stored()?,
+    };
+    file.registries.insert(normalize(url), credentials);
+    let contents = toml::to_string(&file)?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // The permissions only apply to newly created files, so never write into a leftover one.
+    let staging = path.with_extension("toml.tmp");
+    let _ = std::fs::remove_file(&staging);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options.open(&staging)?.write_all(contents.as_bytes())?;
+    std::fs::rename(&staging, &path)?;
+
+    Ok(path)
+}
+
+#[derive(Clap)]
+pub struct LoginCmd {
+    #[clap(about = "Url of the registry to log in to.")]
+    pub registry: String,
+    #[clap(
+        long,
+        about = "Log in using basic auth with this username, and prompt for a password instead of a token."
+    )]
+    pub username: Option<String>,
+    #[clap(
+        long,
+        parse(from_os_str),
+        about = "PEM file holding a client certificate to present to the registry."
+    )]
+    pub certificate: Option<PathBuf>,
+    #[clap(
+        long,
+        parse(from_os_str),
+        about = "PEM file holding the private key of the client certificate."
+    )]
+    pub key: Option<PathBuf>,
+    #[clap(
+        long,
+        about = "Only store the client certificate, without prompting for a token or password."
+    )]
+    pub certificate_only: bool,
+}
+
+impl LoginCmd {
+    pub(crate) fn execute(&self) -> Result<(), Error> {
+        let current = std::env::current_dir()?;
+        let mut credentials = Credentials {
+            username: self.username.clone(),
+            certificate: self.certificate.as_ref().map(|path| current.join(path)),
+            key: self.key.as_ref().map(|path| current.join(path)),
+            ..Credentials::default()
+        };
+
+        // Fail before prompting for anything, if the certificate won't work anyway.
+        credentials.tls_config()?;
+
+        if !self.certificate_only {
+            let secret = match &self.username {
+                Some(username) => prompt(&format!("Password for {}: ", username))?,
+                None => prompt("Token: ")?,
+            };
+
+            if self.username.is_some() {
+                credentials.password = Some(secret);
+            } else {
+                credentials.token = Some(secret);
+            }
+        }
+
+        let path = store(&self.registry, credentials)?;
+        println!(
+            "stored credentials for {} in {}",
+            normalize(&self.registry),
+            path.display()
+        );
+        Ok(())
+    }
+}
+
+/// Reads a secret from the terminal without echoing it, or a single line from stdin if it isn't a terminal.
+fn prompt(message: &str) -> Result<String, Error> {
+    let secret = match rpassword::read_password_from_tty(Some(message)) {
+        Ok(secret) => secret,
+        Err(_) => {
+            let mut line = String::new();
+            io::stdin().read_line(&mut line)?;
+            line.trim_end_matches(&['\r', '\n'][..]).to_string()
+        }
+    };
+
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Credentials;
+
+    #[test]
+    fn test_authorization() {
+        let basic = Credentials {
+            username: Some("alice".to_string()),
+            password: Some("secret".to_string()),
+            ..Credentials::default()
+        };
+        assert_eq!(
+            basic.authorization().as_deref(),
+            Some("Basic YWxpY2U6c2VjcmV0")
+        );
+
+        let token = Credentials {
+            token: Some("abc".to_string()),
+            ..basic
+        };
+        assert_eq!(token.authorization().as_deref(), Some("Bearer abc"));
+
+        assert!(Credentials::default().authorization().is_none());
+        assert!(Credentials {
+            key: Some("client.key".into()),
+            ..Credentials::default()
+        }
+        .tls_config()
+        .is_err());
+    }
+}
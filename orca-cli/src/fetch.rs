@@ -0,0 +1,98 @@
+use std::path::Path;
+
+use clap::Clap;
+use dependency_graph::DependencyGraph;
+
+use crate::{
+    build::{
+        self,
+        overrides::{self, Override},
+        sources, spec, Resolver,
+    },
+    cache::Cache,
+    config::Config,
+    lockfile::{Lockfile, LOCKFILE},
+    output::Reporter,
+    registry::Registries,
+    target,
+};
+
+#[derive(Clap)]
+pub struct FetchCmd {
+    #[clap(
+        long,
+        about = "Path(s) to one or more BuildSpec files, defaults to the workspace members."
+    )]
+    pub spec: Vec<String>,
+    #[clap(
+        long,
+        about = "Fail unless dependencies resolve to exactly the packages in orca.lock."
+    )]
+    pub locked: bool,
+    #[clap(
+        long,
+        about = "Target to fetch dependencies for, such as linux-x86_64. Defaults to the host."
+    )]
+    pub target: Option<String>,
+    #[clap(
+        long = "override",
+        parse(try_from_str = overrides::parse_override),
+        about = "Resolve a dependency to a pinned version or a local spec instead, as NAME=VERSION or NAME=PATH."
+    )]
+    pub overrides: Vec<(String, Override)>,
+    #[clap(
+        long,
+        short,
+        about = "Only fetch the dependencies of the named package(s) and their dependencies, instead of every spec."
+    )]
+    pub package: Vec<String>,
+    #[clap(
+        long,
+        about = "Let pre-releases such as 1.0.0-rc.1 satisfy the requirements of every dependency."
+    )]
+    pub include_prereleases: bool,
+}
+
+impl FetchCmd {
+    /// Resolves every external dependency of the given specs, downloading them into the cache
+    /// so they can later be built offline. Nothing is built.
+    pub(crate) fn execute(&self, config: &Config) -> Result<(), build::Error> {
+        let target = self.target.clone().unwrap_or_else(target::host);
+        let mut specs = spec::load_all(&config.specs(&self.spec), None)?;
+        sources::resolve(&mut specs, Path::new(sources::CHECKOUTS), &target)?;
+        overrides::apply(&mut specs, &config.overrides_with(&self.overrides))?;
+        let mut specs = spec::select_packages(specs, &self.package)?;
+        spec::select_all(&mut specs, &target)?;
+        if self.include_prereleases {
+            spec::include_prereleases(&mut specs);
+        }
+
+        let cache = Cache::configured(config)?.with_target(&target);
+        let registries = Registries::configured(config)?.with_target(&target);
+        let reporter = Reporter::new(config.output, config.verbosity);
+
+        let mut resolver = Resolver::new(&cache, &registries);
+        if self.locked {
+            resolver = resolver.locked(Lockfile::load(LOCKFILE)?);
+        }
+
+        let graph = DependencyGraph::from(&specs[..]);
+        let dependencies: Vec<_> = graph.unresolved_dependencies().collect();
+        reporter.fetching(dependencies.len());
+        let resolved = dependencies.into_iter().try_for_each(|dependency| {
+            reporter.resolving(&dependency.to_string());
+            let package = resolver.resolve(dependency)?;
+            reporter.resolved(&dependency.to_string(), &package);
+            Ok::<_, build::Error>(())
+        });
+        reporter.done();
+        resolved?;
+
+        let lockfile = resolver.finish()?;
+        if !self.locked {
+            lockfile.save(LOCKFILE)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use std::{path::Path, str::FromStr};
+
+use clap::Clap;
+use dependency_graph::{DependencyGraph, Visitor};
+
+use crate::{
+    build::{
+        self, sources,
+        spec::{self, BuildSpec, Dependency},
+    },
+    config::Config,
+    target,
+};
+
+/// Ways of rendering the dependency graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Tree,
+    Dot,
+    Mermaid,
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "tree" => Ok(GraphFormat::Tree),
+            "dot" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            other => Err(format!("unknown graph format: {}", other)),
+        }
+    }
+}
+
+#[derive(Clap)]
+pub struct GraphCmd {
+    #[clap(
+        long,
+        about = "Path(s) to one or more BuildSpec files, defaults to the workspace members."
+    )]
+    pub spec: Vec<String>,
+    #[clap(long, default_value = "tree", possible_values = &["tree", "dot", "mermaid"], about = "How to render the graph.")]
+    pub format: GraphFormat,
+    #[clap(
+        long,
+        about = "Target to show the graph for, such as linux-x86_64. Defaults to the host."
+    )]
+    pub target: Option<String>,
+    #[clap(
+        long,
+        short,
+        about = "Only show the named package(s) and their dependencies, instead of every spec."
+    )]
+    pub package: Vec<String>,
+}
+
+impl GraphCmd {
+    pub(crate) fn execute(&self, config: &Config) -> Result<(), build::Error> {
+        let target = self.target.clone().unwrap_or_else(target::host);
+        let mut specs = spec::load_all(&config.specs(&self.spec), None)?;
+        sources::resolve(&mut specs, Path::new(sources::CHECKOUTS), &target)?;
+        let mut specs = spec::select_packages(specs, &self.package)?;
+        spec::select_all(&mut specs, &target)?;
+
+        let graph = DependencyGraph::from(&specs[..]);
+
+        match self.format {
+            GraphFormat::Tree => {
+                let mut tree = Tree::default();
+                graph.walk(&mut tree);
+                print!("{}", tree.output);
+            }
+            GraphFormat::Dot => print!("{}", graph.to_dot()),
+            GraphFormat::Mermaid => print!("{}", graph.to_mermaid()),
+        }
+
+        Ok(())
+    }
+}
+
+/// Renders the graph like `cargo tree`, with each dependency indented below its dependent.
+#[derive(Default)]
+struct Tree {
+    output: String,
+}
+
+impl<'a> Visitor<'a, BuildSpec> for Tree {
+    fn on_node(&mut self, node: &'a BuildSpec, depth: usize) {
+        self.line(depth, &node.to_string());
+    }
+
+    fn on_unresolved(&mut self, dependency: &'a Dependency, depth: usize) {
+        self.line(depth, &format!("{} (unresolved)", dependency));
+    }
+}
+
+impl Tree {
+    fn line(&mut self, depth: usize, label: &str) {
+        if depth > 0 {
+            self.output.push_str(&"    ".repeat(depth - 1));
+            self.output.push_str("└── ");
+        }
+        self.output.push_str(label);
+        self.output.push('\n');
+    }
+}
@@ -1,6 +1,6 @@
 use std::convert::TryFrom;
 
-use semver::Version;
+use semver::{Prerelease, Version, VersionReq};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -9,11 +9,20 @@ pub enum ParsingError {
     InvalidVersion(#[from] semver::Error),
     #[error("zero-length string is not a valid identifier")]
     ZeroLength,
+    #[error("package name {0:?} contains an empty segment")]
+    EmptySegment(String),
+    #[error("segment {segment:?} of package name {name:?} must start with a letter")]
+    InvalidStart { name: String, segment: String },
+    #[error("package name {name:?} contains invalid character {character:?}")]
+    InvalidCharacter { name: String, character: char },
 }
 
 pub type NameIdentifier = String;
 pub type VersionIdentifier = Version;
 
+/// Separates the segments of a namespaced package name such as `myorg/networking/libfoo`.
+pub const SEPARATOR: char = '/';
+
 #[derive(Debug)]
 pub enum Identifier {
     Name(NameIdentifier),
@@ -33,3 +42,110 @@ impl TryFrom<String> for Identifier {
         })
     }
 }
+
+/// Splits a package name such as `myorg/networking/libfoo` into its segments, validating each of them.
+///
+/// Segments must start with an ASCII letter, and may otherwise only contain ASCII letters, digits, `-`, `_` and `.`.
+/// Since versions always start with a digit, this keeps names and versions apart wherever they share a path,
+/// such as in the cache or in registry URLs.
+pub fn parse_name(name: &str) -> Result<Vec<NameIdentifier>, ParsingError> {
+    if name.is_empty() {
+        return Err(ParsingError::ZeroLength);
+    }
+
+    name.split(SEPARATOR)
+        .map(|segment| {
+            let first_character = segment
+                .chars()
+                .next()
+                .ok_or_else(|| ParsingError::EmptySegment(name.to_string()))?;
+
+            if !first_character.is_ascii_alphabetic() {
+                return Err(ParsingError::InvalidStart {
+                    name: name.to_string(),
+                    segment: segment.to_string(),
+                });
+            }
+
+            if let Some(character) = segment
+                .chars()
+                .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+            {
+                return Err(ParsingError::InvalidCharacter {
+                    name: name.to_string(),
+                    character,
+                });
+            }
+
+            Ok(segment.to_string())
+        })
+        .collect()
+}
+
+/// True if `version` satisfies `requirement`. Pre-releases such as `1.0.0-rc.1` are only matched by requirements
+/// naming a pre-release of the same version themselves, such as `^1.0.0-rc`, unless `prereleases` are included,
+/// in which case a pre-release satisfies every requirement the release it precedes does. Build metadata is never
+/// taken into account.
+pub fn satisfies(version: &Version, requirement: &VersionReq, prereleases: bool) -> bool {
+    if requirement.matches(version) {
+        return true;
+    }
+
+    prereleases
+        && !version.pre.is_empty()
+        && requirement.matches(&Version {
+            pre: Prerelease::EMPTY,
+            ..version.clone()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::{Version, VersionReq};
+
+    use super::{parse_name, satisfies, ParsingError};
+
+    #[test]
+    fn test_parse_name() {
+        assert_eq!(parse_name("libfoo").unwrap(), vec!["libfoo"]);
+        assert_eq!(
+            parse_name("myorg/networking/lib-foo_2.x").unwrap(),
+            vec!["myorg", "networking", "lib-foo_2.x"]
+        );
+
+        assert!(matches!(parse_name(""), Err(ParsingError::ZeroLength)));
+        assert!(matches!(
+            parse_name("myorg//libfoo"),
+            Err(ParsingError::EmptySegment(_))
+        ));
+        assert!(matches!(
+            parse_name("myorg/"),
+            Err(ParsingError::EmptySegment(_))
+        ));
+        assert!(matches!(
+            parse_name("myorg/1.0.0"),
+            Err(ParsingError::InvalidStart { .. })
+        ));
+        assert!(matches!(
+            parse_name("my org/libfoo"),
+            Err(ParsingError::InvalidCharacter { character: ' ', .. })
+        ));
+    }
+
+    #[test]
+    fn test_satisfies() {
+        let satisfies = |version: &str, requirement: &str, prereleases: bool| {
+            satisfies(
+                &Version::parse(version).unwrap(),
+                &VersionReq::parse(requirement).unwrap(),
+                prereleases,
+            )
+        };
+
+        assert!(!satisfies("1.0.0-rc.1", "^1", false));
+        assert!(satisfies("1.0.0-rc.1", "^1", true));
+        assert!(satisfies("1.0.0-rc.2", "^1.0.0-rc.1", false));
+        assert!(!satisfies("2.0.0-rc.1", ">=1, <2", true));
+        assert!(satisfies("1.2.0+build.5", "=1.2.0", false));
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::BTreeMap;
+
+use clap::Clap;
+use semver::{Version, VersionReq};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    cache::{self, cmd::parse_package, Cache, Package},
+    config::Config,
+    output::OutputFormat,
+    registry::{self, Registries, RemoteVersion},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("cache error: {0}")]
+    Cache(#[from] cache::Error),
+    #[error("registry error: {0}")]
+    Registry(#[from] registry::Error),
+    #[error("{0} is neither cached nor published to any registry")]
+    NotFound(String),
+}
+
+#[derive(Clap)]
+pub struct InfoCmd {
+    #[clap(
+        about = "Package to inspect, as name@version, or just name to inspect its highest version."
+    )]
+    pub package: String,
+}
+
+/// Everything known about a package, locally and remotely.
+#[derive(Debug, Serialize)]
+struct Info {
+    name: String,
+    local: Vec<Available>,
+    remote: Vec<Available>,
+    /// Details of the inspected version, once for every target it's available for.
+    details: Vec<Details>,
+}
+
+/// A version of the package which is available somewhere.
+#[derive(Debug, Serialize)]
+struct Available {
+    version: Version,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
+    /// Url of the registry the version is published to, if it isn't cached.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registry: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    yanked: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Details {
+    version: Version,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
+    /// Url of the registry the details are from, if the version isn't cached.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registry: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    dependencies: BTreeMap<String, VersionReq>,
+    artifacts: Vec<ArtifactInfo>,
+    /// Cached packages which depend on this version.
+    required_by: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ArtifactInfo {
+    name: String,
+    /// Size in bytes, which is only known for cached artifacts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    digest: Option<String>,
+}
+
+impl InfoCmd {
+    /// Looks the package up in the cache and the registries. The inspected version is the one asked for, or
+    /// the highest cached version, or the highest published version if none are cached.
+    pub(crate) fn execute(&self, config: &Config) -> Result<(), Error> {
+        let (name, version) = parse_package(&self.package)?;
+
+        let cache = Cache::configured(config)?;
+        let registries = Registries::configured(config)?;
+
+        let local = cache.list_versions(name)?;
+        let remote = registries.versions(name)?;
+
+        let version = version
+            .or_else(|| local.iter().map(|package| &package.version).max().cloned())
+            .or_else(|| {
+                remote
+                    .iter()
+                    .map(|(_, remote)| &remote.version)
+                    .max()
+                    .cloned()
+            })
+            .ok_or_else(|| Error::NotFound(self.package.clone()))?;
+
+        let cached = cache.list()?;
+        let mut details: Vec<_> = local
+            .iter()
+            .filter(|package| package.version == version)
+            .map(|package| local_details(package, &cached))
+            .collect();
+
+        // Only versions which aren't cached are described by the registries.
+        if details.is_empty() {
+            details = remote
+                .iter()
+                .filter(|(_, remote)| remote.version == version)
+                .map(|(registry, remote)| remote_details(name, registry.url(), remote, &cached))
+                .collect();
+        }
+
+        if details.is_empty() {
+            return Err(Error::NotFound(format!("{}@{}", name, version)));
+        }
+
+        let info = Info {
+            name: name.to_string(),
+            local: local
+                .iter()
+                .map(|package| Available {
+                    version: package.version.clone(),
+                    target: package.target.clone(),
+                    registry: None,
+                    yanked: package.yanked,
+                })
+                .collect(),
+            remote: remote
+                .iter()
+                .map(|(registry, remote)| Available {
+                    version: remote.version.clone(),
+                    target: remote.target.clone(),
+                    registry: Some(registry.url().to_string()),
+                    yanked: remote.yanked,
+                })
+                .collect(),
+            details,
+        };
+
+        match config.output {
+            OutputFormat::Text => print(&info),
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&info).map_err(cache::Error::from)?
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+fn local_details(package: &Package, cached: &[Package]) -> Details {
+    Details {
+        version: package.version.clone(),
+        target: package.target.clone(),
+        registry: None,
+        description: package.description.clone(),
+        dependencies: package.dependencies.clone(),
+        artifacts: package
+            .artifacts
+            .iter()
+            .map(|artifact| ArtifactInfo {
+                name: artifact.name.clone(),
+                size: std::fs::metadata(&artifact.path)
+                    .ok()
+                    .map(|metadata| metadata.len()),
+                digest: artifact.digest.clone(),
+            })
+            .collect(),
+        required_by: required_by(&package.name.join("/"), &package.version, cached),
+    }
+}
+
+fn remote_details(
+    name: &str,
+    registry: &str,
+    remote: &RemoteVersion,
+    cached: &[Package],
+) -> Details {
+    Details {
+        version: remote.version.clone(),
+        target: remote.target.clone(),
+        registry: Some(registry.to_string()),
+        description: remote.description.clone(),
+        dependencies: remote.dependencies.clone(),
+        artifacts: remote
+            .artifacts
+            .iter()
+            .map(|artifact| ArtifactInfo {
+                name: artifact.name.clone(),
+                size: None,
+                digest: Some(artifact.digest.clone()),
+            })
+            .collect(),
+        required_by: required_by(name, &remote.version, cached),
+    }
+}
+
+/// The cached packages with a dependency which `version` of the package `name` satisfies.
+fn required_by(name: &str, version: &Version, cached: &[Package]) -> Vec<String> {
+    cached
+        .iter()
+        .filter(|package| {
+            package
+                .dependencies
+                .get(name)
+                .map_or(false, |requirement| requirement.matches(version))
+        })
+        .map(|package| package.to_string())
+        .collect()
+}
+
+fn print(info: &Info) {
+    println!("{}", info.name);
+
+    let list = |available: &[Available]| {
+        let versions: Vec<_> = available
+            .iter()
+            .map(|available| {
+                let mut version = available.version.to_string();
+                if let Some(target) = &available.target {
+                    version = format!("{} ({})", version, target);
+                }
+                if available.yanked {
+                    version = format!("{} (yanked)", version);
+                }
+                version
+            })
+            .collect();
+
+        if versions.is_empty() {
+            "none".to_string()
+        } else {
+            versions.join(", ")
+        }
+    };
+    println!("cached versions: {}", list(&info.local));
+    println!("published versions: {}", list(&info.remote));
+
+    for details in &info.details {
+        println!();
+        print!("{}@{}", info.name, details.version);
+        if let Some(target) = &details.target {
+            print!(" ({})", target);
+        }
+        match &details.registry {
+            Some(registry) => println!(", published to {}", registry),
+            None => println!(", cached"),
+        }
+
+        if let Some(description) = &details.description {
+            println!("  {}", description);
+        }
+
+        println!("  dependencies:");
+        for (name, requirement) in &details.dependencies {
+            println!("    {} {}", name, requirement);
+        }
+
+        println!("  artifacts:");
+        for artifact in &details.artifacts {
+            let size = artifact
+                .size
+                .map_or_else(String::new, |size| format!(" ({} bytes)", size));
+            match &artifact.digest {
+                Some(digest) => println!("    {}{} sha256:{}", artifact.name, size, digest),
+                None => println!("    {}{}", artifact.name, size),
+            }
+        }
+
+        println!("  required by:");
+        for package in &details.required_by {
+            println!("    {}", package);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use semver::{Version, VersionReq};
+
+    use super::required_by;
+    use crate::cache::Package;
+
+    #[test]
+    fn test_required_by() {
+        let package = |name: &str, dependencies: &[(&str, &str)]| Package {
+            name: vec![name.to_string()],
+            version: Version::parse("1.0.0").unwrap(),
+            target: None,
+            signature: None,
+            description: None,
+            dependencies: dependencies
+                .iter()
+                .map(|(name, requirement)| {
+                    (name.to_string(), VersionReq::parse(requirement).unwrap())
+                })
+                .collect::<BTreeMap<_, _>>(),
+            yanked: false,
+            artifacts: vec![],
+        };
+
+        let cached = vec![
+            package("app", &[("libfoo", "^1.2")]),
+            package("tool", &[("libfoo", "^2")]),
+            package("libfoo", &[]),
+        ];
+
+        assert_eq!(
+            required_by("libfoo", &Version::parse("1.4.0").unwrap(), &cached),
+            vec!["app@1.0.0"]
+        );
+        assert!(required_by("libfoo", &Version::parse("1.0.0").unwrap(), &cached).is_empty());
+    }
+}
@@ -0,0 +1,384 @@
+use std::{
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use clap::Clap;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    build::{self, spec::Dependency, Resolver},
+    cache::{self, Cache, Package},
+    config::Config,
+    identifier,
+    registry::Registries,
+    target,
+};
+
+/// Prefix packages are installed into, unless configured otherwise.
+pub const DEFAULT_PREFIX: &str = "/usr/local";
+
+/// Directory within the prefix holding the manifest of every installed package.
+const MANIFESTS: &str = "share/orca/installed";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    IO(#[from] io::Error),
+    #[error("cache error: {0}")]
+    Cache(#[from] cache::Error),
+    #[error("{0}")]
+    Build(#[from] build::Error),
+    #[error("invalid version requirement {0}: {1}")]
+    Requirement(String, #[source] semver::Error),
+    #[error("invalid manifest {0}: {1}")]
+    Manifest(PathBuf, #[source] serde_json::Error),
+    #[error("{0} already exists and isn't part of {1}, use --force to overwrite it")]
+    Conflict(PathBuf, String),
+    #[error("{0} is not installed in {1}")]
+    NotInstalled(String, PathBuf),
+    #[error("refusing to install artifact {0}, which isn't a path within the prefix")]
+    InvalidArtifact(String),
+}
+
+/// Record of an installed package, listing every file it installed relative to the prefix.
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    name: String,
+    version: Version,
+    #[serde(default)]
+    target: Option<String>,
+    files: Vec<PathBuf>,
+}
+
+#[derive(Clap)]
+pub struct InstallCmd {
+    #[clap(
+        about = "Package to install, as name, name@version or name@requirement such as libfoo@^1.2."
+    )]
+    pub package: String,
+    #[clap(
+        long,
+        parse(from_os_str),
+        about = "Directory to install into, defaults to the configured prefix or /usr/local."
+    )]
+    pub prefix: Option<PathBuf>,
+    #[clap(
+        long,
+        about = "Link the artifacts into the prefix instead of copying them. The links break if the package is removed from the cache."
+    )]
+    pub symlink: bool,
+    #[clap(
+        long,
+        about = "Overwrite files in the prefix which belong to other packages, or to none."
+    )]
+    pub force: bool,
+    #[clap(
+        long,
+        about = "Target to install the package for, such as linux-x86_64. Defaults to the host."
+    )]
+    pub target: Option<String>,
+    #[clap(
+        long,
+        about = "Let pre-releases such as 1.0.0-rc.1 satisfy the requirement, as if they were the release they precede."
+    )]
+    pub include_prereleases: bool,
+}
+
+#[derive(Clap)]
+pub struct UninstallCmd {
+    #[clap(about = "Name of the package to uninstall.")]
+    pub name: String,
+    #[clap(
+        long,
+        parse(from_os_str),
+        about = "Directory the package was installed into, defaults to the configured prefix or /usr/local."
+    )]
+    pub prefix: Option<PathBuf>,
+}
+
+impl InstallCmd {
+    /// Resolves the package against the cache and registries, and installs its artifacts into the prefix.
+    /// Any other installed version of the package is uninstalled first.
+    pub(crate) fn execute(&self, config: &Config) -> Result<(), Error> {
+        let target = self.target.clone().unwrap_or_else(target::host);
+        let mut dependency = parse_requirement(&self.package)?;
+        dependency.prereleases = self.include_prereleases;
+        identifier::parse_name(&dependency.name).map_err(cache::Error::from)?;
+
+        let cache = Cache::configured(config)?.with_target(&target);
+        let registries = Registries::configured(config)
+            .map_err(build::Error::from)?
+            .with_target(&target);
+        let package = Resolver::new(&cache, &registries).resolve(&dependency)?;
+
+        let prefix = prefix(&self.prefix, config);
+        let installed = install(&package, &prefix, self.symlink, self.force)?;
+        println!(
+            "installed {} into {} ({} files)",
+            package,
+            prefix.display(),
+            installed
+        );
+
+        Ok(())
+    }
+}
+
+impl UninstallCmd {
+    pub(crate) fn execute(&self, config: &Config) -> Result<(), Error> {
+        let prefix = prefix(&self.prefix, config);
+        let manifest = uninstall(&self.name, &prefix)?;
+        println!(
+            "uninstalled {}@{} from {}",
+            manifest.name,
+            manifest.version,
+            prefix.display()
+        );
+
+        Ok(())
+    }
+}
+
+fn prefix(flag: &Option<PathBuf>, config: &Config) -> PathBuf {
+    flag.clone()
+        .or_else(|| config.prefix.clone())
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_PREFIX))
+}
+
+/// Parses `name`, `name@version` or `name@requirement`. A bare version means exactly that version, rather
+/// than anything compatible with it.
+fn parse_requirement(package: &str) -> Result<Dependency, Error> {
+    let (name, version) = match package.split_once('@') {
+        Some((name, requirement)) => {
+            let requirement = match Version::parse(requirement) {
+                Ok(version) => VersionReq::parse(&format!("={}", version)),
+                Err(_) => VersionReq::parse(requirement),
+            }
+            .map_err(|error| Error::Requirement(requirement.to_string(), error))?;
+
+            (name, requirement)
+        }
+        None => (package, VersionReq::STAR),
+    };
+
+    Ok(Dependency {
+        name: name.to_string(),
+        version,
+        prereleases: false,
+        git: None,
+        rev: None,
+        tag: None,
+        branch: None,
+        url: None,
+        sha256: None,
+        path: None,
+    })
+}
+
+/// Copies or links every artifact of `package` into `prefix`, at the path the artifact is named after, and
+/// records them in the package's manifest. Returns the number of files installed.
+fn install(package: &Package, prefix: &Path, symlink: bool, force: bool) -> Result<usize, Error> {
+    let name = package.name.join("/");
+    let previous = read_manifest(&name, prefix)?;
+
+    let mut files = Vec::new();
+    for artifact in &package.artifacts {
+        let relative = PathBuf::from(&artifact.name);
+        // Whatever ends up in the cache, nothing gets written outside the prefix, not even with --force.
+        let within_prefix = relative.components().next().is_some()
+            && relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+        if !within_prefix {
+            return Err(Error::InvalidArtifact(artifact.name.clone()));
+        }
+
+        let destination = prefix.join(&relative);
+        let owned = previous
+            .as_ref()
+            .map_or(false, |manifest| manifest.files.contains(&relative));
+
+        if destination.symlink_metadata().is_ok() && !owned && !force {
+            return Err(Error::Conflict(destination, name));
+        }
+
+        files.push(relative);
+    }
+
+    if previous.is_some() {
+        uninstall(&name, prefix)?;
+    }
+
+    for (artifact, relative) in package.artifacts.iter().zip(&files) {
+        let destination = prefix.join(relative);
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if destination.symlink_metadata().is_ok() {
+            std::fs::remove_file(&destination)?;
+        }
+
+        if symlink {
+            link(&artifact.path, &destination)?;
+        } else {
+            std::fs::copy(&artifact.path, &destination)?;
+        }
+    }
+
+    let manifest = Manifest {
+        name: name.clone(),
+        version: package.version.clone(),
+        target: package.target.clone(),
+        files,
+    };
+    let path = manifest_path(&name, prefix);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let contents = serde_json::to_string_pretty(&manifest).map_err(cache::Error::from)?;
+    std::fs::write(&path, contents)?;
+
+    Ok(manifest.files.len())
+}
+
+/// Removes every file the package `name` installed into `prefix`, along with any directories left empty.
+fn uninstall(name: &str, prefix: &Path) -> Result<Manifest, Error> {
+    let manifest = read_manifest(name, prefix)?
+        .ok_or_else(|| Error::NotInstalled(name.to_string(), prefix.to_path_buf()))?;
+
+    for relative in &manifest.files {
+        let path = prefix.join(relative);
+        match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            // Files which were already removed by hand are no reason to leave the rest behind.
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error.into()),
+        }
+
+        remove_empty_parents(&path, prefix);
+    }
+
+    let path = manifest_path(name, prefix);
+    std::fs::remove_file(&path)?;
+    remove_empty_parents(&path, prefix);
+
+    Ok(manifest)
+}
+
+fn read_manifest(name: &str, prefix: &Path) -> Result<Option<Manifest>, Error> {
+    let path = manifest_path(name, prefix);
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let contents = std::fs::read(&path)?;
+    let manifest =
+        serde_json::from_slice(&contents).map_err(|error| Error::Manifest(path, error))?;
+    Ok(Some(manifest))
+}
+
+/// Manifests are named after the package, so `org/libfoo` is recorded in `org/libfoo.json`.
+fn manifest_path(name: &str, prefix: &Path) -> PathBuf {
+    prefix.join(MANIFESTS).join(format!("{}.json", name))
+}
+
+/// Removes the parents of `path` for as long as they're empty, stopping at `prefix`.
+fn remove_empty_parents(path: &Path, prefix: &Path) {
+    for parent in path.ancestors().skip(1) {
+        // Removing a directory which isn't empty fails, which is exactly when to stop.
+        if parent == prefix || !parent.starts_with(prefix) || std::fs::remove_dir(parent).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(unix)]
+fn link(original: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+#[cfg(windows)]
+fn link(original: &Path, link: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(original, link)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use semver::Version;
+
+    use super::{install, parse_requirement, uninstall};
+    use crate::cache::{Artifact, Cache, Package};
+
+    #[test]
+    fn test_install_and_uninstall() {
+        let root = std::env::temp_dir().join(format!("orca-install-test-{}", std::process::id()));
+        let build = root.join("build");
+        let prefix = root.join("prefix");
+        std::fs::create_dir_all(build.join("lib")).unwrap();
+        std::fs::create_dir_all(prefix.join("lib")).unwrap();
+        std::fs::write(build.join("lib/libfoo.so"), b"foo").unwrap();
+        std::fs::write(prefix.join("lib/other.so"), b"other").unwrap();
+
+        let cache = Cache::new(root.join("cache")).unwrap();
+        let package = cache
+            .put(&Package {
+                name: vec!["org".to_string(), "libfoo".to_string()],
+                version: Version::parse("1.0.0").unwrap(),
+                target: None,
+                signature: None,
+                description: None,
+                dependencies: BTreeMap::new(),
+                yanked: false,
+                artifacts: vec![Artifact::new(build.join("lib"))],
+            })
+            .unwrap();
+
+        assert_eq!(install(&package, &prefix, false, false).unwrap(), 1);
+        assert_eq!(std::fs::read(prefix.join("lib/libfoo.so")).unwrap(), b"foo");
+
+        // Reinstalling replaces the package's own files.
+        assert_eq!(install(&package, &prefix, true, false).unwrap(), 1);
+
+        uninstall("org/libfoo", &prefix).unwrap();
+        assert!(!prefix.join("lib/libfoo.so").exists());
+        assert!(prefix.join("lib/other.so").exists());
+        assert!(!prefix.join("share").exists());
+        assert!(uninstall("org/libfoo", &prefix).is_err());
+
+        std::fs::write(prefix.join("lib/libfoo.so"), b"unowned").unwrap();
+        assert!(install(&package, &prefix, false, false).is_err());
+        assert!(install(&package, &prefix, false, true).is_ok());
+
+        for name in &["../escaped.so", "/tmp/escaped.so", "lib/../../escaped.so"] {
+            let mut escaping = package.clone();
+            escaping.artifacts[0].name = name.to_string();
+            assert!(install(&escaping, &prefix, false, true).is_err());
+        }
+        assert!(!root.join("escaped.so").exists());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_parse_requirement() {
+        let exact = parse_requirement("libfoo@1.2.0").unwrap();
+        assert_eq!(exact.name, "libfoo");
+        assert!(!exact.version.matches(&Version::parse("1.2.1").unwrap()));
+
+        let compatible = parse_requirement("org/libfoo@^1.2").unwrap();
+        assert!(compatible
+            .version
+            .matches(&Version::parse("1.9.0").unwrap()));
+
+        assert!(parse_requirement("libfoo")
+            .unwrap()
+            .version
+            .matches(&Version::parse("0.1.0").unwrap()));
+        assert!(parse_requirement("libfoo@nope").is_err());
+    }
+}
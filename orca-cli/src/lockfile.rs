@@ -0,0 +1,142 @@
+use std::{collections::BTreeMap, path::Path};
+
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::cache::Package;
+
+/// Default location of the lockfile, relative to the current directory.
+pub const LOCKFILE: &str = "orca.lock";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to access lockfile: {0}")]
+    IO(#[from] std::io::Error),
+    #[error("failed to parse lockfile: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("failed to write lockfile: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+/// The exact package chosen for a single dependency.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    /// The requirement as declared by the dependent, such as `^1.2`.
+    pub requirement: VersionReq,
+    pub version: Version,
+    /// The target the package was built for, if it is target specific.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Digests of the package's artifacts, by artifact name.
+    #[serde(default)]
+    pub artifacts: BTreeMap<String, String>,
+}
+
+impl LockedPackage {
+    pub fn new(requirement: &VersionReq, package: &Package) -> Self {
+        LockedPackage {
+            name: package.name.join("/"),
+            requirement: requirement.clone(),
+            version: package.version.clone(),
+            target: package.target.clone(),
+            artifacts: package
+                .artifacts
+                .iter()
+                .filter_map(|artifact| Some((artifact.name.clone(), artifact.digest.clone()?)))
+                .collect(),
+        }
+    }
+
+    /// True if this entry locks the given dependency.
+    pub fn locks(&self, name: &str, requirement: &VersionReq) -> bool {
+        self.name == name && &self.requirement == requirement
+    }
+}
+
+/// Contents of an `orca.lock` file, recording the packages chosen for every external dependency of a build,
+/// so the exact same packages can be used again later on, see `orca build --locked`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedPackage>,
+}
+
+impl Lockfile {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let contents = format!(
+            "# This file is generated by orca, and should not be edited by hand.\n{}",
+            toml::to_string(self)?
+        );
+
+        Ok(std::fs::write(path, contents)?)
+    }
+
+    /// Finds the entry locking the given dependency.
+    pub fn get(&self, name: &str, requirement: &VersionReq) -> Option<&LockedPackage> {
+        self.packages
+            .iter()
+            .find(|locked| locked.locks(name, requirement))
+    }
+
+    /// Name and version of every locked package.
+    pub fn pinned(&self) -> impl Iterator<Item = (String, Version)> + '_ {
+        self.packages
+            .iter()
+            .map(|locked| (locked.name.clone(), locked.version.clone()))
+    }
+
+    /// Records the package chosen for a dependency, replacing any entry already locking it.
+    /// Entries are kept sorted, so the same resolution always produces the same lockfile.
+    pub fn insert(&mut self, locked: LockedPackage) {
+        self.packages
+            .retain(|existing| !existing.locks(&locked.name, &locked.requirement));
+        self.packages.push(locked);
+        self.packages.sort_by(|a, b| {
+            (&a.name, a.requirement.to_string(), &a.version).cmp(&(
+                &b.name,
+                b.requirement.to_string(),
+                &b.version,
+            ))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::{Version, VersionReq};
+
+    use super::{LockedPackage, Lockfile};
+
+    #[test]
+    fn test_lockfile_roundtrip() {
+        let mut lockfile = Lockfile::default();
+        for (name, requirement, version) in
+            &[("zlib", "^1", "1.2.11"), ("openssl", ">=1.1", "1.1.1")]
+        {
+            lockfile.insert(LockedPackage {
+                name: name.to_string(),
+                requirement: VersionReq::parse(requirement).unwrap(),
+                version: Version::parse(version).unwrap(),
+                target: None,
+                artifacts: Default::default(),
+            });
+        }
+
+        assert_eq!(lockfile.packages[0].name, "openssl");
+        assert!(lockfile
+            .get("zlib", &VersionReq::parse("^1").unwrap())
+            .is_some());
+        assert!(lockfile
+            .get("zlib", &VersionReq::parse("^2").unwrap())
+            .is_none());
+
+        let serialized = toml::to_string(&lockfile).unwrap();
+        assert_eq!(toml::from_str::<Lockfile>(&serialized).unwrap(), lockfile);
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::Clap;
+use semver::Version;
+use thiserror::Error;
+
+use crate::{
+    cache::{self, cmd::parse_package},
+    identifier,
+};
+
+/// Directory holding the output of every build, as `<name>/<version>/<timestamp>.log`.
+pub const LOGS: &str = ".orca/logs";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    IO(#[from] io::Error),
+    #[error("cache error: {0}")]
+    Cache(#[from] cache::Error),
+    #[error("no build logs for {0}")]
+    NotFound(String),
+}
+
+/// The log of a single build, which every step and hook of the build writes its output to. Clones write to the
+/// same file, so the output of a step can be captured from both stdout and stderr at once.
+#[derive(Clone)]
+pub struct BuildLog {
+    path: PathBuf,
+    file: Arc<Mutex<File>>,
+}
+
+impl BuildLog {
+    /// Creates a new log for `version` of the package `name` in `directory`, named after the current time in
+    /// milliseconds since the epoch, so the latest build's log is the one with the highest number.
+    pub fn create(directory: &Path, name: &str, version: &Version) -> io::Result<Self> {
+        let directory = package_directory(directory, name).join(version.to_string());
+        std::fs::create_dir_all(&directory)?;
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis());
+        let path = directory.join(format!("{}.log", timestamp));
+
+        Ok(BuildLog {
+            file: Arc::new(Mutex::new(File::create(&path)?)),
+            path,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends a line to the log. Failing to write the log isn't worth failing the build over, so errors are
+    /// ignored, just like failing to print the output would be.
+    pub fn write(&self, line: &str) {
+        let mut file = self.file.lock().expect("build log poisoned");
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+/// Directory holding the logs of every version of the package `name`, nested by the segments of its name.
+fn package_directory(directory: &Path, name: &str) -> PathBuf {
+    name.split(identifier::SEPARATOR)
+        .fold(directory.to_path_buf(), |path, segment| path.join(segment))
+}
+
+/// The most recent log in `directory` of the package `name`, limited to `version` if given.
+fn latest(directory: &Path, name: &str, version: Option<&Version>) -> io::Result<Option<PathBuf>> {
+    let directory = package_directory(directory, name);
+    if !directory.is_dir() {
+        return Ok(None);
+    }
+
+    let mut versions = Vec::new();
+    match version {
+        Some(version) => versions.push(directory.join(version.to_string())),
+        None => {
+            for entry in std::fs::read_dir(&directory)? {
+                let path = entry?.path();
+                let is_version = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .map_or(false, |name| Version::parse(name).is_ok());
+                if is_version {
+                    versions.push(path);
+                }
+            }
+        }
+    }
+
+    let mut latest: Option<(u128, PathBuf)> = None;
+    for version in versions.into_iter().filter(|path| path.is_dir()) {
+        for entry in std::fs::read_dir(&version)? {
+            let path = entry?.path();
+            let timestamp = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".log"))
+                .and_then(|timestamp| timestamp.parse::<u128>().ok());
+
+            if let Some(timestamp) = timestamp {
+                if latest
+                    .as_ref()
+                    .map_or(true, |(newest, _)| timestamp > *newest)
+                {
+                    latest = Some((timestamp, path));
+                }
+            }
+        }
+    }
+
+    Ok(latest.map(|(_, path)| path))
+}
+
+#[derive(Clap)]
+pub struct LogsCmd {
+    #[clap(
+        about = "Package to show the latest build log of, as name, or name@version for the latest build of that version."
+    )]
+    pub package: String,
+    #[clap(
+        long,
+        about = "Only print the path of the log, instead of its contents."
+    )]
+    pub path: bool,
+}
+
+impl LogsCmd {
+    pub(crate) fn execute(&self) -> Result<(), Error> {
+        let (name, version) = parse_package(&self.package)?;
+        let path = latest(Path::new(LOGS), name, version.as_ref())?
+            .ok_or_else(|| Error::NotFound(self.package.clone()))?;
+
+        if self.path {
+            println!("{}", path.display());
+        } else {
+            io::copy(&mut File::open(&path)?, &mut io::stdout().lock())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+
+    use super::{latest, BuildLog};
+
+    #[test]
+    fn test_latest() {
+        let root = std::env::temp_dir().join(format!("orca-logs-test-{}", std::process::id()));
+        let version = |version: &str| Version::parse(version).unwrap();
+
+        assert!(latest(&root, "org/foo", None).unwrap().is_none());
+
+        let old = BuildLog::create(&root, "org/foo", &version("1.0.0")).unwrap();
+        old.write("building 1.0.0");
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let new = BuildLog::create(&root, "org/foo", &version("0.9.0")).unwrap();
+        new.write("building 0.9.0");
+
+        assert!(old.path().starts_with(root.join("org/foo/1.0.0")));
+        assert_eq!(
+            latest(&root, "org/foo", None).unwrap().as_deref(),
+            Some(new.path())
+        );
+        assert_eq!(
+            latest(&root, "org/foo", Some(&version("1.0.0")))
+                .unwrap()
+                .as_deref(),
+            Some(old.path())
+        );
+        assert_eq!(
+            std::fs::read_to_string(old.path()).unwrap(),
+            "building 1.0.0\n"
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
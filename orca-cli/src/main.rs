@@ -1,30 +1,199 @@
+use std::path::PathBuf;
+
 use clap::{AppSettings, Clap};
+use log::error;
 
 mod build;
 mod cache;
+mod config;
+mod credentials;
+mod fetch;
+mod graph;
 mod identifier;
+mod info;
+mod install;
+mod lockfile;
+mod logs;
+mod output;
+mod publish;
+mod registry;
+mod release;
+mod scaffold;
+mod search;
+mod serve;
+mod signing;
+mod target;
+mod verify;
+mod yank;
 
 use build::BuildCmd;
+use cache::CacheCmd;
+use config::{Config, ConfigFile};
+use credentials::LoginCmd;
+use fetch::FetchCmd;
+use graph::GraphCmd;
+use info::InfoCmd;
+use install::{InstallCmd, UninstallCmd};
+use logs::LogsCmd;
+use output::{OutputFormat, Verbosity};
+use publish::PublishCmd;
+use release::VersionCmd;
+use scaffold::{InitCmd, NewCmd};
+use search::SearchCmd;
+use serve::ServeCmd;
+use verify::VerifyCmd;
+use yank::YankCmd;
 
 #[derive(Clap)]
 #[clap(version = "0.0.1", author = "Mathias Pius <contact@pius.io>")]
 #[clap(setting = AppSettings::ColoredHelp)]
 struct Opts {
-    #[clap(long, short, default_value = ".orca/cache")]
-    cache_directory: String,
+    #[clap(
+        long,
+        short,
+        parse(from_os_str),
+        about = "Directory of the package cache, defaults to .orca/cache."
+    )]
+    cache_directory: Option<PathBuf>,
+    #[clap(
+        long,
+        about = "Url of the registry to download missing dependencies from."
+    )]
+    registry: Option<String>,
+    #[clap(long, possible_values = &["text", "json"], about = "Print results as text or json.")]
+    output: Option<OutputFormat>,
+    #[clap(long, about = "Refuse packages which aren't signed by a trusted key.")]
+    require_signatures: bool,
+    #[clap(
+        long,
+        short,
+        global = true,
+        conflicts_with = "verbose",
+        about = "Only print errors, without progress bars."
+    )]
+    quiet: bool,
+    #[clap(
+        long,
+        short,
+        global = true,
+        parse(from_occurrences),
+        about = "Print everything which happens line by line, instead of progress bars. Pass twice for debugging information."
+    )]
+    verbose: u64,
     #[clap(subcommand)]
     subcmd: SubCommand,
 }
 
 #[derive(Clap)]
 enum SubCommand {
+    #[clap(about = "Build packages from their BuildSpecs.")]
     Build(BuildCmd),
+    #[clap(about = "Inspect and maintain the local package cache.")]
+    Cache(CacheCmd),
+    #[clap(about = "Download the dependencies of a set of BuildSpecs, without building anything.")]
+    Fetch(FetchCmd),
+    #[clap(about = "Print the dependency graph of a set of BuildSpecs.")]
+    Graph(GraphCmd),
+    #[clap(about = "Show the versions, dependencies, artifacts and dependents of a package.")]
+    Info(InfoCmd),
+    #[clap(about = "Create a skeleton BuildSpec in the current directory.")]
+    Init(InitCmd),
+    #[clap(about = "Install a package's artifacts into a prefix such as /usr/local.")]
+    Install(InstallCmd),
+    #[clap(about = "Store credentials for a registry in the user config.")]
+    Login(LoginCmd),
+    #[clap(about = "Print the output of the latest build of a package.")]
+    Logs(LogsCmd),
+    #[clap(about = "Create a new directory containing a skeleton BuildSpec.")]
+    New(NewCmd),
+    #[clap(about = "Upload a cached package to the registry.")]
+    Publish(PublishCmd),
+    #[clap(about = "Search the registries for packages by name.")]
+    Search(SearchCmd),
+    #[clap(about = "Serve the package cache over http, as a registry for other machines.")]
+    Serve(ServeCmd),
+    #[clap(about = "Remove the files of an installed package from its prefix.")]
+    Uninstall(UninstallCmd),
+    #[clap(about = "Check the integrity of the cached packages.")]
+    Verify(VerifyCmd),
+    #[clap(about = "Show or bump the version of a spec in the workspace.")]
+    Version(VersionCmd),
+    #[clap(
+        about = "Mark a version of a package as yanked, so it's only used by builds whose lockfile pins it."
+    )]
+    Yank(YankCmd),
 }
 
 fn main() {
     let opts = Opts::parse();
 
+    if let Err(error) = run(&opts) {
+        report(error.as_ref());
+        std::process::exit(1);
+    }
+}
+
+fn run(opts: &Opts) -> Result<(), Box<dyn std::error::Error>> {
+    let verbosity = Verbosity::from_flags(opts.quiet, opts.verbose);
+    let config = Config::load(ConfigFile {
+        cache_directory: opts.cache_directory.clone(),
+        registry: opts.registry.clone(),
+        output: opts.output,
+        verbosity,
+        require_signatures: Some(true).filter(|_| opts.require_signatures),
+        ..ConfigFile::default()
+    });
+
+    // The configured verbosity decides what to log, so logging can't start until the config has been loaded.
+    init_logger(
+        config
+            .as_ref()
+            .map_or(verbosity.unwrap_or_default(), |config| config.verbosity),
+    );
+    let config = config?;
+
     match &opts.subcmd {
-        SubCommand::Build(build) => build.execute(&opts),
+        SubCommand::Build(build) => build.execute(&config)?,
+        SubCommand::Cache(cache) => cache.execute(&config)?,
+        SubCommand::Fetch(fetch) => fetch.execute(&config)?,
+        SubCommand::Graph(graph) => graph.execute(&config)?,
+        SubCommand::Info(info) => info.execute(&config)?,
+        SubCommand::Init(init) => init.execute()?,
+        SubCommand::Install(install) => install.execute(&config)?,
+        SubCommand::Login(login) => login.execute()?,
+        SubCommand::Logs(logs) => logs.execute()?,
+        SubCommand::New(new) => new.execute()?,
+        SubCommand::Publish(publish) => publish.execute(&config)?,
+        SubCommand::Search(search) => search.execute(&config)?,
+        SubCommand::Serve(serve) => serve.execute(&config)?,
+        SubCommand::Uninstall(uninstall) => uninstall.execute(&config)?,
+        SubCommand::Verify(verify) => verify.execute(&config)?,
+        SubCommand::Version(version) => version.execute(&config)?,
+        SubCommand::Yank(yank) => yank.execute(&config)?,
+    }
+
+    Ok(())
+}
+
+/// Logs at the level `verbosity` asks for, unless `RUST_LOG` is set.
+fn init_logger(verbosity: Verbosity) {
+    let mut builder = pretty_env_logger::formatted_builder();
+    builder.filter_level(verbosity.log_level());
+
+    if let Ok(filters) = std::env::var("RUST_LOG") {
+        builder.parse_filters(&filters);
+    }
+
+    builder.init();
+}
+
+/// Logs an error along with the chain of errors which caused it.
+fn report(error: &dyn std::error::Error) {
+    error!("{}", error);
+
+    let mut source = error.source();
+    while let Some(cause) = source {
+        error!("  caused by: {}", cause);
+        source = cause.source();
     }
 }
@@ -0,0 +1,398 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use log::{error, info, LevelFilter};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::Package;
+
+/// How commands report their results.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Human-readable text.
+    #[default]
+    Text,
+    /// Machine-readable json. Commands producing a stream of events print one json object per line.
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            other => Err(format!("unknown output format: {}", other)),
+        }
+    }
+}
+
+/// How much commands print while they run, see `--quiet` and `--verbose`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    /// Only errors.
+    Quiet,
+    /// Progress bars when running in a terminal, and a line for every package built. The output of build steps
+    /// is only written to the build logs, see `orca logs`.
+    #[default]
+    Normal,
+    /// Everything which happens, line by line, including the output of build steps, without progress bars.
+    Verbose,
+    /// Everything, including debugging information.
+    Debug,
+}
+
+impl Verbosity {
+    /// Verbosity according to the `--quiet` and `--verbose` flags, where `verbose` is the number of times
+    /// `--verbose` was given.
+    pub fn from_flags(quiet: bool, verbose: u64) -> Option<Self> {
+        match (quiet, verbose) {
+            (true, _) => Some(Verbosity::Quiet),
+            (false, 0) => None,
+            (false, 1) => Some(Verbosity::Verbose),
+            (false, _) => Some(Verbosity::Debug),
+        }
+    }
+
+    /// Which log messages to show, unless `RUST_LOG` says otherwise.
+    pub fn log_level(self) -> LevelFilter {
+        match self {
+            Verbosity::Quiet => LevelFilter::Error,
+            Verbosity::Normal => LevelFilter::Warn,
+            Verbosity::Verbose => LevelFilter::Info,
+            Verbosity::Debug => LevelFilter::Debug,
+        }
+    }
+}
+
+/// Events emitted while building, printed as json lines when using `--output json`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    Resolved {
+        dependency: String,
+        package: String,
+    },
+    Started {
+        package: &'a str,
+    },
+    /// The package was built before, and nothing it's built from has changed since.
+    Unchanged {
+        package: &'a str,
+    },
+    Finished {
+        package: &'a str,
+        artifacts: Vec<&'a str>,
+        /// Duration of the build in seconds.
+        duration: f64,
+    },
+    Failed {
+        package: &'a str,
+        error: String,
+        duration: f64,
+    },
+    /// The package wasn't built, since one of its dependencies failed, see `--keep-going`.
+    Skipped {
+        package: &'a str,
+    },
+    /// Everything the build worked through once it's done, see `--keep-going`.
+    Summary {
+        built: &'a [String],
+        failed: &'a [String],
+        skipped: &'a [String],
+    },
+}
+
+/// Reports build progress in the configured [`OutputFormat`] and [`Verbosity`]. Text output to a terminal shows
+/// progress bars for every package being built or resolved, along with the overall progress, unless the verbosity
+/// asks for either less or more than that.
+#[derive(Clone)]
+pub struct Reporter {
+    format: OutputFormat,
+    verbosity: Verbosity,
+    progress: Option<Arc<Progress>>,
+}
+
+impl Reporter {
+    pub fn new(format: OutputFormat, verbosity: Verbosity) -> Self {
+        let terminal = atty::is(atty::Stream::Stdout) && atty::is(atty::Stream::Stderr);
+        let progress = format == OutputFormat::Text && verbosity == Verbosity::Normal && terminal;
+
+        Reporter {
+            format,
+            verbosity,
+            progress: Some(Arc::new(Progress::new())).filter(|_| progress),
+        }
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// Announces how many packages there are to build, each of which is reused or built. Resolving dependencies
+    /// shows a spinner, but doesn't count towards the overall progress.
+    pub fn planned(&self, steps: usize) {
+        if let Some(progress) = &self.progress {
+            progress.overall.set_length(steps as u64);
+        }
+    }
+
+    /// Announces how many dependencies there are to resolve, when resolving them is all there is to do.
+    pub fn fetching(&self, dependencies: usize) {
+        if let Some(progress) = &self.progress {
+            progress.overall.set_length(dependencies as u64);
+            progress.resolutions.store(true, Ordering::SeqCst);
+        }
+    }
+
+    pub fn resolving(&self, dependency: &str) {
+        if let Some(progress) = &self.progress {
+            progress.start(dependency, format!("resolving {}", dependency));
+        }
+    }
+
+    pub fn resolved(&self, dependency: &str, package: &Package) {
+        if let Some(progress) = &self.progress {
+            progress.resolved(dependency);
+        }
+
+        match self.format {
+            OutputFormat::Text => info!("resolved {} to {}", dependency, package),
+            OutputFormat::Json => self.emit(&Event::Resolved {
+                dependency: dependency.to_string(),
+                package: package.to_string(),
+            }),
+        }
+    }
+
+    pub fn unchanged(&self, package: &str) {
+        if let Some(progress) = &self.progress {
+            progress.finish(package);
+        }
+
+        match self.format {
+            OutputFormat::Text => info!("{} is up to date", package),
+            OutputFormat::Json => self.emit(&Event::Unchanged { package }),
+        }
+    }
+
+    pub fn started(&self, package: &str) {
+        if let Some(progress) = &self.progress {
+            progress.start(package, format!("building {}", package));
+        }
+
+        match self.format {
+            OutputFormat::Text => info!("building {}", package),
+            OutputFormat::Json => self.emit(&Event::Started { package }),
+        }
+    }
+
+    pub fn finished(&self, package: &str, built: &Package, duration: Duration) {
+        if let Some(progress) = &self.progress {
+            progress.finish(package);
+        }
+
+        match self.format {
+            OutputFormat::Text if self.verbosity > Verbosity::Quiet => self.print(&format!(
+                "built {} in {:.1}s",
+                package,
+                duration.as_secs_f64()
+            )),
+            OutputFormat::Text => {}
+            OutputFormat::Json => self.emit(&Event::Finished {
+                package,
+                artifacts: built
+                    .artifacts
+                    .iter()
+                    .map(|artifact| artifact.name.as_str())
+                    .collect(),
+                duration: duration.as_secs_f64(),
+            }),
+        }
+    }
+
+    pub fn failed(&self, package: &str, failure: &dyn std::error::Error, duration: Duration) {
+        if let Some(progress) = &self.progress {
+            progress.finish(package);
+        }
+
+        match self.format {
+            OutputFormat::Text => error!("failed to build {}: {}", package, failure),
+            OutputFormat::Json => self.emit(&Event::Failed {
+                package,
+                error: failure.to_string(),
+                duration: duration.as_secs_f64(),
+            }),
+        }
+    }
+
+    pub fn skipped(&self, package: &str) {
+        match self.format {
+            OutputFormat::Text if self.verbosity > Verbosity::Quiet => self.print(&format!(
+                "skipped {}, since one of its dependencies failed",
+                package
+            )),
+            OutputFormat::Text => {}
+            OutputFormat::Json => self.emit(&Event::Skipped { package }),
+        }
+    }
+
+    /// Lists the packages which were built, failed and were skipped, once a build which keeps going is done.
+    pub fn summary(&self, built: &[String], failed: &[String], skipped: &[String]) {
+        match self.format {
+            OutputFormat::Text if self.verbosity > Verbosity::Quiet => {
+                self.print(&format!(
+                    "built {} packages, {} failed, {} skipped",
+                    built.len(),
+                    failed.len(),
+                    skipped.len()
+                ));
+                for (label, packages) in &[("failed", failed), ("skipped", skipped)] {
+                    if !packages.is_empty() {
+                        self.print(&format!("  {}: {}", label, packages.join(", ")));
+                    }
+                }
+            }
+            OutputFormat::Text => {}
+            OutputFormat::Json => self.emit(&Event::Summary {
+                built,
+                failed,
+                skipped,
+            }),
+        }
+    }
+
+    /// Forwards a line of output from one of the steps building `package`, which is only printed when running
+    /// verbosely, since the output of parallel builds is interleaved. Output on stdout is moved to stderr when
+    /// printing json, so stdout only holds events.
+    pub fn output(&self, package: &str, line: &str, stderr: bool) {
+        if self.verbosity < Verbosity::Verbose {
+            return;
+        }
+
+        let line = format!("[{}] {}", package, line);
+        if stderr || self.format == OutputFormat::Json {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    /// Clears the progress bars, once everything is done or has failed.
+    pub fn done(&self) {
+        if let Some(progress) = &self.progress {
+            progress.clear();
+        }
+    }
+
+    fn print(&self, line: &str) {
+        match &self.progress {
+            Some(progress) => progress.println(line),
+            None => println!("{}", line),
+        }
+    }
+
+    fn emit(&self, event: &Event) {
+        match serde_json::to_string(event) {
+            Ok(line) => println!("{}", line),
+            Err(failure) => error!("failed to serialize event: {}", failure),
+        }
+    }
+}
+
+/// Progress bars for a build, with a spinner for each package being worked on above a bar for the whole build.
+struct Progress {
+    bars: MultiProgress,
+    overall: ProgressBar,
+    /// Spinners of the packages being worked on, by package.
+    packages: Mutex<HashMap<String, ProgressBar>>,
+    /// Whether resolved dependencies count towards the overall progress, which they only do when fetching.
+    resolutions: AtomicBool,
+}
+
+impl Progress {
+    fn new() -> Self {
+        let bars = MultiProgress::new();
+        let overall = bars.add(ProgressBar::new(0));
+        overall.set_style(
+            ProgressStyle::with_template("[{elapsed_precise}] [{bar:40}] {pos}/{len}")
+                .expect("progress template is valid")
+                .progress_chars("=> "),
+        );
+        overall.enable_steady_tick(Duration::from_millis(100));
+
+        Progress {
+            bars,
+            overall,
+            packages: Mutex::new(HashMap::new()),
+            resolutions: AtomicBool::new(false),
+        }
+    }
+
+    fn start(&self, package: &str, message: String) {
+        let spinner = self
+            .bars
+            .insert_before(&self.overall, ProgressBar::new_spinner());
+        spinner.set_style(
+            ProgressStyle::with_template("{spinner} {msg} ({elapsed})")
+                .expect("progress template is valid"),
+        );
+        spinner.set_message(message);
+        spinner.enable_steady_tick(Duration::from_millis(100));
+
+        self.packages
+            .lock()
+            .expect("progress poisoned")
+            .insert(package.to_string(), spinner);
+    }
+
+    /// Removes the spinner of `package`, without advancing the overall progress.
+    fn stop(&self, package: &str) {
+        if let Some(spinner) = self
+            .packages
+            .lock()
+            .expect("progress poisoned")
+            .remove(package)
+        {
+            spinner.finish_and_clear();
+            self.bars.remove(&spinner);
+        }
+    }
+
+    fn finish(&self, package: &str) {
+        self.stop(package);
+        self.overall.inc(1);
+    }
+
+    fn resolved(&self, dependency: &str) {
+        if self.resolutions.load(Ordering::SeqCst) {
+            self.finish(dependency);
+        } else {
+            self.stop(dependency);
+        }
+    }
+
+    fn println(&self, line: &str) {
+        // Printing only fails if the terminal went away, in which case there's nobody to print for anyway.
+        let _ = self.bars.println(line);
+    }
+
+    fn clear(&self) {
+        for (_, spinner) in self.packages.lock().expect("progress poisoned").drain() {
+            spinner.finish_and_clear();
+        }
+
+        self.overall.finish_and_clear();
+    }
+}
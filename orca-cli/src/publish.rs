@@ -0,0 +1,153 @@
+use std::path::PathBuf;
+
+use clap::Clap;
+use thiserror::Error;
+
+use crate::{
+    build::{
+        self, spec,
+        template::{self, Variables},
+    },
+    cache::{self, cmd::parse_package, Cache, Package},
+    config::Config,
+    output::Reporter,
+    registry::{self, Registries, Registry},
+    target,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("cache error: {0}")]
+    Cache(#[from] cache::Error),
+    #[error("{0}")]
+    Spec(#[from] spec::Error),
+    #[error("build failed: {0}")]
+    Build(#[from] build::Error),
+    #[error("{0}")]
+    Template(#[from] template::Error),
+    #[error("registry error: {0}")]
+    Registry(#[from] registry::Error),
+    #[error("no registry configured, use --registry or set it in orca.toml")]
+    NoRegistry,
+    #[error("package {0} is not in the cache")]
+    NotCached(String),
+    #[error("io error: {0}")]
+    IO(#[from] std::io::Error),
+}
+
+#[derive(Clap)]
+pub struct PublishCmd {
+    #[clap(
+        about = "Package to publish, as name@version, or just name to publish its highest cached version."
+    )]
+    pub package: String,
+    #[clap(long, about = "BuildSpec file(s) to build before publishing.")]
+    pub spec: Vec<String>,
+    #[clap(
+        long,
+        parse(from_os_str),
+        default_value = ".orca/build",
+        about = "Directory in which packages are built."
+    )]
+    pub build_directory: PathBuf,
+    #[clap(
+        long,
+        about = "Token used to authenticate with the registry, instead of the configured credentials."
+    )]
+    pub token: Option<String>,
+    #[clap(
+        long,
+        about = "Print what would be uploaded, without uploading anything."
+    )]
+    pub dry_run: bool,
+    #[clap(
+        long = "define",
+        short = 'D',
+        parse(try_from_str = template::parse_define),
+        about = "Define a variable for use as ${KEY} in the spec files, as KEY=VALUE."
+    )]
+    pub defines: Vec<(String, String)>,
+    #[clap(
+        long,
+        about = "Target to build and publish for, such as linux-x86_64. Defaults to the host."
+    )]
+    pub target: Option<String>,
+}
+
+impl PublishCmd {
+    pub(crate) fn execute(&self, config: &Config) -> Result<(), Error> {
+        let url = config.registry.as_deref().ok_or(Error::NoRegistry)?;
+        let mut credentials = config.credentials(url);
+        if let Some(token) = &self.token {
+            credentials.token = Some(token.clone());
+        }
+
+        let target = self.target.clone().unwrap_or_else(target::host);
+        let registry = Registry::new(url)
+            .with_credentials(&credentials)?
+            .with_target(&target)
+            .with_retries(config.retries);
+        let cache = Cache::configured(config)?.with_target(&target);
+
+        if !self.spec.is_empty() {
+            let mut specs = spec::load_all(&self.spec, None)?;
+            spec::select_all(&mut specs, &target)?;
+
+            let variables = Variables::new(self.defines.clone());
+            for spec in &mut specs {
+                variables.apply(spec)?;
+            }
+
+            let registries = Registries::configured(config)?.with_target(&target);
+            let mut resolver = build::Resolver::new(&cache, &registries);
+            let options = build::BuildOptions {
+                build_directory: self.build_directory.clone(),
+                jobs: config.jobs.unwrap_or_else(build::default_jobs),
+                reporter: Reporter::new(config.output, config.verbosity),
+                hooks: config.hooks.clone(),
+                sandbox: config.sandbox,
+                executor: config.executor,
+                environment: config.environment.clone(),
+                keep_going: false,
+            };
+            build::build_all(&specs, &mut resolver, &options)?;
+        }
+
+        let package = self.cached_package(&cache)?;
+
+        if self.dry_run {
+            println!("would publish {} to {}", package, registry.url());
+            for artifact in &package.artifacts {
+                println!(
+                    "  {} ({} bytes)",
+                    artifact.name,
+                    std::fs::metadata(&artifact.path)?.len()
+                );
+            }
+            return Ok(());
+        }
+
+        registry.publish(&package)?;
+        println!("published {} to {}", package, registry.url());
+        Ok(())
+    }
+
+    fn cached_package(&self, cache: &Cache) -> Result<Package, Error> {
+        let (name, version) = parse_package(&self.package)?;
+
+        let package = match version {
+            Some(version) => cache.get(name, &version)?,
+            None => match cache
+                .list_versions(name)?
+                .into_iter()
+                .rev()
+                .find(|package| cache.supports(package))
+            {
+                Some(latest) => cache.get(name, &latest.version)?,
+                None => None,
+            },
+        };
+
+        package.ok_or_else(|| Error::NotCached(self.package.clone()))
+    }
+}
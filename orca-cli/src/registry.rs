@@ -0,0 +1,743 @@
+use std::{
+    collections::BTreeMap,
+    fs::OpenOptions,
+    io,
+    path::{Component, Path},
+    thread,
+    time::Duration,
+};
+
+use log::{info, warn};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    cache::{self, Artifact, Cache, Package},
+    config::Config,
+    credentials::{self, Credentials},
+    identifier,
+    signing::Signature,
+    target,
+};
+
+/// Number of times a failed download is retried, unless configured otherwise.
+pub const DEFAULT_RETRIES: u32 = 3;
+
+/// How long to wait before the first retry of a download. Each retry after it waits twice as long, up to [`MAX_BACKOFF`].
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    IO(#[from] io::Error),
+    #[error("cache error: {0}")]
+    Cache(#[from] cache::Error),
+    #[error("registry request failed: {0}")]
+    Http(#[from] Box<ureq::Error>),
+    #[error("downloaded artifact {0} does not match its expected digest")]
+    DigestMismatch(String),
+    #[error("artifact {0} is not stored in the cache, and can't be published")]
+    Unpublishable(String),
+    #[error("{0}")]
+    Name(#[from] identifier::ParsingError),
+    #[error("{0}")]
+    Credentials(#[from] credentials::Error),
+    #[error("invalid route pattern: {0}")]
+    Pattern(#[from] glob::PatternError),
+    #[error("registry {0} does not support searching")]
+    SearchUnsupported(String),
+    #[error("invalid artifact name {0}, artifacts must be named by a relative path")]
+    InvalidArtifact(String),
+}
+
+impl Error {
+    /// True if trying again might succeed, such as when the connection dropped or the server is overloaded.
+    fn is_transient(&self) -> bool {
+        match self {
+            Error::IO(_) | Error::DigestMismatch(_) => true,
+            Error::Http(error) => match error.as_ref() {
+                ureq::Error::Status(status, _) => {
+                    *status >= 500 || *status == 408 || *status == 429
+                }
+                ureq::Error::Transport(_) => true,
+            },
+            _ => false,
+        }
+    }
+}
+
+/// A single artifact of a published package.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteArtifact {
+    /// Path of the artifact relative to the package.
+    pub name: String,
+    /// Hex-encoded SHA-256 digest of the artifact's contents.
+    pub digest: String,
+}
+
+/// Whether `name` can name an artifact, which it can if it's a relative path made up of plain segments only,
+/// such as `lib/libfoo.so`, so it can't point outside the directory the artifact is stored in.
+pub(crate) fn is_artifact_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .split('/')
+            .all(|segment| !matches!(segment, "" | "." | ".."))
+        && Path::new(name)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// A single published version of a package, as listed by the registry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteVersion {
+    pub version: Version,
+    /// The target this version was built for, or `None` if it isn't target specific.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<Signature>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Version requirements on other packages, by package name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, VersionReq>,
+    /// Yanked versions are still listed, but are only downloaded when pinned by a lockfile.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub yanked: bool,
+    pub artifacts: Vec<RemoteArtifact>,
+}
+
+/// A package matching a search, as listed by the registry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub name: String,
+    /// Every published version of the package, lowest first.
+    pub versions: Vec<Version>,
+    /// Description of the highest version which has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Client for a remote package registry, which exposes the following endpoints:
+///
+/// * `GET /api/v1/packages/<name>` lists the published versions of a package as json, see [`RemoteVersion`].
+/// * `GET /api/v1/packages/<name>/<version>/artifacts/<artifact>` downloads a single artifact.
+/// * `GET /api/v1/search?q=<query>` lists the packages matching a query as json, see [`SearchResult`]
+///   and [`search::matches`](crate::search::matches) for how queries match. Registries aren't required
+///   to support searching.
+///
+/// Publishing a package uploads each artifact using `PUT /api/v1/packages/<name>/<version>/artifacts/<artifact>`,
+/// followed by its [`RemoteVersion`] using `PUT /api/v1/packages/<name>/<version>`, which makes it available.
+/// `PUT /api/v1/packages/<name>/<version>/yank` yanks every target of a version, and `DELETE` on the same path
+/// undoes that.
+///
+/// Target specific versions live under `/api/v1/packages/<name>/<version>/targets/<target>` instead, and are listed
+/// alongside the rest. Lookups prefer versions built for the registry's target, falling back to ones which
+/// aren't target specific.
+///
+/// Namespaced package names such as `myorg/networking/libfoo` are passed along as-is, so their segments become
+/// part of the path. Since name segments can't start with a digit, the version is always the first segment which does.
+///
+/// `orca serve` implements this api on top of a local cache, so any machine can act as a registry.
+///
+/// Downloads which fail along the way are retried with exponential backoff, see [`Registry::with_retries`].
+/// A retry resumes where the previous attempt stopped using a `Range` request, and every artifact is checked
+/// against its published digest once it is complete.
+pub struct Registry {
+    url: String,
+    agent: ureq::Agent,
+    /// Value of the `Authorization` header sent along with every request.
+    authorization: Option<String>,
+    target: String,
+    retries: u32,
+}
+
+impl Registry {
+    pub fn new(url: &str) -> Self {
+        Registry {
+            url: url.trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new().build(),
+            authorization: None,
+            target: target::host(),
+            retries: DEFAULT_RETRIES,
+        }
+    }
+
+    /// Retries failed downloads up to `retries` times, waiting twice as long before each retry as the one before it.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Connects to the registry at `url`, using the credentials and retries configured for it.
+    pub fn configured(url: &str, config: &Config) -> Result<Self, Error> {
+        Ok(Registry::new(url)
+            .with_credentials(&config.credentials(url))?
+            .with_retries(config.retries))
+    }
+
+    /// Looks up versions built for `target`, instead of the host.
+    pub fn with_target<S: Into<String>>(mut self, target: S) -> Self {
+        self.target = target.into();
+        self
+    }
+
+    /// Authenticates every request using `credentials`, presenting their client certificate if they have one.
+    pub fn with_credentials(mut self, credentials: &Credentials) -> Result<Self, Error> {
+        self.authorization = credentials.authorization();
+
+        if let Some(tls_config) = credentials.tls_config()? {
+            self.agent = ureq::AgentBuilder::new().tls_config(tls_config).build();
+        }
+
+        Ok(self)
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// All published versions of the named package. A package the registry doesn't know about has no versions.
+    pub fn versions(&self, name: &str) -> Result<Vec<RemoteVersion>, Error> {
+        identifier::parse_name(name)?;
+
+        match self.request("GET", &format!("packages/{}", name)).call() {
+            Ok(response) => Ok(response.into_json()?),
+            Err(ureq::Error::Status(404, _)) => Ok(vec![]),
+            Err(error) => Err(Box::new(error).into()),
+        }
+    }
+
+    /// Every package the registry has which matches `query`.
+    pub fn search(&self, query: &str) -> Result<Vec<SearchResult>, Error> {
+        match self.request("GET", "search").query("q", query).call() {
+            Ok(response) => Ok(response.into_json()?),
+            Err(ureq::Error::Status(404, _)) => Err(Error::SearchUnsupported(self.url.clone())),
+            Err(error) => Err(Box::new(error).into()),
+        }
+    }
+
+    /// Finds the highest published version of the named package which satisfies `required_version`, skipping
+    /// yanked versions. Pre-releases are only considered if `prereleases` are included, see [`identifier::satisfies`].
+    pub fn find(
+        &self,
+        name: &str,
+        required_version: &VersionReq,
+        prereleases: bool,
+    ) -> Result<Option<RemoteVersion>, Error> {
+        Ok(self
+            .versions(name)?
+            .into_iter()
+            .filter(|remote| {
+                self.supports(remote)
+                    && !remote.yanked
+                    && identifier::satisfies(&remote.version, required_version, prereleases)
+            })
+            .max_by(|a, b| {
+                a.version
+                    .cmp(&b.version)
+                    .then(a.target.is_some().cmp(&b.target.is_some()))
+            }))
+    }
+
+    /// Looks up exactly the given version of the named package.
+    pub fn get(&self, name: &str, version: &Version) -> Result<Option<RemoteVersion>, Error> {
+        Ok(self
+            .versions(name)?
+            .into_iter()
+            .filter(|remote| self.supports(remote) && &remote.version == version)
+            .max_by_key(|remote| remote.target.is_some()))
+    }
+
+    /// True if `remote` can be used when looking up versions for the registry's target.
+    fn supports(&self, remote: &RemoteVersion) -> bool {
+        remote
+            .target
+            .as_ref()
+            .map_or(true, |target| target == &self.target)
+    }
+
+    /// Downloads every artifact of the given version of a package, and stores the package in `cache`.
+    /// Artifacts are verified against the digests published by the registry, and the package against the
+    /// cache's signature requirements, before the package is kept.
+    pub fn download(
+        &self,
+        name: &str,
+        remote: &RemoteVersion,
+        cache: &Cache,
+    ) -> Result<Package, Error> {
+        info!("downloading {}@{} from {}", name, remote.version, self.url);
+
+        let staging = std::env::temp_dir().join(format!(
+            "orca-download-{}-{}-{}-{}",
+            name.replace('/', "-"),
+            remote.version,
+            remote.target.as_deref().unwrap_or("any"),
+            std::process::id()
+        ));
+
+        let result = self.download_into(name, remote, cache, &staging);
+        let _ = std::fs::remove_dir_all(&staging);
+        result
+    }
+
+    fn download_into(
+        &self,
+        name: &str,
+        remote: &RemoteVersion,
+        cache: &Cache,
+        staging: &Path,
+    ) -> Result<Package, Error> {
+        // The names come from the registry, which mustn't be able to write anywhere but the staging directory.
+        if let Some(artifact) = remote
+            .artifacts
+            .iter()
+            .find(|artifact| !is_artifact_name(&artifact.name))
+        {
+            return Err(Error::InvalidArtifact(artifact.name.clone()));
+        }
+
+        let mut artifacts = Vec::new();
+
+        for artifact in &remote.artifacts {
+            let path = staging.join(&artifact.name);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            self.fetch(
+                &format!(
+                    "{}/artifacts/{}",
+                    version_path(name, &remote.version, remote.target.as_deref()),
+                    artifact.name
+                ),
+                &path,
+                artifact,
+            )?;
+
+            artifacts.push(Artifact {
+                name: artifact.name.clone(),
+                path,
+                digest: Some(artifact.digest.clone()),
+            });
+        }
+
+        let download = Package {
+            name: identifier::parse_name(name)?,
+            version: remote.version.clone(),
+            target: remote.target.clone(),
+            signature: remote.signature.clone(),
+            description: remote.description.clone(),
+            dependencies: remote.dependencies.clone(),
+            yanked: remote.yanked,
+            artifacts,
+        };
+
+        // Downloaded packages must be signed as strictly as any other package in the cache. They're checked before
+        // they're stored, so a bad download never replaces a good local copy.
+        cache.check(&download)?;
+        Ok(cache.put(&download)?)
+    }
+
+    /// Downloads the given endpoint into a file at `path`, retrying transient failures, and checks the result
+    /// against the digest of `artifact`.
+    fn fetch(&self, endpoint: &str, path: &Path, artifact: &RemoteArtifact) -> Result<(), Error> {
+        fetch(
+            &|| self.request("GET", endpoint),
+            path,
+            &artifact.name,
+            &artifact.digest,
+            self.retries,
+        )
+    }
+
+    /// Uploads the artifacts of a cached package, followed by its metadata.
+    pub fn publish(&self, package: &Package) -> Result<(), Error> {
+        let path = version_path(
+            &package.name.join("/"),
+            &package.version,
+            package.target.as_deref(),
+        );
+        let mut artifacts = Vec::new();
+
+        for artifact in &package.artifacts {
+            let digest = match &artifact.digest {
+                Some(digest) => digest.clone(),
+                None => return Err(Error::Unpublishable(artifact.name.clone())),
+            };
+
+            let file = std::fs::File::open(&artifact.path)?;
+            self.request("PUT", &format!("{}/artifacts/{}", path, artifact.name))
+                .send(file)
+                .map_err(Box::new)?;
+
+            artifacts.push(RemoteArtifact {
+                name: artifact.name.clone(),
+                digest,
+            });
+        }
+
+        let metadata = serde_json::to_string(&RemoteVersion {
+            version: package.version.clone(),
+            target: package.target.clone(),
+            signature: package.signature.clone(),
+            description: package.description.clone(),
+            dependencies: package.dependencies.clone(),
+            yanked: package.yanked,
+            artifacts,
+        })
+        .map_err(io::Error::from)?;
+
+        self.request("PUT", &path)
+            .set("Content-Type", "application/json")
+            .send_string(&metadata)
+            .map_err(Box::new)?;
+
+        Ok(())
+    }
+
+    /// Marks every target of the given version of a package as yanked, or no longer yanked. Returns false if the
+    /// registry doesn't have the version.
+    pub fn yank(&self, name: &str, version: &Version, yanked: bool) -> Result<bool, Error> {
+        identifier::parse_name(name)?;
+
+        let method = if yanked { "PUT" } else { "DELETE" };
+        let path = format!("{}/yank", version_path(name, version, None));
+        match self.request(method, &path).call() {
+            Ok(_) => Ok(true),
+            Err(ureq::Error::Status(404, _)) => Ok(false),
+            Err(error) => Err(Box::new(error).into()),
+        }
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        let request = self
+            .agent
+            .request(method, &format!("{}/api/v1/{}", self.url, path));
+
+        match &self.authorization {
+            Some(authorization) => request.set("Authorization", authorization),
+            None => request,
+        }
+    }
+}
+
+/// Downloads whatever `request` asks for into a file at `path`, retrying transient failures up to `retries` times,
+/// and checks the result against `digest`. Failures are reported as failures to download `name`.
+fn fetch(
+    request: &dyn Fn() -> ureq::Request,
+    path: &Path,
+    name: &str,
+    digest: &str,
+    retries: u32,
+) -> Result<(), Error> {
+    let mut attempt = 0;
+
+    loop {
+        let result = fetch_once(request, path).and_then(|_| {
+            if cache::digest(path)? == digest {
+                Ok(())
+            } else {
+                // Whatever is there is no good to resume from.
+                std::fs::remove_file(path)?;
+                Err(Error::DigestMismatch(name.to_string()))
+            }
+        });
+
+        match result {
+            Err(error) if attempt < retries && error.is_transient() => {
+                let backoff = INITIAL_BACKOFF
+                    .checked_mul(2u32.saturating_pow(attempt))
+                    .map_or(MAX_BACKOFF, |backoff| backoff.min(MAX_BACKOFF));
+                attempt += 1;
+
+                warn!(
+                    "downloading {} failed: {}, retrying in {:?} ({}/{})",
+                    name, error, backoff, attempt, retries
+                );
+                thread::sleep(backoff);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Downloads whatever `request` asks for into a file at `path`. If the file already holds part of the download,
+/// only the rest of it is requested.
+fn fetch_once(request: &dyn Fn() -> ureq::Request, path: &Path) -> Result<(), Error> {
+    let existing = std::fs::metadata(path).map_or(0, |metadata| metadata.len());
+
+    let mut partial = request();
+    if existing > 0 {
+        partial = partial.set("Range", &format!("bytes={}-", existing));
+    }
+
+    let response = match partial.call() {
+        Ok(response) => response,
+        // The partial download is at least as large as the artifact, so it can't be resumed.
+        Err(ureq::Error::Status(416, _)) => {
+            std::fs::remove_file(path)?;
+            return fetch_once(request, path);
+        }
+        Err(error) => return Err(Box::new(error).into()),
+    };
+
+    // Servers which don't support ranges send the whole artifact instead.
+    let mut file = if response.status() == 206 {
+        OpenOptions::new().append(true).open(path)?
+    } else {
+        std::fs::File::create(path)?
+    };
+    io::copy(&mut response.into_reader(), &mut file)?;
+
+    Ok(())
+}
+
+/// Path of the given version of a package, relative to the api root.
+fn version_path(name: &str, version: &Version, target: Option<&str>) -> String {
+    match target {
+        Some(target) => format!("packages/{}/{}/targets/{}", name, version, target),
+        None => format!("packages/{}/{}", name, version),
+    }
+}
+
+/// Routes packages whose names match `packages` to the listed registries, as configured in the `[[routes]]`
+/// sections of the config. A glob pattern's `*` matches across segments, so `internal/*` matches
+/// `internal/networking/libfoo` as well.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Route {
+    pub packages: String,
+    /// Urls of the registries to look for matching packages in, in order.
+    pub registries: Vec<String>,
+}
+
+/// Every registry packages can be resolved against. Packages are looked for in the configured mirrors first,
+/// in order, followed by the canonical registry. Packages matching a [`Route`] are only looked for in the
+/// registries of the first route they match instead.
+///
+/// A registry which can't be reached is skipped in favour of the next one. If no other registry has the package
+/// either, the package isn't reported as missing, but the registry's failure is reported instead.
+#[derive(Default)]
+pub struct Registries {
+    registries: Vec<Registry>,
+    /// Retries of downloads which aren't from any registry, see [`Registries::download_url`].
+    retries: u32,
+    /// Indices of the registries packages which don't match any route are looked for in.
+    defaults: Vec<usize>,
+    routes: Vec<(glob::Pattern, Vec<usize>)>,
+}
+
+impl Registries {
+    /// Connects to the configured mirrors, registry and routes.
+    pub fn configured(config: &Config) -> Result<Self, Error> {
+        let mut registries = Registries {
+            retries: config.retries,
+            ..Registries::default()
+        };
+
+        for url in config.mirrors.iter().chain(&config.registry) {
+            let index = registries.add(url, config)?;
+            registries.defaults.push(index);
+        }
+
+        for route in &config.routes {
+            let pattern = glob::Pattern::new(&route.packages)?;
+            let indices = route
+                .registries
+                .iter()
+                .map(|url| registries.add(url, config))
+                .collect::<Result<_, _>>()?;
+            registries.routes.push((pattern, indices));
+        }
+
+        Ok(registries)
+    }
+
+    /// Looks up versions built for `target`, instead of the host.
+    pub fn with_target(mut self, target: &str) -> Self {
+        self.registries = self
+            .registries
+            .into_iter()
+            .map(|registry| registry.with_target(target))
+            .collect();
+        self
+    }
+
+    /// Downloads the file at `url` into `path`, retrying and resuming the download the same way as artifacts from a
+    /// registry, and checks it against `digest`. No credentials are sent along, since the url can point anywhere.
+    pub fn download_url(&self, url: &str, path: &Path, digest: &str) -> Result<(), Error> {
+        info!("downloading {}", url);
+
+        let agent = ureq::AgentBuilder::new().build();
+        fetch(&|| agent.get(url), path, url, digest, self.retries)
+    }
+
+    /// Index of the registry at `url`, connecting to it unless that's been done already.
+    fn add(&mut self, url: &str, config: &Config) -> Result<usize, Error> {
+        let url = credentials::normalize(url);
+        if let Some(index) = self
+            .registries
+            .iter()
+            .position(|registry| registry.url() == url)
+        {
+            return Ok(index);
+        }
+
+        self.registries.push(Registry::configured(&url, config)?);
+        Ok(self.registries.len() - 1)
+    }
+
+    /// The registries to look for the named package in, in order.
+    fn candidates(&self, name: &str) -> impl Iterator<Item = &Registry> + '_ {
+        let indices = self
+            .routes
+            .iter()
+            .find(|(pattern, _)| pattern.matches(name))
+            .map_or(&self.defaults, |(_, indices)| indices);
+
+        indices.iter().map(move |index| &self.registries[*index])
+    }
+
+    /// Finds the highest version of the named package satisfying `required_version` in the first registry
+    /// which has one, along with that registry.
+    pub fn find(
+        &self,
+        name: &str,
+        required_version: &VersionReq,
+        prereleases: bool,
+    ) -> Result<Option<(&Registry, RemoteVersion)>, Error> {
+        self.first(name, |registry| {
+            registry.find(name, required_version, prereleases)
+        })
+    }
+
+    /// Looks up exactly the given version of the named package in the first registry which has it.
+    pub fn get(
+        &self,
+        name: &str,
+        version: &Version,
+    ) -> Result<Option<(&Registry, RemoteVersion)>, Error> {
+        self.first(name, |registry| registry.get(name, version))
+    }
+
+    /// Every published version of the named package in any of the registries it's looked for in, along with
+    /// the registry publishing it. Registries which can't be reached are skipped.
+    pub fn versions(&self, name: &str) -> Result<Vec<(&Registry, RemoteVersion)>, Error> {
+        let mut versions = Vec::new();
+
+        for registry in self.candidates(name) {
+            match registry.versions(name) {
+                Ok(found) => versions.extend(found.into_iter().map(|remote| (registry, remote))),
+                Err(error) if error.is_transient() => {
+                    warn!("registry {} is unavailable: {}", registry.url(), error)
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(versions)
+    }
+
+    /// Every package matching `query` in any of the registries, along with every registry which couldn't be
+    /// searched and why.
+    pub fn search(&self, query: &str) -> (Vec<SearchResult>, Vec<(&Registry, Error)>) {
+        let mut results = Vec::new();
+        let mut failures = Vec::new();
+
+        for registry in &self.registries {
+            match registry.search(query) {
+                Ok(found) => results.extend(found),
+                Err(error) => failures.push((registry, error)),
+            }
+        }
+
+        (results, failures)
+    }
+
+    fn first<F>(&self, name: &str, lookup: F) -> Result<Option<(&Registry, RemoteVersion)>, Error>
+    where
+        F: Fn(&Registry) -> Result<Option<RemoteVersion>, Error>,
+    {
+        let mut failure = None;
+
+        for registry in self.candidates(name) {
+            match lookup(registry) {
+                Ok(Some(remote)) => return Ok(Some((registry, remote))),
+                Ok(None) => continue,
+                Err(error) if error.is_transient() => {
+                    warn!(
+                        "registry {} is unavailable, trying the next one: {}",
+                        registry.url(),
+                        error
+                    );
+                    failure = Some(error);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        match failure {
+            Some(error) => Err(error),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_artifact_name, Registries};
+    use crate::config::{Config, ConfigFile};
+
+    #[test]
+    fn test_routing() {
+        let config: ConfigFile = toml::from_str(
+            r#"
+            registry = "https://registry.example.com/"
+            mirrors = ["https://mirror.example.com"]
+
+            [[routes]]
+            packages = "internal/*"
+            registries = ["https://corp.example.com", "https://mirror.example.com"]
+            "#,
+        )
+        .unwrap();
+        let registries = Registries::configured(&Config::from(config)).unwrap();
+
+        let urls = |name: &str| -> Vec<_> {
+            registries
+                .candidates(name)
+                .map(|registry| registry.url().to_string())
+                .collect()
+        };
+
+        assert_eq!(
+            urls("libfoo"),
+            vec!["https://mirror.example.com", "https://registry.example.com"]
+        );
+        assert_eq!(
+            urls("internal/networking/libfoo"),
+            vec!["https://corp.example.com", "https://mirror.example.com"]
+        );
+        // Each registry is only connected to once, however many times it's mentioned.
+        assert_eq!(registries.registries.len(), 3);
+    }
+
+    #[test]
+    fn test_artifact_names() {
+        assert!(is_artifact_name("libfoo.so"));
+        assert!(is_artifact_name("lib/libfoo.so"));
+
+        for name in [
+            "",
+            "/etc/passwd",
+            "../../etc/passwd",
+            "lib/../../x",
+            "lib//libfoo.so",
+            "./libfoo.so",
+        ] {
+            assert!(!is_artifact_name(name), "{}", name);
+        }
+    }
+}
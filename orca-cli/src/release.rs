@@ -0,0 +1,420 @@
+use std::{io, ops::Range, path::PathBuf, process, str::FromStr};
+
+use clap::Clap;
+use log::warn;
+use semver::{Comparator, Op, Prerelease, Version, VersionReq};
+use thiserror::Error;
+
+use crate::{
+    build::spec::{self, BuildSpec},
+    config::Config,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    IO(#[from] io::Error),
+    #[error("{0}")]
+    Spec(#[from] spec::Error),
+    #[error("no spec named {0}")]
+    NotFound(String),
+    #[error("{0} is defined by more than one spec")]
+    Ambiguous(String),
+    #[error("the version of {0} can't be found in {1}")]
+    Unwritable(String, PathBuf),
+    #[error("git {0} failed")]
+    Git(String),
+}
+
+/// Which part of a version to increment. Incrementing a part resets the ones after it to zero, and any
+/// pre-release or build metadata is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bump {
+    Major,
+    Minor,
+    Patch,
+}
+
+impl FromStr for Bump {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "major" => Ok(Bump::Major),
+            "minor" => Ok(Bump::Minor),
+            "patch" => Ok(Bump::Patch),
+            other => Err(format!("unknown version part: {}", other)),
+        }
+    }
+}
+
+impl Bump {
+    pub fn apply(self, version: &Version) -> Version {
+        let (major, minor, patch) = match self {
+            Bump::Major => (version.major + 1, 0, 0),
+            Bump::Minor => (version.major, version.minor + 1, 0),
+            Bump::Patch => (version.major, version.minor, version.patch + 1),
+        };
+
+        Version::new(major, minor, patch)
+    }
+}
+
+#[derive(Clap)]
+pub struct VersionCmd {
+    #[clap(about = "Name of the package whose version to show or bump.")]
+    pub package: String,
+    #[clap(
+        long,
+        about = "Path(s) to one or more BuildSpec files, defaults to the workspace members."
+    )]
+    pub spec: Vec<String>,
+    #[clap(long, possible_values = &["major", "minor", "patch"], about = "Part of the version to increment. Without it, the current version is printed.")]
+    pub bump: Option<Bump>,
+    #[clap(
+        long,
+        requires = "bump",
+        about = "Also update the requirements of specs depending on the package, if they don't allow the new version."
+    )]
+    pub update_dependents: bool,
+    #[clap(
+        long,
+        requires = "bump",
+        about = "Commit the changed spec files using git, and tag the commit as name@version."
+    )]
+    pub tag: bool,
+}
+
+impl VersionCmd {
+    /// Rewrites the version in the spec file in place, leaving the rest of the file as it is. Versions are expected
+    /// to be declared near the name of the spec, and requirements near the name of the dependency, as they are in
+    /// specs created by `orca new`.
+    pub(crate) fn execute(&self, config: &Config) -> Result<(), Error> {
+        let specs = spec::load_all(&config.specs(&self.spec), None)?;
+
+        let mut matching = specs.iter().filter(|spec| spec.name == self.package);
+        let spec = matching
+            .next()
+            .ok_or_else(|| Error::NotFound(self.package.clone()))?;
+        if matching.next().is_some() {
+            return Err(Error::Ambiguous(self.package.clone()));
+        }
+
+        let bump = match self.bump {
+            Some(bump) => bump,
+            None => {
+                println!("{}", spec.version);
+                return Ok(());
+            }
+        };
+
+        let version = bump.apply(&spec.version);
+        let path = spec
+            .path
+            .as_deref()
+            .ok_or_else(|| Error::NotFound(self.package.clone()))?;
+        let contents = std::fs::read_to_string(path)?;
+        let contents = set_version(&contents, &spec.name, &spec.version, &version)
+            .ok_or_else(|| Error::Unwritable(spec.name.clone(), path.to_path_buf()))?;
+        std::fs::write(path, contents)?;
+        println!("bumped {} from {} to {}", spec.name, spec.version, version);
+
+        let mut changed = vec![path.to_path_buf()];
+        for dependent in &specs {
+            let dependency = match dependent
+                .dependencies
+                .iter()
+                .find(|dependency| dependency.name == spec.name)
+            {
+                Some(dependency) if !dependency.matches(&version) => dependency,
+                _ => continue,
+            };
+
+            if !self.update_dependents {
+                warn!(
+                    "{} requires {} {}, which no longer includes {}",
+                    dependent, dependency.name, dependency.version, version
+                );
+                continue;
+            }
+
+            let requirement = widen(&dependency.version, &version);
+            if !update_requirement(dependent, &spec.name, &dependency.version, &requirement)? {
+                // Requirements inherited from a base spec are left for the user to update.
+                warn!(
+                    "the requirement of {} on {} can't be found in its spec file",
+                    dependent, spec.name
+                );
+                continue;
+            }
+
+            println!(
+                "updated the requirement of {} on {} from {} to {}",
+                dependent, spec.name, dependency.version, requirement
+            );
+            changed.extend(dependent.path.clone());
+        }
+
+        if self.tag {
+            changed.sort();
+            changed.dedup();
+            tag(&format!("{}@{}", spec.name, version), &changed)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Rewrites the requirement of `dependent` on `name` in its spec file. Returns false if it isn't in there.
+fn update_requirement(
+    dependent: &BuildSpec,
+    name: &str,
+    previous: &VersionReq,
+    requirement: &VersionReq,
+) -> Result<bool, Error> {
+    let path = match &dependent.path {
+        Some(path) => path,
+        None => return Ok(false),
+    };
+
+    let contents = std::fs::read_to_string(path)?;
+    match set_requirement(&contents, name, previous, requirement) {
+        Some(contents) => {
+            std::fs::write(path, contents)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// The requirement to replace `requirement` with so it includes `version`, which keeps the operator and precision
+/// of simple requirements such as `^1.2` or `~1.2.3`, and replaces anything else with `^version`.
+pub fn widen(requirement: &VersionReq, version: &Version) -> VersionReq {
+    let comparator = match requirement.comparators.as_slice() {
+        [comparator] if matches!(comparator.op, Op::Caret | Op::Tilde | Op::Exact) => Comparator {
+            op: comparator.op,
+            major: version.major,
+            minor: comparator.minor.map(|_| version.minor),
+            patch: comparator.patch.map(|_| version.patch),
+            pre: Prerelease::EMPTY,
+        },
+        _ => Comparator {
+            op: Op::Caret,
+            major: version.major,
+            minor: Some(version.minor),
+            patch: Some(version.patch),
+            pre: Prerelease::EMPTY,
+        },
+    };
+
+    VersionReq {
+        comparators: vec![comparator],
+    }
+}
+
+/// Replaces `previous`, the version of the spec named `name`, with `version` in the contents of its spec file.
+pub fn set_version(
+    contents: &str,
+    name: &str,
+    previous: &Version,
+    version: &Version,
+) -> Option<String> {
+    replace_near(contents, name, version, |value| {
+        Version::parse(value).map_or(false, |value| &value == previous)
+    })
+}
+
+/// Replaces `previous`, a requirement on the package `name`, with `requirement` in the contents of a spec file.
+pub fn set_requirement(
+    contents: &str,
+    name: &str,
+    previous: &VersionReq,
+    requirement: &VersionReq,
+) -> Option<String> {
+    replace_near(contents, name, requirement, |value| {
+        VersionReq::parse(value).map_or(false, |value| &value == previous)
+    })
+}
+
+/// Replaces the `version` value accepted by `matches` closest to a `name` equal to `name`, which works the same
+/// way for specs written in json, yaml or toml.
+fn replace_near<F: Fn(&str) -> bool, D: std::fmt::Display>(
+    contents: &str,
+    name: &str,
+    replacement: D,
+    matches: F,
+) -> Option<String> {
+    let names: Vec<_> = values(contents, "name")
+        .into_iter()
+        .filter(|range| &contents[range.clone()] == name)
+        .collect();
+
+    let distance = |range: &Range<usize>| {
+        names
+            .iter()
+            .map(|name| (name.start as isize - range.start as isize).abs())
+            .min()
+    };
+
+    let range = values(contents, "version")
+        .into_iter()
+        .filter(|range| matches(&contents[range.clone()]))
+        .filter_map(|range| distance(&range).map(|distance| (distance, range)))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, range)| range)?;
+
+    let mut replaced = contents.to_string();
+    replaced.replace_range(range, &replacement.to_string());
+    Some(replaced)
+}
+
+/// Ranges of the values of every `key` in `contents`, such as `key = "value"`, `"key": "value"` or `key: value`,
+/// excluding any quotes around the value.
+fn values(contents: &str, key: &str) -> Vec<Range<usize>> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    let mut ranges = Vec::new();
+
+    for (start, _) in contents.match_indices(key) {
+        let end = start + key.len();
+        if contents[..start].chars().next_back().map_or(false, is_word)
+            || contents[end..].chars().next().map_or(false, is_word)
+        {
+            continue;
+        }
+
+        let rest = contents[end..].trim_start_matches(&['"', '\''][..]);
+        let rest = rest.trim_start_matches(&[' ', '\t'][..]);
+        let rest = match rest.strip_prefix(&['=', ':'][..]) {
+            Some(rest) => rest.trim_start_matches(&[' ', '\t'][..]),
+            None => continue,
+        };
+
+        let offset = contents.len() - rest.len();
+        let range = match rest.chars().next() {
+            Some(quote) if quote == '"' || quote == '\'' => rest[1..]
+                .find(quote)
+                .map(|length| offset + 1..offset + 1 + length),
+            // Unquoted yaml values end at the end of the line, or the end of a flow mapping.
+            Some(_) => {
+                let length = rest.find(&[',', '}', '\n', '#'][..]).unwrap_or(rest.len());
+                let length = rest[..length].trim_end().len();
+                Some(offset..offset + length).filter(|range| !range.is_empty())
+            }
+            None => None,
+        };
+
+        ranges.extend(range);
+    }
+
+    ranges
+}
+
+/// Commits `files` and tags the commit as `release`.
+fn tag(release: &str, files: &[PathBuf]) -> Result<(), Error> {
+    let message = format!("Release {}", release);
+
+    git(&["add", "--"], files)?;
+    git(&["commit", "--message", &message, "--"], files)?;
+    git(&["tag", "--annotate", "--message", &message, release], &[])?;
+    println!("tagged {}", release);
+
+    Ok(())
+}
+
+fn git(arguments: &[&str], paths: &[PathBuf]) -> Result<(), Error> {
+    let status = process::Command::new("git")
+        .args(arguments)
+        .args(paths)
+        .status()?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::Git(arguments[0].to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::{Version, VersionReq};
+
+    use super::{set_requirement, set_version, widen, Bump};
+
+    #[test]
+    fn test_bump() {
+        let version = Version::parse("1.2.3-beta.1").unwrap();
+        assert_eq!(Bump::Major.apply(&version), Version::new(2, 0, 0));
+        assert_eq!(Bump::Minor.apply(&version), Version::new(1, 3, 0));
+        assert_eq!(Bump::Patch.apply(&version), Version::new(1, 2, 4));
+    }
+
+    #[test]
+    fn test_widen() {
+        let widen = |requirement: &str, version: &str| {
+            widen(
+                &VersionReq::parse(requirement).unwrap(),
+                &Version::parse(version).unwrap(),
+            )
+            .to_string()
+        };
+
+        assert_eq!(widen("^1", "2.0.0"), "^2");
+        assert_eq!(widen("~1.2", "1.3.0"), "~1.3");
+        assert_eq!(widen("=1.2.3", "1.2.4"), "=1.2.4");
+        assert_eq!(widen(">=1, <2", "2.0.0"), "^2.0.0");
+    }
+
+    #[test]
+    fn test_set_version() {
+        let toml = indoc::indoc! {r#"
+            [[specs]]
+            name = "libfoo"
+            version = "1.2.0"
+
+            [[specs]]
+            name = "app"
+            version = "1.2.0" # same as libfoo
+            dependencies = [{ name = "libfoo", version = "^1" }]
+        "#};
+
+        let bumped = set_version(
+            toml,
+            "app",
+            &Version::parse("1.2.0").unwrap(),
+            &Version::new(2, 0, 0),
+        )
+        .unwrap();
+        assert!(bumped.contains("name = \"libfoo\"\nversion = \"1.2.0\""));
+        assert!(bumped.contains("version = \"2.0.0\" # same as libfoo"));
+
+        let updated = set_requirement(
+            &bumped,
+            "libfoo",
+            &VersionReq::parse("^1").unwrap(),
+            &VersionReq::parse("^2").unwrap(),
+        )
+        .unwrap();
+        assert!(updated.contains("{ name = \"libfoo\", version = \"^2\" }"));
+
+        let yaml = "name: libfoo\nversion: 0.1.0\n";
+        assert_eq!(
+            set_version(
+                yaml,
+                "libfoo",
+                &Version::parse("0.1.0").unwrap(),
+                &Version::new(0, 1, 1)
+            )
+            .unwrap(),
+            "name: libfoo\nversion: 0.1.1\n"
+        );
+
+        let json = r#"{ "name": "libfoo", "version": "0.1.0" }"#;
+        assert!(set_version(
+            json,
+            "other",
+            &Version::parse("0.1.0").unwrap(),
+            &Version::new(0, 2, 0)
+        )
+        .is_none());
+    }
+}
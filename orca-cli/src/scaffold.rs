@@ -0,0 +1,212 @@
+use std::{
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::Clap;
+use semver::Version;
+use thiserror::Error;
+
+use crate::{build::spec::Format, config::PROJECT_CONFIG, identifier};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    IO(#[from] io::Error),
+    #[error("{0} already exists")]
+    Exists(PathBuf),
+    #[error("invalid package name {0}: {1}")]
+    Name(String, #[source] identifier::ParsingError),
+    #[error("invalid version {0}: {1}")]
+    Version(String, #[source] semver::Error),
+}
+
+/// Options shared by `orca init` and `orca new`.
+#[derive(Clap)]
+pub struct ScaffoldOptions {
+    #[clap(
+        long,
+        about = "Name of the package, defaults to the name of the directory."
+    )]
+    pub name: Option<String>,
+    #[clap(long, about = "Version of the package, defaults to 0.1.0.")]
+    pub version: Option<String>,
+    #[clap(long, default_value = "toml", possible_values = &["json", "yaml", "toml"], about = "Format to write the spec in.")]
+    pub format: Format,
+    #[clap(
+        long,
+        about = "Also create an orca.toml, which makes the directory a workspace."
+    )]
+    pub workspace: bool,
+    #[clap(
+        long,
+        short,
+        about = "Use the defaults instead of prompting for anything left out."
+    )]
+    pub yes: bool,
+}
+
+#[derive(Clap)]
+pub struct InitCmd {
+    #[clap(flatten)]
+    pub options: ScaffoldOptions,
+}
+
+#[derive(Clap)]
+pub struct NewCmd {
+    #[clap(parse(from_os_str), about = "Directory to create the spec in.")]
+    pub path: PathBuf,
+    #[clap(flatten)]
+    pub options: ScaffoldOptions,
+}
+
+impl InitCmd {
+    pub(crate) fn execute(&self) -> Result<(), Error> {
+        scaffold(&std::env::current_dir()?, &self.options)
+    }
+}
+
+impl NewCmd {
+    pub(crate) fn execute(&self) -> Result<(), Error> {
+        if self.path.exists() {
+            return Err(Error::Exists(self.path.clone()));
+        }
+
+        std::fs::create_dir_all(&self.path)?;
+        scaffold(&self.path, &self.options)
+    }
+}
+
+/// Writes a skeleton spec into `directory`, along with a workspace config if asked to.
+fn scaffold(directory: &Path, options: &ScaffoldOptions) -> Result<(), Error> {
+    let spec_path = directory.join(format!("spec.{}", options.format.extension()));
+    let config_path = directory.join(PROJECT_CONFIG);
+
+    for path in std::iter::once(&spec_path).chain(Some(&config_path).filter(|_| options.workspace))
+    {
+        if path.exists() {
+            return Err(Error::Exists(path.clone()));
+        }
+    }
+
+    let default_name = directory
+        .canonicalize()?
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "package".to_string());
+    let interactive = !options.yes && atty::is(atty::Stream::Stdin);
+
+    let name = match &options.name {
+        Some(name) => name.clone(),
+        None if interactive => prompt("Package name", &default_name)?,
+        None => default_name,
+    };
+    identifier::parse_name(&name).map_err(|error| Error::Name(name.clone(), error))?;
+
+    let version = match &options.version {
+        Some(version) => version.clone(),
+        None if interactive => prompt("Version", "0.1.0")?,
+        None => "0.1.0".to_string(),
+    };
+    let version = Version::parse(&version).map_err(|error| Error::Version(version, error))?;
+
+    std::fs::write(&spec_path, skeleton(options.format, &name, &version))?;
+    println!("created {}", spec_path.display());
+
+    if options.workspace {
+        std::fs::write(&config_path, "[workspace]\nmembers = [\".\"]\n")?;
+        println!("created {}", config_path.display());
+    }
+
+    Ok(())
+}
+
+/// A spec with a single step, which builds a placeholder artifact.
+fn skeleton(format: Format, name: &str, version: &Version) -> String {
+    match format {
+        Format::Json => format!(
+            r#"{{
+    "name": "{name}",
+    "version": "{version}",
+    "dependencies": [],
+    "steps": [
+        {{ "run": "mkdir -p dist && echo ${{name}}-${{version}} > dist/README" }}
+    ],
+    "artifacts": ["dist/*"]
+}}
+"#,
+            name = name,
+            version = version
+        ),
+        Format::Yaml => format!(
+            r#"name: {name}
+version: {version}
+# Other specs or packages this one needs, such as `- {{ name: zlib, version: "^1" }}`.
+dependencies: []
+# Commands to build the package with, run in order within the build directory.
+steps:
+  - run: mkdir -p dist && echo ${{name}}-${{version}} > dist/README
+# Files to collect into the cache once the build has finished.
+artifacts:
+  - dist/*
+"#,
+            name = name,
+            version = version
+        ),
+        Format::Toml => format!(
+            r#"name = "{name}"
+version = "{version}"
+# Other specs or packages this one needs, such as `{{ name = "zlib", version = "^1" }}`.
+dependencies = []
+# Files to collect into the cache once the build has finished.
+artifacts = ["dist/*"]
+
+# Commands to build the package with, run in order within the build directory.
+[[steps]]
+run = "mkdir -p dist && echo ${{name}}-${{version}} > dist/README"
+"#,
+            name = name,
+            version = version
+        ),
+    }
+}
+
+/// Asks for a value on stderr, falling back to `default` if the answer is empty.
+fn prompt(question: &str, default: &str) -> Result<String, Error> {
+    eprint!("{} [{}]: ", question, default);
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+
+    let answer = answer.trim();
+    Ok(if answer.is_empty() {
+        default.to_string()
+    } else {
+        answer.to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use semver::Version;
+
+    use super::skeleton;
+    use crate::build::spec::{parse, Format};
+
+    #[test]
+    fn test_skeletons_parse() {
+        let version = Version::parse("0.1.0").unwrap();
+
+        for format in &[Format::Json, Format::Yaml, Format::Toml] {
+            let contents = skeleton(*format, "org/libfoo", &version);
+            let specs = parse(&contents, *format, Path::new("spec")).unwrap();
+
+            assert_eq!(specs[0].name, "org/libfoo");
+            assert_eq!(specs[0].version, version);
+            assert_eq!(specs[0].artifacts, vec!["dist/*"]);
+        }
+    }
+}
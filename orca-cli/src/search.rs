@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+
+use clap::Clap;
+use log::warn;
+use semver::Version;
+use thiserror::Error;
+
+use crate::{
+    cache::{self, Cache, Package},
+    config::Config,
+    output::OutputFormat,
+    registry::{self, Registries, SearchResult},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("cache error: {0}")]
+    Cache(#[from] cache::Error),
+    #[error("registry error: {0}")]
+    Registry(#[from] registry::Error),
+    #[error("invalid pattern: {0}")]
+    Pattern(#[from] glob::PatternError),
+}
+
+#[derive(Clap)]
+pub struct SearchCmd {
+    #[clap(
+        about = "Part of the package name to search for, or a glob pattern such as org/*/lib* matching the whole name."
+    )]
+    pub query: String,
+    #[clap(long, about = "Search the local cache as well as the registries.")]
+    pub cache: bool,
+}
+
+impl SearchCmd {
+    pub(crate) fn execute(&self, config: &Config) -> Result<(), Error> {
+        // Fail on patterns which can't match anything, before asking every registry about them.
+        matches(&self.query, "")?;
+
+        let registries = Registries::configured(config)?;
+        let (mut results, failures) = registries.search(&self.query);
+        for (registry, error) in failures {
+            warn!("failed to search {}: {}", registry.url(), error);
+        }
+
+        if self.cache {
+            let packages = Cache::configured(config)?.list()?;
+            let mut matching = Vec::new();
+            for package in packages {
+                if matches(&self.query, &package.name.join("/"))? {
+                    matching.push(package);
+                }
+            }
+
+            results.extend(summarize(matching));
+        }
+
+        let results = merge(results);
+        match config.output {
+            OutputFormat::Text => {
+                for result in &results {
+                    let versions: Vec<_> = result.versions.iter().map(|v| v.to_string()).collect();
+                    match &result.description {
+                        Some(description) => {
+                            println!("{} ({}): {}", result.name, versions.join(", "), description)
+                        }
+                        None => println!("{} ({})", result.name, versions.join(", ")),
+                    }
+                }
+            }
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&results).map_err(cache::Error::from)?
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+/// True if the package `name` matches `query`. Queries containing any of `*`, `?` or `[` are glob patterns
+/// which must match the whole name, while anything else matches any name containing it, ignoring case.
+pub fn matches(query: &str, name: &str) -> Result<bool, glob::PatternError> {
+    if query.contains(&['*', '?', '['][..]) {
+        Ok(glob::Pattern::new(query)?.matches(name))
+    } else {
+        Ok(name.to_lowercase().contains(&query.to_lowercase()))
+    }
+}
+
+/// Describes `packages` the way searches list them, with one result for each package name.
+pub fn summarize<I: IntoIterator<Item = Package>>(packages: I) -> Vec<SearchResult> {
+    merge(packages.into_iter().map(|package| SearchResult {
+        name: package.name.join("/"),
+        versions: vec![package.version],
+        description: package.description,
+    }))
+}
+
+/// Combines results for the same package, such as ones found in more than one registry, ordered by name.
+/// Descriptions of higher versions take precedence.
+fn merge<I: IntoIterator<Item = SearchResult>>(results: I) -> Vec<SearchResult> {
+    let mut merged: BTreeMap<String, (Vec<Version>, Option<(Version, String)>)> = BTreeMap::new();
+
+    for result in results {
+        let (versions, description) = merged.entry(result.name).or_default();
+
+        if let (Some(latest), Some(text)) = (result.versions.iter().max(), result.description) {
+            if description
+                .as_ref()
+                .map_or(true, |(version, _)| latest > version)
+            {
+                *description = Some((latest.clone(), text));
+            }
+        }
+        versions.extend(result.versions);
+    }
+
+    merged
+        .into_iter()
+        .map(|(name, (mut versions, description))| {
+            versions.sort();
+            versions.dedup();
+
+            SearchResult {
+                name,
+                versions,
+                description: description.map(|(_, text)| text),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+
+    use super::{matches, merge};
+    use crate::registry::SearchResult;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("foo", "org/LibFoo").unwrap());
+        assert!(!matches("bar", "org/libfoo").unwrap());
+        assert!(matches("org/*", "org/networking/libfoo").unwrap());
+        assert!(!matches("lib*", "org/libfoo").unwrap());
+        assert!(matches("[", "libfoo").is_err());
+    }
+
+    #[test]
+    fn test_merge() {
+        let result = |version: &str, description: Option<&str>| SearchResult {
+            name: "libfoo".to_string(),
+            versions: vec![Version::parse(version).unwrap()],
+            description: description.map(String::from),
+        };
+
+        let merged = merge(vec![
+            result("1.2.0", Some("newer")),
+            result("1.0.0", Some("older")),
+            result("1.2.0", None),
+        ]);
+
+        assert_eq!(merged.len(), 1);
+        assert_eq!(
+            merged[0].versions,
+            vec![
+                Version::parse("1.0.0").unwrap(),
+                Version::parse("1.2.0").unwrap()
+            ]
+        );
+        assert_eq!(merged[0].description.as_deref(), Some("newer"));
+    }
+}
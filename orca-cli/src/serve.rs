@@ -0,0 +1,671 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread,
+};
+
+use clap::Clap;
+use log::{error, info};
+use semver::Version;
+use thiserror::Error;
+use tiny_http::{Header, Method, Request, Response, ResponseBox, Server};
+
+use crate::{
+    cache::{self, Artifact, Cache, Package},
+    config::Config,
+    identifier,
+    registry::{is_artifact_name, RemoteArtifact, RemoteVersion},
+    search,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    IO(#[from] io::Error),
+    #[error("cache error: {0}")]
+    Cache(#[from] cache::Error),
+    #[error("failed to listen on {0}: {1}")]
+    Bind(String, String),
+    #[error(
+        "refusing to accept uploads without a token, pass --token or configure credentials for {0}"
+    )]
+    NoToken(String),
+}
+
+#[derive(Clap)]
+pub struct ServeCmd {
+    #[clap(
+        long,
+        default_value = "127.0.0.1:8080",
+        about = "Address to listen on, as host:port."
+    )]
+    pub listen: String,
+    #[clap(
+        long,
+        about = "Accept packages published using orca publish, and versions yanked using orca yank. Uploads must be authorized with --token, or the credentials configured for this server's url."
+    )]
+    pub allow_uploads: bool,
+    #[clap(
+        long,
+        about = "Token uploads must be sent with as a bearer token, instead of the credentials configured for this server's url."
+    )]
+    pub token: Option<String>,
+    #[clap(
+        long,
+        default_value = "4",
+        about = "Number of requests to handle at the same time."
+    )]
+    pub threads: usize,
+}
+
+impl ServeCmd {
+    pub(crate) fn execute(&self, config: &Config) -> Result<(), Error> {
+        let url = format!("http://{}", self.listen);
+        let authorization = match &self.token {
+            Some(token) => Some(format!("Bearer {}", token)),
+            None => config.credentials(&url).authorization(),
+        };
+        // Anyone who can reach the server could publish and yank packages otherwise.
+        if self.allow_uploads && authorization.is_none() {
+            return Err(Error::NoToken(url));
+        }
+
+        let server = Server::http(&self.listen)
+            .map_err(|error| Error::Bind(self.listen.clone(), error.to_string()))?;
+
+        let state = Arc::new(State {
+            cache: Cache::configured(config)?,
+            uploads: self.allow_uploads,
+            authorization,
+            staging: std::env::temp_dir().join(format!("orca-serve-{}", std::process::id())),
+        });
+        let server = Arc::new(server);
+
+        info!(
+            "serving {} on http://{}",
+            config.cache_directory.display(),
+            self.listen
+        );
+
+        let workers: Vec<_> = (0..self.threads.max(1))
+            .map(|_| {
+                let server = server.clone();
+                let state = state.clone();
+
+                thread::spawn(move || {
+                    for request in server.incoming_requests() {
+                        state.handle(request);
+                    }
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            let _ = worker.join();
+        }
+
+        Ok(())
+    }
+}
+
+/// An endpoint of the registry api, see [`Registry`](crate::registry::Registry) for the api served.
+#[derive(Debug, PartialEq)]
+enum Route {
+    Versions {
+        name: String,
+    },
+    Version {
+        name: String,
+        version: Version,
+        target: Option<String>,
+    },
+    Artifact {
+        name: String,
+        version: Version,
+        target: Option<String>,
+        artifact: String,
+    },
+    Search {
+        query: String,
+    },
+    /// Yanks every target of a version, which only applies to versions as a whole.
+    Yank {
+        name: String,
+        version: Version,
+    },
+}
+
+impl Route {
+    /// Parses the path of a request. Since name segments can't start with a digit, the version is the
+    /// first segment which does.
+    fn parse(url: &str) -> Option<Route> {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        if path == "/api/v1/search" {
+            let query = query
+                .split('&')
+                .find_map(|parameter| parameter.strip_prefix("q="))
+                .and_then(decode)?;
+            return Some(Route::Search { query });
+        }
+
+        let path = path.strip_prefix("/api/v1/packages/")?;
+        let segments: Vec<_> = path.split('/').collect();
+
+        let position = segments.iter().position(|segment| {
+            segment
+                .chars()
+                .next()
+                .map_or(false, |first| first.is_ascii_digit())
+        });
+
+        let (name, rest) = match position {
+            Some(position) => (segments[..position].join("/"), &segments[position..]),
+            None => (segments.join("/"), &segments[segments.len()..]),
+        };
+        identifier::parse_name(&name).ok()?;
+
+        let (version, rest) = match rest.split_first() {
+            Some((version, rest)) => (Version::parse(version).ok()?, rest),
+            None => return Some(Route::Versions { name }),
+        };
+
+        let (target, rest) = match rest {
+            ["targets", target, rest @ ..] if !matches!(*target, "" | "." | "..") => {
+                (Some(target.to_string()), rest)
+            }
+            rest => (None, rest),
+        };
+
+        match rest {
+            ["yank"] if target.is_none() => Some(Route::Yank { name, version }),
+            [] => Some(Route::Version {
+                name,
+                version,
+                target,
+            }),
+            ["artifacts", artifact @ ..] if is_artifact_name(&artifact.join("/")) => {
+                Some(Route::Artifact {
+                    name,
+                    version,
+                    target,
+                    artifact: artifact.join("/"),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Everything shared between the threads handling requests.
+struct State {
+    cache: Cache,
+    uploads: bool,
+    /// The `Authorization` header uploads must be sent with. Without one, no upload is authorized.
+    authorization: Option<String>,
+    /// Where uploaded artifacts are kept until the package they belong to is published.
+    staging: PathBuf,
+}
+
+impl State {
+    fn handle(&self, mut request: Request) {
+        let method = request.method().clone();
+        let url = request.url().to_string();
+
+        let response = match self.respond(&mut request) {
+            Ok(response) => response,
+            Err(error) => {
+                error!("{} {} failed: {}", method, url, error);
+                text(500, &error.to_string())
+            }
+        };
+
+        info!("{} {} {}", method, url, response.status_code().0);
+        if let Err(error) = request.respond(response) {
+            error!("failed to respond to {} {}: {}", method, url, error);
+        }
+    }
+
+    fn respond(&self, request: &mut Request) -> Result<ResponseBox, Error> {
+        let route = match Route::parse(request.url()) {
+            Some(route) => route,
+            None => return Ok(text(404, "not found")),
+        };
+
+        let method = request.method().clone();
+        match (&method, route) {
+            (Method::Get, Route::Versions { name }) => {
+                let versions: Vec<_> = self
+                    .cache
+                    .list_versions(&name)?
+                    .iter()
+                    .map(remote_version)
+                    .collect();
+                json(&versions)
+            }
+            (
+                Method::Get,
+                Route::Version {
+                    name,
+                    version,
+                    target,
+                },
+            ) => match self.package(&name, &version, target.as_deref())? {
+                Some(package) => json(&remote_version(&package)),
+                None => Ok(text(404, "no such package")),
+            },
+            (
+                Method::Get,
+                Route::Artifact {
+                    name,
+                    version,
+                    target,
+                    artifact,
+                },
+            ) => {
+                let found = self
+                    .package(&name, &version, target.as_deref())?
+                    .and_then(|package| {
+                        package
+                            .artifacts
+                            .into_iter()
+                            .find(|candidate| candidate.name == artifact)
+                    });
+
+                match found {
+                    Some(artifact) => {
+                        Ok(Response::from_file(std::fs::File::open(&artifact.path)?).boxed())
+                    }
+                    None => Ok(text(404, "no such artifact")),
+                }
+            }
+            (Method::Get, Route::Search { query }) => {
+                let mut matching = Vec::new();
+                for package in self.cache.list()? {
+                    match search::matches(&query, &package.name.join("/")) {
+                        Ok(true) => matching.push(package),
+                        Ok(false) => {}
+                        Err(error) => return Ok(text(400, &error.to_string())),
+                    }
+                }
+
+                json(&search::summarize(matching))
+            }
+            (Method::Put | Method::Delete, _) if !self.uploads => Ok(text(
+                405,
+                "uploads are disabled, use orca serve --allow-uploads",
+            )),
+            (Method::Put | Method::Delete, _) if !self.authorized(request) => {
+                Ok(text(401, "unauthorized"))
+            }
+            (Method::Put | Method::Delete, Route::Yank { name, version }) => {
+                let yanked = method == Method::Put;
+                if self.cache.yank(&name, &version, yanked)?.is_empty() {
+                    return Ok(text(404, "no such package"));
+                }
+
+                info!(
+                    "{} {}@{}",
+                    if yanked { "yanked" } else { "unyanked" },
+                    name,
+                    version
+                );
+                Ok(text(200, "ok"))
+            }
+            (
+                Method::Put,
+                Route::Artifact {
+                    name,
+                    version,
+                    target,
+                    artifact,
+                },
+            ) => {
+                let path = self
+                    .upload_path(&name, &version, target.as_deref())
+                    .join(&artifact);
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+
+                let mut file = std::fs::File::create(&path)?;
+                io::copy(request.as_reader(), &mut file)?;
+                Ok(text(200, "ok"))
+            }
+            (
+                Method::Put,
+                Route::Version {
+                    name,
+                    version,
+                    target,
+                },
+            ) => {
+                let remote: RemoteVersion = match serde_json::from_reader(request.as_reader()) {
+                    Ok(remote) => remote,
+                    Err(error) => return Ok(text(400, &error.to_string())),
+                };
+
+                if remote.version != version || remote.target != target {
+                    return Ok(text(400, "metadata does not match the published path"));
+                }
+
+                let staging = self.upload_path(&name, &version, target.as_deref());
+                let result = self.publish(&name, &remote, &staging);
+                let _ = std::fs::remove_dir_all(&staging);
+                result
+            }
+            _ => Ok(text(405, "method not allowed")),
+        }
+    }
+
+    /// Stores a published package in the cache, once its artifacts have all been uploaded. The package
+    /// must match the digests it was published with, and the cache's signature requirements, which are checked
+    /// before it's stored. Published versions are never replaced, so a bad upload can't destroy a good one.
+    fn publish(
+        &self,
+        name: &str,
+        remote: &RemoteVersion,
+        staging: &Path,
+    ) -> Result<ResponseBox, Error> {
+        // Names come from the request, and mustn't be able to point at files outside the staging directory.
+        if let Some(artifact) = remote
+            .artifacts
+            .iter()
+            .find(|artifact| !is_artifact_name(&artifact.name))
+        {
+            return Ok(text(
+                400,
+                &format!("invalid artifact name {}", artifact.name),
+            ));
+        }
+
+        if self
+            .package(name, &remote.version, remote.target.as_deref())?
+            .is_some()
+        {
+            return Ok(text(
+                409,
+                &format!("{}@{} is already published", name, remote.version),
+            ));
+        }
+
+        let mut artifacts = Vec::new();
+        for artifact in &remote.artifacts {
+            let path = staging.join(&artifact.name);
+            if !path.is_file() {
+                return Ok(text(
+                    400,
+                    &format!("artifact {} was not uploaded", artifact.name),
+                ));
+            }
+
+            artifacts.push(Artifact {
+                name: artifact.name.clone(),
+                path,
+                digest: Some(artifact.digest.clone()),
+            });
+        }
+
+        let published = Package {
+            name: identifier::parse_name(name).map_err(cache::Error::from)?,
+            version: remote.version.clone(),
+            target: remote.target.clone(),
+            signature: remote.signature.clone(),
+            description: remote.description.clone(),
+            dependencies: remote.dependencies.clone(),
+            yanked: remote.yanked,
+            artifacts,
+        };
+
+        if let Err(error) = self.cache.check(&published) {
+            return Ok(text(400, &error.to_string()));
+        }
+
+        let package = self.cache.put(&published)?;
+        info!("published {}", package);
+        Ok(text(200, "ok"))
+    }
+
+    /// The cached package with exactly the given name, version and target.
+    fn package(
+        &self,
+        name: &str,
+        version: &Version,
+        target: Option<&str>,
+    ) -> Result<Option<Package>, Error> {
+        Ok(self
+            .cache
+            .list_versions(name)?
+            .into_iter()
+            .find(|package| &package.version == version && package.target.as_deref() == target))
+    }
+
+    fn authorized(&self, request: &Request) -> bool {
+        let expected = match &self.authorization {
+            Some(authorization) => authorization,
+            None => return false,
+        };
+
+        request.headers().iter().any(|header| {
+            header.field.equiv("Authorization")
+                && constant_time_eq(header.value.as_str().as_bytes(), expected.as_bytes())
+        })
+    }
+
+    fn upload_path(&self, name: &str, version: &Version, target: Option<&str>) -> PathBuf {
+        self.staging
+            .join(name)
+            .join(version.to_string())
+            .join(target.unwrap_or("any"))
+    }
+}
+
+/// Describes a cached package the way the registry api lists it.
+fn remote_version(package: &Package) -> RemoteVersion {
+    RemoteVersion {
+        version: package.version.clone(),
+        target: package.target.clone(),
+        signature: package.signature.clone(),
+        description: package.description.clone(),
+        dependencies: package.dependencies.clone(),
+        yanked: package.yanked,
+        artifacts: package
+            .artifacts
+            .iter()
+            .filter_map(|artifact| {
+                Some(RemoteArtifact {
+                    name: artifact.name.clone(),
+                    digest: artifact.digest.clone()?,
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Decodes a percent-encoded query parameter, in which `+` stands for a space.
+fn decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut remaining = value.bytes();
+
+    while let Some(byte) = remaining.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let high = (remaining.next()? as char).to_digit(16)?;
+                let low = (remaining.next()? as char).to_digit(16)?;
+                bytes.push((high * 16 + low) as u8);
+            }
+            byte => bytes.push(byte),
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+/// Compares two secrets in time which only depends on their lengths, so the time taken to reject a guess doesn't
+/// tell how much of it was right.
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .fold(0, |difference, (left, right)| difference | (left ^ right))
+            == 0
+}
+
+fn text(status: u16, body: &str) -> ResponseBox {
+    Response::from_string(body).with_status_code(status).boxed()
+}
+
+fn json<T: serde::Serialize>(value: &T) -> Result<ResponseBox, Error> {
+    let body = serde_json::to_string(value).map_err(io::Error::from)?;
+    let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .expect("static header is valid");
+
+    Ok(Response::from_string(body).with_header(header).boxed())
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+
+    use super::{constant_time_eq, Route, State};
+    use crate::{
+        cache::{self, Cache},
+        registry::RemoteVersion,
+    };
+
+    #[test]
+    fn test_routes() {
+        let version = Version::parse("1.2.0").unwrap();
+
+        assert_eq!(
+            Route::parse("/api/v1/packages/org/foo"),
+            Some(Route::Versions {
+                name: "org/foo".to_string()
+            })
+        );
+        assert_eq!(
+            Route::parse("/api/v1/packages/org/foo/1.2.0/targets/linux-x86_64"),
+            Some(Route::Version {
+                name: "org/foo".to_string(),
+                version: version.clone(),
+                target: Some("linux-x86_64".to_string()),
+            })
+        );
+        assert_eq!(
+            Route::parse("/api/v1/packages/foo/1.2.0/artifacts/lib/libfoo.so"),
+            Some(Route::Artifact {
+                name: "foo".to_string(),
+                version,
+                target: None,
+                artifact: "lib/libfoo.so".to_string(),
+            })
+        );
+
+        assert_eq!(
+            Route::parse("/api/v1/packages/foo/1.2.0/artifacts/../x"),
+            None
+        );
+        assert_eq!(
+            Route::parse("/api/v1/packages/foo/1.2.0/yank"),
+            Some(Route::Yank {
+                name: "foo".to_string(),
+                version: Version::parse("1.2.0").unwrap(),
+            })
+        );
+        assert_eq!(
+            Route::parse("/api/v1/packages/foo/1.2.0/targets/linux-x86_64/yank"),
+            None
+        );
+        assert_eq!(Route::parse("/api/v1/packages/foo/1.2.0/other"), None);
+        assert_eq!(Route::parse("/api/v1/packages/1.2.0"), None);
+        assert_eq!(Route::parse("/index.html"), None);
+
+        assert_eq!(
+            Route::parse("/api/v1/search?q=org%2Flib%2A"),
+            Some(Route::Search {
+                query: "org/lib*".to_string()
+            })
+        );
+        assert_eq!(Route::parse("/api/v1/search?q=%zz"), None);
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"Bearer secret", b"Bearer secret"));
+        assert!(!constant_time_eq(b"Bearer secret", b"Bearer secreT"));
+        assert!(!constant_time_eq(b"Bearer secret", b"Bearer secret2"));
+        assert!(!constant_time_eq(b"", b"Bearer secret"));
+    }
+
+    #[test]
+    fn test_publish_rejects_escaping_artifacts() {
+        let root = std::env::temp_dir().join(format!("orca-serve-test-{}", std::process::id()));
+        let state = State {
+            cache: Cache::new(root.join("cache")).unwrap(),
+            uploads: true,
+            authorization: None,
+            staging: root.join("staging"),
+        };
+
+        let version = Version::parse("1.0.0").unwrap();
+        let staging = state.upload_path("foo", &version, None);
+        std::fs::create_dir_all(&staging).unwrap();
+        // The file the name points at exists, so only the name itself can be what's rejected.
+        std::fs::create_dir_all(staging.join("../../etc")).unwrap();
+        std::fs::write(staging.join("../../etc/passwd"), b"secret").unwrap();
+
+        let remote: RemoteVersion = serde_json::from_str(
+            r#"{ "version": "1.0.0", "artifacts": [{ "name": "../../etc/passwd", "digest": "00" }] }"#,
+        )
+        .unwrap();
+        let response = state.publish("foo", &remote, &staging).unwrap();
+        assert_eq!(response.status_code().0, 400);
+        assert!(state.cache.list_versions("foo").unwrap().is_empty());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_publish_never_replaces_a_version() {
+        let root = std::env::temp_dir().join(format!("orca-serve-publish-{}", std::process::id()));
+        let state = State {
+            cache: Cache::new(root.join("cache")).unwrap(),
+            uploads: true,
+            authorization: None,
+            staging: root.join("staging"),
+        };
+
+        let version = Version::parse("1.0.0").unwrap();
+        let staging = state.upload_path("foo", &version, None);
+        std::fs::create_dir_all(&staging).unwrap();
+        std::fs::write(staging.join("libfoo.so"), b"foo").unwrap();
+        let digest = cache::digest(&staging.join("libfoo.so")).unwrap();
+        let remote = |digest: &str| -> RemoteVersion {
+            serde_json::from_str(&format!(
+                r#"{{ "version": "1.0.0", "artifacts": [{{ "name": "libfoo.so", "digest": "{}" }}] }}"#,
+                digest
+            ))
+            .unwrap()
+        };
+
+        // Artifacts which don't match their digests are rejected before anything is stored.
+        let response = state
+            .publish("foo", &remote(&"0".repeat(64)), &staging)
+            .unwrap();
+        assert_eq!(response.status_code().0, 400);
+        assert!(state.cache.list_versions("foo").unwrap().is_empty());
+
+        let response = state.publish("foo", &remote(&digest), &staging).unwrap();
+        assert_eq!(response.status_code().0, 200);
+
+        // Publishing the same version again is a conflict, and leaves the published one alone.
+        let response = state.publish("foo", &remote(&digest), &staging).unwrap();
+        assert_eq!(response.status_code().0, 409);
+        let published = state.cache.get("foo", &version).unwrap().unwrap();
+        assert_eq!(std::fs::read(&published.artifacts[0].path).unwrap(), b"foo");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,222 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer as _, Verifier as _};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::cache::Package;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to read signing key {0}: {1}")]
+    IO(PathBuf, #[source] std::io::Error),
+    #[error("invalid signing key {0}, expected a hex-encoded ed25519 secret key")]
+    InvalidSigningKey(PathBuf),
+    #[error("invalid trusted key {0}, expected a hex-encoded ed25519 public key")]
+    InvalidTrustedKey(String),
+    #[error("{0} is not signed")]
+    Unsigned(String),
+    #[error("{package} is signed by {key}, which is not a trusted key")]
+    Untrusted { package: String, key: String },
+    #[error("signature of {0} does not match its contents")]
+    Invalid(String),
+}
+
+/// Ed25519 signature of a package, covering its name, version, target and the digests of all its artifacts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Signature {
+    /// Hex-encoded public key of the signer.
+    pub key: String,
+    /// Hex-encoded signature.
+    pub signature: String,
+}
+
+/// Signs packages as they are put into the cache, using the secret key in a keyfile. The keyfile holds
+/// the hex-encoded 32 byte ed25519 secret key, optionally followed by a newline.
+pub struct Signer {
+    keypair: Keypair,
+}
+
+impl Signer {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let contents =
+            std::fs::read_to_string(path).map_err(|error| Error::IO(path.to_path_buf(), error))?;
+
+        let secret = hex::decode(contents.trim())
+            .ok()
+            .and_then(|bytes| SecretKey::from_bytes(&bytes).ok())
+            .ok_or_else(|| Error::InvalidSigningKey(path.to_path_buf()))?;
+        let public = PublicKey::from(&secret);
+
+        Ok(Signer {
+            keypair: Keypair { secret, public },
+        })
+    }
+
+    /// Hex-encoded public key, which others need to trust in order to verify the signatures.
+    pub fn public_key(&self) -> String {
+        hex::encode(self.keypair.public.as_bytes())
+    }
+
+    pub fn sign(&self, package: &Package) -> Signature {
+        Signature {
+            key: self.public_key(),
+            signature: hex::encode(self.keypair.sign(&message(package)).to_bytes()),
+        }
+    }
+}
+
+/// Decides which package signatures are acceptable. Signatures made by a trusted key are always checked against
+/// the package they belong to. Unsigned packages, and packages signed by keys which aren't trusted, are only
+/// accepted if signatures aren't required.
+#[derive(Default)]
+pub struct Verifier {
+    trusted: Vec<PublicKey>,
+    required: bool,
+}
+
+impl Verifier {
+    /// Trusts the given hex-encoded public keys.
+    pub fn new<S: AsRef<str>>(trusted: &[S], required: bool) -> Result<Self, Error> {
+        let trusted = trusted
+            .iter()
+            .map(|key| {
+                hex::decode(key.as_ref())
+                    .ok()
+                    .and_then(|bytes| PublicKey::from_bytes(&bytes).ok())
+                    .ok_or_else(|| Error::InvalidTrustedKey(key.as_ref().to_string()))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Verifier { trusted, required })
+    }
+
+    /// Also trusts the key of `signer`, so packages signed locally are accepted.
+    pub fn trust(&mut self, signer: &Signer) {
+        if !self.trusted.contains(&signer.keypair.public) {
+            self.trusted.push(signer.keypair.public);
+        }
+    }
+
+    pub fn verify(&self, package: &Package) -> Result<(), Error> {
+        let signature = match &package.signature {
+            Some(signature) => signature,
+            None if self.required => return Err(Error::Unsigned(package.to_string())),
+            None => return Ok(()),
+        };
+
+        let key = match self
+            .trusted
+            .iter()
+            .find(|key| hex::encode(key.as_bytes()) == signature.key)
+        {
+            Some(key) => key,
+            None if self.required => {
+                return Err(Error::Untrusted {
+                    package: package.to_string(),
+                    key: signature.key.clone(),
+                })
+            }
+            None => return Ok(()),
+        };
+
+        let bytes =
+            hex::decode(&signature.signature).map_err(|_| Error::Invalid(package.to_string()))?;
+        let parsed = ed25519_dalek::Signature::from_bytes(&bytes)
+            .map_err(|_| Error::Invalid(package.to_string()))?;
+
+        key.verify(&message(package), &parsed)
+            .map_err(|_| Error::Invalid(package.to_string()))
+    }
+}
+
+impl fmt::Debug for Verifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Verifier")
+            .field("trusted", &self.trusted.len())
+            .field("required", &self.required)
+            .finish()
+    }
+}
+
+/// The signed contents of a package. Artifacts only count through their digests, so the message is
+/// the same no matter where the artifacts are stored.
+fn message(package: &Package) -> Vec<u8> {
+    let mut artifacts: Vec<_> = package
+        .artifacts
+        .iter()
+        .map(|artifact| {
+            format!(
+                "{} {}\n",
+                artifact.digest.as_deref().unwrap_or_default(),
+                artifact.name
+            )
+        })
+        .collect();
+    artifacts.sort();
+
+    format!(
+        "orca-signature-v1\n{}\n{}\n{}\n{}",
+        package.name.join("/"),
+        package.version,
+        package.target.as_deref().unwrap_or_default(),
+        artifacts.concat()
+    )
+    .into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use semver::Version;
+
+    use super::{Error, Signer, Verifier};
+    use crate::cache::{Artifact, Package};
+
+    #[test]
+    fn test_sign_and_verify() {
+        let path = std::env::temp_dir().join(format!("orca-signing-test-{}", std::process::id()));
+        std::fs::write(&path, format!("{}\n", "2a".repeat(32))).unwrap();
+        let signer = Signer::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut package = Package {
+            name: vec!["org".to_string(), "foo".to_string()],
+            version: Version::parse("1.0.0").unwrap(),
+            target: None,
+            signature: None,
+            description: None,
+            dependencies: BTreeMap::new(),
+            yanked: false,
+            artifacts: vec![Artifact {
+                name: "libfoo.so".to_string(),
+                path: "objects/ab".into(),
+                digest: Some("ab".repeat(32)),
+            }],
+        };
+
+        assert!(Verifier::default().verify(&package).is_ok());
+        assert!(matches!(
+            Verifier::new::<&str>(&[], true).unwrap().verify(&package),
+            Err(Error::Unsigned(_))
+        ));
+
+        package.signature = Some(signer.sign(&package));
+        assert!(Verifier::default().verify(&package).is_ok());
+        assert!(matches!(
+            Verifier::new::<&str>(&[], true).unwrap().verify(&package),
+            Err(Error::Untrusted { .. })
+        ));
+
+        let verifier = Verifier::new(&[signer.public_key()], true).unwrap();
+        assert!(verifier.verify(&package).is_ok());
+
+        package.artifacts[0].digest = Some("cd".repeat(32));
+        assert!(matches!(verifier.verify(&package), Err(Error::Invalid(_))));
+    }
+}
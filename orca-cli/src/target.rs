@@ -0,0 +1,5 @@
+/// The target orca is running on, such as `linux-x86_64`, which is what gets built and resolved unless
+/// another one is selected using `--target`.
+pub fn host() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
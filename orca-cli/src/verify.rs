@@ -0,0 +1,70 @@
+use clap::Clap;
+use thiserror::Error;
+
+use crate::{
+    cache::{self, cmd::parse_package, Cache},
+    config::Config,
+    output::OutputFormat,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("cache error: {0}")]
+    Cache(#[from] cache::Error),
+    #[error("{0} cache entries failed verification")]
+    Failed(usize),
+}
+
+#[derive(Clap)]
+pub struct VerifyCmd {
+    #[clap(
+        about = "Package to verify, as name or name@version. Verifies the whole cache if left out."
+    )]
+    pub package: Option<String>,
+}
+
+impl VerifyCmd {
+    /// Checks the artifacts, signatures and indices of the cached packages, failing if any of them don't check out.
+    pub(crate) fn execute(&self, config: &Config) -> Result<(), Error> {
+        let cache = Cache::configured(config)?;
+
+        let findings = match &self.package {
+            Some(package) => {
+                let (name, version) = parse_package(package)?;
+                cache.audit(Some(name), version.as_ref())?
+            }
+            None => cache.audit(None, None)?,
+        };
+
+        match config.output {
+            OutputFormat::Text => {
+                for finding in &findings {
+                    match &finding.problem {
+                        None => println!("ok       {}", finding.package),
+                        Some(problem) => println!(
+                            "FAILED   {}: {} ({})",
+                            finding.package,
+                            problem,
+                            finding.path.display()
+                        ),
+                    }
+                }
+            }
+            OutputFormat::Json => {
+                for finding in &findings {
+                    println!(
+                        "{}",
+                        serde_json::to_string(finding).map_err(cache::Error::from)?
+                    );
+                }
+            }
+        }
+
+        let failed = findings.iter().filter(|finding| !finding.is_ok()).count();
+        if failed > 0 {
+            return Err(Error::Failed(failed));
+        }
+
+        Ok(())
+    }
+}
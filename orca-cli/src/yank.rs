@@ -0,0 +1,66 @@
+use clap::Clap;
+use thiserror::Error;
+
+use crate::{
+    cache::{self, cmd::parse_package, Cache},
+    config::Config,
+    registry::{self, Registry},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("cache error: {0}")]
+    Cache(#[from] cache::Error),
+    #[error("registry error: {0}")]
+    Registry(#[from] registry::Error),
+    #[error("{0} has no version, use name@version")]
+    NoVersion(String),
+    #[error("{0} is neither cached nor published to the registry")]
+    NotFound(String),
+}
+
+#[derive(Clap)]
+pub struct YankCmd {
+    #[clap(about = "Version of a package to yank, as name@version.")]
+    pub package: String,
+    #[clap(
+        long,
+        about = "Undo a previous yank, making the version resolvable again."
+    )]
+    pub undo: bool,
+    #[clap(
+        long,
+        about = "Only yank the version in the local cache, not in the registry."
+    )]
+    pub local: bool,
+}
+
+impl YankCmd {
+    /// Yanks the version in the local cache, and in the configured registry unless `--local` is given.
+    /// Yanked versions are skipped when resolving dependencies, unless a lockfile pins them.
+    pub(crate) fn execute(&self, config: &Config) -> Result<(), Error> {
+        let (name, version) = parse_package(&self.package)?;
+        let version = version.ok_or_else(|| Error::NoVersion(self.package.clone()))?;
+        let yanked = !self.undo;
+        let action = if yanked { "yanked" } else { "unyanked" };
+
+        let mut found = false;
+        for package in Cache::configured(config)?.yank(name, &version, yanked)? {
+            println!("{} {} in the cache", action, package);
+            found = true;
+        }
+
+        if let (Some(url), false) = (&config.registry, self.local) {
+            if Registry::configured(url, config)?.yank(name, &version, yanked)? {
+                println!("{} {}@{} in {}", action, name, version, url);
+                found = true;
+            }
+        }
+
+        if found {
+            Ok(())
+        } else {
+            Err(Error::NotFound(self.package.clone()))
+        }
+    }
+}